@@ -0,0 +1,482 @@
+//! A `wmctrl` work-alike built on the `Send*` requests of `ewmh::proto`
+//!
+//! Supports the common subset of `wmctrl`: listing windows (`-l` with `-p`, `-G` and `-x`) and
+//! desktops (`-d`), switching desktops (`-s`), activating (`-a`) and closing (`-c`) windows, and
+//! moving/resizing (`-r WIN -e`), changing the state (`-r WIN -b`) and the desktop (`-r WIN -t`)
+//! of windows. `-n` sets the number of desktops, `-N` (not in `wmctrl`) their names.
+//!
+//! ```sh
+//! cargo run --example wmctrl --features ewmh,icccm -- -l -p -G
+//! cargo run --example wmctrl --features ewmh,icccm -- -r firefox -b add,maximized_vert,maximized_horz
+//! ```
+
+use std::convert::TryFrom;
+use std::process::exit;
+
+use xcb::{Xid, XidNew};
+use xcb_wm::{ewmh, icccm, Connection, WindowInfo};
+
+const USAGE: &str = "\
+usage: wmctrl [options] [actions]
+
+actions:
+    -l                    list windows
+    -d                    list desktops, the current one is marked with `*`
+    -s <DESK>             switch to desktop <DESK>
+    -a <WIN>              activate window <WIN>, switching to its desktop
+    -c <WIN>              close window <WIN>
+    -r <WIN> -e <MVARG>   move/resize <WIN>, <MVARG> is `gravity,x,y,width,height`
+                          (-1 leaves a value unchanged)
+    -r <WIN> -b <STARG>   change the state of <WIN>, <STARG> is
+                          `add|remove|toggle,<PROP1>[,<PROP2>]`, e.g. `add,above`
+    -r <WIN> -t <DESK>    move <WIN> to desktop <DESK>
+    -n <NUM>              set the number of desktops
+    -N <NAMES>            set the names of the desktops, comma separated
+
+options:
+    -i                    <WIN> is a window id (decimal or 0x hex)
+    -x                    <WIN> is matched against WM_CLASS, -l prints it
+    -p                    -l prints the PID of windows
+    -G                    -l prints the geometry of windows
+
+<WIN> is a case-insensitive substring of the title, or `:ACTIVE:` for the active window.";
+
+/// Client messages are sent as a pager, like `wmctrl` does
+const SOURCE_INDICATION: u32 = 2;
+
+/// `CurrentTime`
+const TIMESTAMP: u32 = 0;
+
+enum Action {
+    List,
+    Desktops,
+    SwitchDesktop(u32),
+    Activate(String),
+    Close(String),
+    MoveResize(String, String),
+    ChangeState(String, String),
+    MoveToDesktop(String, u32),
+    NumberOfDesktops(u32),
+    DesktopNames(String),
+}
+
+#[derive(Default)]
+struct Options {
+    by_id: bool,
+    by_class: bool,
+    pid: bool,
+    geometry: bool,
+}
+
+fn parse_args() -> Result<(Action, Options), String> {
+    let mut options = Options::default();
+    let mut action = None;
+    let mut target: Option<String> = None;
+
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        let next = match arg.as_str() {
+            "-i" => {
+                options.by_id = true;
+                None
+            }
+            "-x" => {
+                options.by_class = true;
+                None
+            }
+            "-p" => {
+                options.pid = true;
+                None
+            }
+            "-G" => {
+                options.geometry = true;
+                None
+            }
+            "-l" => Some(Action::List),
+            "-d" => Some(Action::Desktops),
+            "-s" => Some(Action::SwitchDesktop(number(&value(&mut args, "-s")?)?)),
+            "-a" => Some(Action::Activate(value(&mut args, "-a")?)),
+            "-c" => Some(Action::Close(value(&mut args, "-c")?)),
+            "-n" => Some(Action::NumberOfDesktops(number(&value(&mut args, "-n")?)?)),
+            "-N" => Some(Action::DesktopNames(value(&mut args, "-N")?)),
+            "-r" => {
+                target = Some(value(&mut args, "-r")?);
+                None
+            }
+            "-e" | "-b" | "-t" => {
+                let window = target.take().ok_or(format!("{} needs -r <WIN>", arg))?;
+                let arg_value = value(&mut args, &arg)?;
+                Some(match arg.as_str() {
+                    "-e" => Action::MoveResize(window, arg_value),
+                    "-b" => Action::ChangeState(window, arg_value),
+                    _ => Action::MoveToDesktop(window, number(&arg_value)?),
+                })
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                exit(0);
+            }
+            _ => return Err(format!("unknown option {}", arg)),
+        };
+
+        if next.is_some() {
+            if action.is_some() {
+                return Err("only one action at a time".to_owned());
+            }
+            action = next;
+        }
+    }
+
+    if target.is_some() {
+        return Err("-r needs one of -e, -b or -t".to_owned());
+    }
+    let action = action.ok_or("no action given")?;
+    Ok((action, options))
+}
+
+fn value(args: &mut impl Iterator<Item = String>, option: &str) -> Result<String, String> {
+    args.next().ok_or(format!("{} needs a value", option))
+}
+
+fn number(value: &str) -> Result<u32, String> {
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed.map_err(|_| format!("{} is not a number", value))
+}
+
+fn main() {
+    let (action, options) = parse_args().unwrap_or_else(|e| {
+        eprintln!("wmctrl: {}\n\n{}", e, USAGE);
+        exit(2);
+    });
+
+    let (xcb_con, screen) = xcb::Connection::connect(None).unwrap_or_else(|e| {
+        eprintln!("wmctrl: cannot open display: {}", e);
+        exit(1);
+    });
+    let con = Connection::try_connect(&xcb_con, screen).unwrap_or_else(|e| {
+        eprintln!("wmctrl: {}", e);
+        exit(1);
+    });
+
+    if let Err(e) = run(&xcb_con, &con, action, &options) {
+        eprintln!("wmctrl: {}", e);
+        exit(1);
+    }
+}
+
+fn run(
+    xcb_con: &xcb::Connection,
+    con: &Connection,
+    action: Action,
+    options: &Options,
+) -> Result<(), String> {
+    match action {
+        Action::List => list_windows(xcb_con, con, options).map_err(|e| e.to_string()),
+        Action::Desktops => list_desktops(con).map_err(|e| e.to_string()),
+        Action::SwitchDesktop(desktop) => {
+            let request = ewmh::proto::SendCurrentDesktop::new(con.ewmh(), desktop)
+                .map_err(|e| e.to_string())?;
+            con.send_and_check_request(&request)
+                .map_err(|e| e.to_string())
+        }
+        Action::Activate(pattern) => {
+            let window = find_window(con, &pattern, options)?;
+            activate(con, window).map_err(|e| e.to_string())
+        }
+        Action::Close(pattern) => {
+            let window = find_window(con, &pattern, options)?;
+            let request =
+                ewmh::proto::SendCloseWindow::new(con.ewmh(), window, SOURCE_INDICATION, TIMESTAMP)
+                    .map_err(|e| e.to_string())?;
+            con.send_and_check_request(&request)
+                .map_err(|e| e.to_string())
+        }
+        Action::MoveResize(pattern, geometry) => {
+            let window = find_window(con, &pattern, options)?;
+            let request = moveresize(con, window, &geometry)?;
+            con.send_and_check_request(&request)
+                .map_err(|e| e.to_string())
+        }
+        Action::ChangeState(pattern, state) => {
+            let window = find_window(con, &pattern, options)?;
+            let request = change_state(con, window, &state)?;
+            con.send_and_check_request(&request)
+                .map_err(|e| e.to_string())
+        }
+        Action::MoveToDesktop(pattern, desktop) => {
+            let window = find_window(con, &pattern, options)?;
+            let request =
+                ewmh::proto::SendWmDesktop::new(con.ewmh(), window, desktop, SOURCE_INDICATION)
+                    .map_err(|e| e.to_string())?;
+            con.send_and_check_request(&request)
+                .map_err(|e| e.to_string())
+        }
+        Action::NumberOfDesktops(desktops) => {
+            let request = ewmh::proto::SendNumberOfDesktops::new(con.ewmh(), desktops)
+                .map_err(|e| e.to_string())?;
+            con.send_and_check_request(&request)
+                .map_err(|e| e.to_string())
+        }
+        Action::DesktopNames(names) => {
+            let request = ewmh::proto::SetDesktopNames::new(names.split(',').collect());
+            con.send_and_check_request(&request)
+                .map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// `wmctrl -l`: `<id> <desktop> [<pid>] [<x> <y> <width> <height>] [<class>] <host> <title>`
+fn list_windows(
+    xcb_con: &xcb::Connection,
+    con: &Connection,
+    options: &Options,
+) -> xcb_wm::Result<()> {
+    for window in clients(con)? {
+        let info = WindowInfo::fetch(con, window)?;
+        let host = con
+            .wait_for_optional_reply(
+                con.send_request(&icccm::proto::GetWmClientMachine::new(window)),
+            )?
+            .map_or_else(|| "N/A".to_owned(), |reply| reply.name);
+
+        // Sticky windows are on desktop 0xFFFFFFFF, printed as -1
+        let mut line = format!(
+            "{:#010x} {:>2}",
+            window.resource_id(),
+            info.desktop.map_or(-1, |desktop| desktop as i32)
+        );
+        if options.pid {
+            line += &format!(" {:<6}", info.pid.unwrap_or(0));
+        }
+        if options.geometry {
+            let (x, y, width, height) = geometry(xcb_con, con.root(), window)?;
+            line += &format!(" {:<4} {:<4} {:<4} {:<4}", x, y, width, height);
+        }
+        if options.by_class {
+            line += &format!(
+                " {:<20}",
+                format!(
+                    "{}.{}",
+                    info.instance.unwrap_or_default(),
+                    info.class.unwrap_or_default()
+                )
+            );
+        }
+        println!("{} {} {}", line, host, info.title.unwrap_or_default());
+    }
+
+    Ok(())
+}
+
+/// `wmctrl -d`: `<desktop> <*|-> DG: <geometry> VP: <viewport> WA: <workarea> <name>`
+fn list_desktops(con: &Connection) -> xcb_wm::Result<()> {
+    let desktops = con
+        .wait_for_reply(con.send_request(&ewmh::proto::GetNumberOfDesktops))?
+        .desktops;
+    let current = con
+        .wait_for_optional_reply(con.send_request(&ewmh::proto::GetCurrentDesktop))?
+        .map(|reply| reply.desktop);
+    let names = con
+        .wait_for_optional_reply(con.send_request(&ewmh::proto::GetDesktopNames))?
+        .map_or_else(Vec::new, |reply| reply.names);
+    let geometry = con
+        .wait_for_optional_reply(con.send_request(&ewmh::proto::GetDesktopGeometry))?
+        .map_or_else(|| "N/A".to_owned(), |g| format!("{}x{}", g.width, g.height));
+    let viewport = con
+        .wait_for_optional_reply(con.send_request(&ewmh::proto::GetDesktopViewport))?
+        .map_or_else(|| "N/A".to_owned(), |v| format!("{},{}", v.x, v.y));
+    let workarea = con
+        .wait_for_optional_reply(con.send_request(&ewmh::proto::GetWorkarea))?
+        .map_or_else(
+            || "N/A".to_owned(),
+            |w| format!("{},{} {}x{}", w.x, w.y, w.width, w.height),
+        );
+
+    for desktop in 0..desktops {
+        println!(
+            "{:<2} {} DG: {}  VP: {}  WA: {}  {}",
+            desktop,
+            if Some(desktop) == current { '*' } else { '-' },
+            geometry,
+            viewport,
+            workarea,
+            names.get(desktop as usize).map_or("", String::as_str)
+        );
+    }
+
+    Ok(())
+}
+
+/// Switch to the desktop of `window` and activate it, like `wmctrl -a`
+fn activate(con: &Connection, window: xcb::x::Window) -> xcb_wm::Result<()> {
+    let desktop =
+        con.wait_for_optional_reply(con.send_request(&ewmh::proto::GetWmDesktop(window)))?;
+    if let Some(desktop) = desktop.filter(|reply| reply.desktop != u32::MAX) {
+        con.send_and_check_request(&ewmh::proto::SendCurrentDesktop::new(
+            con.ewmh(),
+            desktop.desktop,
+        )?)?;
+    }
+
+    con.send_and_check_request(&ewmh::proto::SendActiveWindow::new(
+        con.ewmh(),
+        window,
+        SOURCE_INDICATION,
+        TIMESTAMP,
+        None,
+    )?)
+}
+
+/// `_NET_MOVERESIZE_WINDOW` from `gravity,x,y,width,height`
+fn moveresize(
+    con: &Connection,
+    window: xcb::x::Window,
+    geometry: &str,
+) -> Result<ewmh::proto::SendMoveresizeWindow, String> {
+    let values = geometry
+        .split(',')
+        .map(|value| value.trim().parse::<i32>())
+        .collect::<Result<Vec<_>, _>>()
+        .ok()
+        .filter(|values| values.len() == 5)
+        .ok_or(format!("invalid geometry {}", geometry))?;
+
+    let gravity = gravity(values[0]).ok_or(format!("invalid gravity {}", values[0]))?;
+    let unless_unchanged = |value: i32| Some(value).filter(|value| *value != -1);
+    let size = |value: i32| {
+        unless_unchanged(value)
+            .map(|size| u32::try_from(size).map_err(|_| format!("invalid size {}", size)))
+            .transpose()
+    };
+
+    ewmh::proto::SendMoveresizeWindow::new(
+        con.ewmh(),
+        window,
+        gravity,
+        SOURCE_INDICATION,
+        unless_unchanged(values[1]),
+        unless_unchanged(values[2]),
+        size(values[3])?,
+        size(values[4])?,
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn gravity(value: i32) -> Option<xcb::x::Gravity> {
+    use xcb::x::Gravity::*;
+
+    let gravity = match value {
+        0 => BitForget,
+        1 => NorthWest,
+        2 => North,
+        3 => NorthEast,
+        4 => West,
+        5 => Center,
+        6 => East,
+        7 => SouthWest,
+        8 => South,
+        9 => SouthEast,
+        10 => Static,
+        _ => return None,
+    };
+    Some(gravity)
+}
+
+/// `_NET_WM_STATE` from `add|remove|toggle,<PROP1>[,<PROP2>]`
+fn change_state(
+    con: &Connection,
+    window: xcb::x::Window,
+    state: &str,
+) -> Result<ewmh::proto::SendWmState, String> {
+    let mut parts = state.split(',');
+
+    let action = match parts.next() {
+        Some("remove") => ewmh::proto::WmStateAction::Remove,
+        Some("add") => ewmh::proto::WmStateAction::Add,
+        Some("toggle") => ewmh::proto::WmStateAction::Toggle,
+        _ => return Err(format!("invalid state change {}", state)),
+    };
+
+    let mut states = [xcb::x::ATOM_NONE; 2];
+    for (slot, name) in states.iter_mut().zip(parts.by_ref()) {
+        let name = format!("_NET_WM_STATE_{}", name.trim().to_uppercase());
+        *slot = con.atom(&name).map_err(|e| e.to_string())?;
+    }
+    if states[0] == xcb::x::ATOM_NONE || parts.next().is_some() {
+        return Err(format!("invalid state change {}", state));
+    }
+
+    ewmh::proto::SendWmState::new(con.ewmh(), window, action, states, SOURCE_INDICATION)
+        .map_err(|e| e.to_string())
+}
+
+fn clients(con: &Connection) -> xcb_wm::Result<Vec<xcb::x::Window>> {
+    Ok(con
+        .wait_for_optional_reply(con.send_request(&ewmh::proto::GetClientList))?
+        .map_or_else(Vec::new, |reply| reply.clients))
+}
+
+/// The first client matching `pattern`
+fn find_window(
+    con: &Connection,
+    pattern: &str,
+    options: &Options,
+) -> Result<xcb::x::Window, String> {
+    if pattern == ":ACTIVE:" {
+        return con
+            .wait_for_reply(con.send_request(&ewmh::proto::GetActiveWindow))
+            .map(|reply| reply.window)
+            .map_err(|e| format!("no active window: {}", e));
+    }
+    if options.by_id {
+        return number(pattern).map(xcb::x::Window::new);
+    }
+
+    let pattern = pattern.to_lowercase();
+    for window in clients(con).map_err(|e| e.to_string())? {
+        let info = WindowInfo::fetch(con, window).map_err(|e| e.to_string())?;
+        let candidates = if options.by_class {
+            vec![info.instance, info.class]
+        } else {
+            vec![info.title]
+        };
+
+        if candidates
+            .into_iter()
+            .flatten()
+            .any(|candidate| candidate.to_lowercase().contains(&pattern))
+        {
+            return Ok(window);
+        }
+    }
+
+    Err(format!("no window matches {}", pattern))
+}
+
+/// Position relative to the root window and size of `window`
+fn geometry(
+    xcb_con: &xcb::Connection,
+    root: xcb::x::Window,
+    window: xcb::x::Window,
+) -> xcb_wm::Result<(i16, i16, u16, u16)> {
+    let geometry = xcb_con.wait_for_reply(xcb_con.send_request(&xcb::x::GetGeometry {
+        drawable: xcb::x::Drawable::Window(window),
+    }))?;
+    let position = xcb_con.wait_for_reply(xcb_con.send_request(&xcb::x::TranslateCoordinates {
+        src_window: window,
+        dst_window: root,
+        src_x: 0,
+        src_y: 0,
+    }))?;
+
+    Ok((
+        position.dst_x(),
+        position.dst_y(),
+        geometry.width(),
+        geometry.height(),
+    ))
+}
@@ -0,0 +1,80 @@
+//! Interned atoms, shared by the `ewmh` and `icccm` connections
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::backend::Backend;
+use crate::{Error, Result};
+
+/// Cache of interned atoms and their names
+///
+/// Atoms are interned on first use ([`AtomCache::intern`]) or ahead of time in one pipelined
+/// round-trip ([`AtomCache::intern_all`]). Reverse lookups via `GetAtomName`
+/// ([`AtomCache::name`]) are cached as well.
+///
+/// Every [`crate::ewmh::Connection`] and [`crate::icccm::Connection`] owns (or shares) a cache.
+/// It is usually accessed through their `atom` and `atom_name` methods.
+#[derive(Debug, Default)]
+pub struct AtomCache {
+    atoms: Mutex<HashMap<String, xcb::x::Atom>>,
+    names: Mutex<HashMap<xcb::x::Atom, String>>,
+}
+
+impl AtomCache {
+    pub fn new() -> AtomCache {
+        AtomCache::default()
+    }
+
+    /// The atom for `name` if it has been interned before
+    pub fn get(&self, name: &str) -> Option<xcb::x::Atom> {
+        self.atoms.lock().unwrap().get(name).copied()
+    }
+
+    /// The atom for `name`, interned on first use
+    pub fn intern(&self, con: &dyn Backend, name: &str) -> Result<xcb::x::Atom> {
+        Ok(self.intern_all(con, &[name])?[0])
+    }
+
+    /// The atoms for `names`, in the same order
+    ///
+    /// Atoms that are not cached yet are interned in one pipelined round-trip.
+    pub fn intern_all(&self, con: &dyn Backend, names: &[&str]) -> Result<Vec<xcb::x::Atom>> {
+        let missing: Vec<&str> = names
+            .iter()
+            .copied()
+            .filter(|name| self.get(name).is_none())
+            .collect();
+
+        if !missing.is_empty() {
+            let atoms = con.intern_atoms(&missing)?;
+            if atoms.len() != missing.len() {
+                return Err(Error::Backend(
+                    format!("interned {} atoms for {} names", atoms.len(), missing.len()).into(),
+                ));
+            }
+
+            for (name, atom) in missing.iter().zip(atoms) {
+                self.insert(name, atom);
+            }
+        }
+
+        Ok(names.iter().map(|name| self.get(name).unwrap()).collect())
+    }
+
+    /// The name of `atom`, looked up via `GetAtomName` on first use
+    pub fn name(&self, con: &dyn Backend, atom: xcb::x::Atom) -> Result<String> {
+        if let Some(name) = self.names.lock().unwrap().get(&atom) {
+            return Ok(name.clone());
+        }
+
+        let name = con.atom_name(atom)?;
+
+        self.insert(&name, atom);
+        Ok(name)
+    }
+
+    fn insert(&self, name: &str, atom: xcb::x::Atom) {
+        self.atoms.lock().unwrap().insert(name.to_owned(), atom);
+        self.names.lock().unwrap().insert(atom, name.to_owned());
+    }
+}
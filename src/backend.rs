@@ -0,0 +1,416 @@
+//! Transport of `ewmh` and `icccm` requests
+//!
+//! The requests of both protocols only describe what to send: a `GetProperty`, a
+//! `ChangeProperty` or a `SendEvent` of a client message. Decoding the replies works on a plain
+//! [`Property`]. Everything that talks to the X server goes through the object safe [`Backend`]
+//! trait, so the same request and reply types work over any X connection library.
+//!
+//! [`Backend`] is implemented for [`xcb::Connection`] and, with the `x11rb` feature, for
+//! `x11rb` connections wrapped in `X11rbBackend`. Another library is supported by implementing
+//! [`Backend`] for its connection type and passing it to
+//! [`crate::ewmh::Connection::try_connect`] or [`crate::icccm::Connection::try_connect`].
+//! Windows, atoms and the request descriptions are the plain data types of [`xcb::x`].
+//!
+//! The `async` API additionally needs to check for a reply without blocking, see
+//! [`PollBackend`]. `X11rbBackend` cannot do that, so it only supports the blocking API.
+
+use std::convert::TryFrom;
+
+use crate::error::Result;
+use crate::property::Property;
+
+#[cfg(feature = "x11rb")]
+mod x11rb_backend;
+
+#[cfg(feature = "x11rb")]
+pub use x11rb_backend::X11rbBackend;
+
+/// Cookie of a checked `GetProperty` request
+#[derive(Debug)]
+pub struct PropertyCookie(Cookie<xcb::x::GetPropertyCookie>);
+
+/// Cookie of an unchecked `GetProperty` request
+#[derive(Debug)]
+pub struct PropertyCookieUnchecked(Cookie<xcb::x::GetPropertyCookieUnchecked>);
+
+/// Cookie of an unchecked request without reply
+#[derive(Debug)]
+pub struct VoidCookie(Cookie<xcb::VoidCookie>);
+
+/// Cookie of a checked request without reply, see [`Backend::check_request`]
+#[derive(Debug)]
+pub struct VoidCookieChecked(Cookie<xcb::VoidCookieChecked>);
+
+/// A sequence number, or the cookie itself if the request was sent by the [`xcb::Connection`]
+/// backend
+///
+/// `xcb` can only rebuild a cookie from a sequence number with `unsafe` code, so its cookies are
+/// kept as they are. A sequence number passed to the `xcb` backend is an error.
+#[derive(Debug)]
+enum Cookie<X> {
+    Sequence(u64),
+    Xcb(X),
+}
+
+macro_rules! backend_cookie {
+    ($($cookie:ident),+) => {
+        $(impl $cookie {
+            /// Wrap the sequence number a [`Backend`] assigned to the request
+            pub fn new(sequence: u64) -> $cookie {
+                $cookie(Cookie::Sequence(sequence))
+            }
+
+            pub fn sequence(&self) -> u64 {
+                match &self.0 {
+                    Cookie::Sequence(sequence) => *sequence,
+                    Cookie::Xcb(cookie) => xcb::Cookie::sequence(cookie),
+                }
+            }
+        })+
+    };
+}
+
+backend_cookie!(
+    PropertyCookie,
+    PropertyCookieUnchecked,
+    VoidCookie,
+    VoidCookieChecked
+);
+
+impl<X> Cookie<X> {
+    fn xcb(&self) -> Result<&X> {
+        match self {
+            Cookie::Xcb(cookie) => Ok(cookie),
+            Cookie::Sequence(_) => Err(not_an_xcb_cookie()),
+        }
+    }
+
+    fn into_xcb(self) -> Result<X> {
+        match self {
+            Cookie::Xcb(cookie) => Ok(cookie),
+            Cookie::Sequence(_) => Err(not_an_xcb_cookie()),
+        }
+    }
+}
+
+fn not_an_xcb_cookie() -> crate::Error {
+    crate::Error::Backend("the cookie was not returned by an xcb::Connection".into())
+}
+
+/// Transport independent `ChangeProperty` request
+#[derive(Debug)]
+pub struct PropertyChange<'a> {
+    pub mode: xcb::x::PropMode,
+    pub window: xcb::x::Window,
+    pub property: xcb::x::Atom,
+    pub r#type: xcb::x::Atom,
+
+    /// 8, 16 or 32
+    pub format: u8,
+
+    /// The elements of the new value in native byte order
+    pub data: &'a [u8],
+}
+
+impl<'a, P: xcb::x::PropEl> From<&xcb::x::ChangeProperty<'a, P>> for PropertyChange<'a> {
+    fn from(request: &xcb::x::ChangeProperty<'a, P>) -> PropertyChange<'a> {
+        // SAFETY: Every byte of the plain integers and resource ids that are `PropEl` is
+        // initialized. This is how `xcb` serializes the data, too.
+        let data = unsafe {
+            std::slice::from_raw_parts(
+                request.data.as_ptr() as *const u8,
+                std::mem::size_of_val(request.data),
+            )
+        };
+
+        PropertyChange {
+            mode: request.mode,
+            window: request.window,
+            property: request.property,
+            r#type: request.r#type,
+            format: P::FORMAT,
+            data,
+        }
+    }
+}
+
+/// Connection to an X server that `ewmh` and `icccm` requests are sent over
+///
+/// Like [`xcb::Connection`], sending a request only queues it. Replies and errors are retrieved
+/// with the returned cookie. Implementations report their own errors as
+/// [`crate::Error::Backend`].
+pub trait Backend {
+    /// Root window of `screen`, [`crate::Error::NoSuchScreen`] if there is no such screen
+    fn screen_root(&self, screen: i32) -> Result<xcb::x::Window>;
+
+    /// Intern all `names` (in one round-trip if possible)
+    fn intern_atoms(&self, names: &[&str]) -> Result<Vec<xcb::x::Atom>>;
+
+    /// The name of `atom`
+    fn atom_name(&self, atom: xcb::x::Atom) -> Result<String>;
+
+    fn get_property(&self, request: &xcb::x::GetProperty) -> PropertyCookie;
+
+    fn get_property_unchecked(&self, request: &xcb::x::GetProperty) -> PropertyCookieUnchecked;
+
+    /// Wait for the property requested by [`Backend::get_property`]
+    fn wait_for_property(&self, cookie: PropertyCookie) -> Result<Property>;
+
+    /// Wait for the property requested by [`Backend::get_property_unchecked`]
+    ///
+    /// `None` if the request failed. The error is delivered to the event loop instead.
+    fn wait_for_property_unchecked(
+        &self,
+        cookie: PropertyCookieUnchecked,
+    ) -> Result<Option<Property>>;
+
+    fn change_property(&self, request: &PropertyChange) -> VoidCookie;
+
+    fn change_property_checked(&self, request: &PropertyChange) -> VoidCookieChecked;
+
+    fn send_client_message(
+        &self,
+        request: &xcb::x::SendEvent<xcb::x::ClientMessageEvent>,
+    ) -> VoidCookie;
+
+    fn send_client_message_checked(
+        &self,
+        request: &xcb::x::SendEvent<xcb::x::ClientMessageEvent>,
+    ) -> VoidCookieChecked;
+
+    /// Wait until the server processed the request of `cookie` and report its error, if any
+    fn check_request(&self, cookie: VoidCookieChecked) -> Result<()>;
+
+    /// The events this client selected on `window`
+    fn event_mask(&self, window: xcb::x::Window) -> Result<xcb::x::EventMask>;
+
+    /// Replace the events this client selected on `window` with `mask`
+    fn select_events(&self, window: xcb::x::Window, mask: xcb::x::EventMask) -> Result<()>;
+
+    /// Send all queued requests to the server
+    fn flush(&self) -> Result<()>;
+}
+
+/// A [`Backend`] that can check whether a reply has arrived without blocking
+///
+/// The `async` connections are built from a `PollBackend`, so a backend that cannot poll is
+/// rejected at compile time.
+pub trait PollBackend: Backend {
+    /// The property requested by [`Backend::get_property`] if it has arrived already
+    fn poll_for_property(&self, cookie: &PropertyCookie) -> Option<Result<Property>>;
+}
+
+/// Request descriptions that a [`Backend`] can send
+///
+/// Implemented for the three kinds of requests `ewmh` and `icccm` consist of.
+pub trait BackendRequest {
+    type Cookie;
+
+    fn send(&self, backend: &dyn Backend) -> Self::Cookie;
+}
+
+/// Request descriptions without reply that a [`Backend`] can send checked
+pub trait BackendVoidRequest: BackendRequest<Cookie = VoidCookie> {
+    fn send_checked(&self, backend: &dyn Backend) -> VoidCookieChecked;
+}
+
+impl BackendRequest for xcb::x::GetProperty {
+    type Cookie = PropertyCookie;
+
+    fn send(&self, backend: &dyn Backend) -> PropertyCookie {
+        backend.get_property(self)
+    }
+}
+
+impl<P: xcb::x::PropEl> BackendRequest for xcb::x::ChangeProperty<'_, P> {
+    type Cookie = VoidCookie;
+
+    fn send(&self, backend: &dyn Backend) -> VoidCookie {
+        backend.change_property(&PropertyChange::from(self))
+    }
+}
+
+impl<P: xcb::x::PropEl> BackendVoidRequest for xcb::x::ChangeProperty<'_, P> {
+    fn send_checked(&self, backend: &dyn Backend) -> VoidCookieChecked {
+        backend.change_property_checked(&PropertyChange::from(self))
+    }
+}
+
+impl BackendRequest for xcb::x::SendEvent<'_, xcb::x::ClientMessageEvent> {
+    type Cookie = VoidCookie;
+
+    fn send(&self, backend: &dyn Backend) -> VoidCookie {
+        backend.send_client_message(self)
+    }
+}
+
+impl BackendVoidRequest for xcb::x::SendEvent<'_, xcb::x::ClientMessageEvent> {
+    fn send_checked(&self, backend: &dyn Backend) -> VoidCookieChecked {
+        backend.send_client_message_checked(self)
+    }
+}
+
+/// `ChangeProperty` with the data of `request` as elements of its format
+fn with_xcb_change_property<T>(
+    request: &PropertyChange,
+    f: impl FnOnce(&dyn XcbChangeProperty) -> T,
+) -> T {
+    fn request_of<'a, P: xcb::x::PropEl>(
+        request: &PropertyChange,
+        data: &'a [P],
+    ) -> xcb::x::ChangeProperty<'a, P> {
+        xcb::x::ChangeProperty {
+            mode: request.mode,
+            window: request.window,
+            property: request.property,
+            r#type: request.r#type,
+            data,
+        }
+    }
+
+    match request.format {
+        16 => {
+            let data: Vec<u16> = request
+                .data
+                .chunks_exact(2)
+                .map(|b| u16::from_ne_bytes([b[0], b[1]]))
+                .collect();
+            f(&request_of(request, &data))
+        }
+        32 => {
+            let data: Vec<u32> = request
+                .data
+                .chunks_exact(4)
+                .map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+                .collect();
+            f(&request_of(request, &data))
+        }
+        _ => f(&request_of(request, request.data)),
+    }
+}
+
+/// `ChangeProperty` of any format, sent by the [`xcb::Connection`] backend
+trait XcbChangeProperty {
+    fn send(&self, con: &xcb::Connection) -> xcb::VoidCookie;
+    fn send_checked(&self, con: &xcb::Connection) -> xcb::VoidCookieChecked;
+}
+
+impl<P: xcb::x::PropEl> XcbChangeProperty for xcb::x::ChangeProperty<'_, P> {
+    fn send(&self, con: &xcb::Connection) -> xcb::VoidCookie {
+        con.send_request(self)
+    }
+
+    fn send_checked(&self, con: &xcb::Connection) -> xcb::VoidCookieChecked {
+        con.send_request_checked(self)
+    }
+}
+
+impl Backend for xcb::Connection {
+    fn screen_root(&self, screen: i32) -> Result<xcb::x::Window> {
+        usize::try_from(screen)
+            .ok()
+            .and_then(|n| self.get_setup().roots().nth(n))
+            .map(|s| s.root())
+            .ok_or(crate::Error::NoSuchScreen(screen))
+    }
+
+    fn intern_atoms(&self, names: &[&str]) -> Result<Vec<xcb::x::Atom>> {
+        let cookies: Vec<xcb::x::InternAtomCookie> = names
+            .iter()
+            .map(|name| {
+                self.send_request(&xcb::x::InternAtom {
+                    only_if_exists: false,
+                    name: name.as_bytes(),
+                })
+            })
+            .collect();
+
+        cookies
+            .into_iter()
+            .map(|cookie| Ok(self.wait_for_reply(cookie)?.atom()))
+            .collect()
+    }
+
+    fn atom_name(&self, atom: xcb::x::Atom) -> Result<String> {
+        let cookie = self.send_request(&xcb::x::GetAtomName { atom });
+        Ok(self.wait_for_reply(cookie)?.name().to_utf8().into_owned())
+    }
+
+    fn get_property(&self, request: &xcb::x::GetProperty) -> PropertyCookie {
+        PropertyCookie(Cookie::Xcb(self.send_request(request)))
+    }
+
+    fn get_property_unchecked(&self, request: &xcb::x::GetProperty) -> PropertyCookieUnchecked {
+        PropertyCookieUnchecked(Cookie::Xcb(self.send_request_unchecked(request)))
+    }
+
+    fn wait_for_property(&self, cookie: PropertyCookie) -> Result<Property> {
+        Ok(self.wait_for_reply(cookie.0.into_xcb()?)?.into())
+    }
+
+    fn wait_for_property_unchecked(
+        &self,
+        cookie: PropertyCookieUnchecked,
+    ) -> Result<Option<Property>> {
+        Ok(self
+            .wait_for_reply_unchecked(cookie.0.into_xcb()?)?
+            .map(Property::from))
+    }
+
+    fn change_property(&self, request: &PropertyChange) -> VoidCookie {
+        VoidCookie(Cookie::Xcb(with_xcb_change_property(request, |r| {
+            r.send(self)
+        })))
+    }
+
+    fn change_property_checked(&self, request: &PropertyChange) -> VoidCookieChecked {
+        VoidCookieChecked(Cookie::Xcb(with_xcb_change_property(request, |r| {
+            r.send_checked(self)
+        })))
+    }
+
+    fn send_client_message(
+        &self,
+        request: &xcb::x::SendEvent<xcb::x::ClientMessageEvent>,
+    ) -> VoidCookie {
+        VoidCookie(Cookie::Xcb(self.send_request(request)))
+    }
+
+    fn send_client_message_checked(
+        &self,
+        request: &xcb::x::SendEvent<xcb::x::ClientMessageEvent>,
+    ) -> VoidCookieChecked {
+        VoidCookieChecked(Cookie::Xcb(self.send_request_checked(request)))
+    }
+
+    fn check_request(&self, cookie: VoidCookieChecked) -> Result<()> {
+        Ok(xcb::Connection::check_request(self, cookie.0.into_xcb()?)?)
+    }
+
+    fn event_mask(&self, window: xcb::x::Window) -> Result<xcb::x::EventMask> {
+        let cookie = self.send_request(&xcb::x::GetWindowAttributes { window });
+        Ok(self.wait_for_reply(cookie)?.your_event_mask())
+    }
+
+    fn select_events(&self, window: xcb::x::Window, mask: xcb::x::EventMask) -> Result<()> {
+        Ok(
+            self.send_and_check_request(&xcb::x::ChangeWindowAttributes {
+                window,
+                value_list: &[xcb::x::Cw::EventMask(mask)],
+            })?,
+        )
+    }
+
+    fn flush(&self) -> Result<()> {
+        Ok(xcb::Connection::flush(self)?)
+    }
+}
+
+impl PollBackend for xcb::Connection {
+    fn poll_for_property(&self, cookie: &PropertyCookie) -> Option<Result<Property>> {
+        match cookie.0.xcb() {
+            Ok(cookie) => self.poll_for_reply(cookie).map(|reply| Ok(reply?.into())),
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
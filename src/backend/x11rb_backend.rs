@@ -0,0 +1,305 @@
+//! [`Backend`] over an `x11rb` connection
+//!
+//! `x11rb` identifies requests by their sequence numbers, so the cookies of this backend are
+//! plain [`PropertyCookie::new`] and friends. Requests that could not be sent get a sequence
+//! number from the top of the range that no real request has, their error is reported when the
+//! cookie is waited for.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Mutex;
+
+use ::x11rb::connection::Connection;
+use ::x11rb::cookie::{Cookie, VoidCookie as X11rbVoidCookie};
+use ::x11rb::errors::ConnectionError;
+use ::x11rb::protocol::xproto::{self, ConnectionExt as _};
+use ::x11rb::x11_utils::TryParse;
+use xcb::{Xid, XidNew};
+
+use super::{
+    Backend, PropertyChange, PropertyCookie, PropertyCookieUnchecked, VoidCookie, VoidCookieChecked,
+};
+use crate::error::{Error, Result};
+use crate::property::Property;
+
+/// Any `x11rb` connection, e.g. a `RustConnection` or a reference to one
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let (x11rb_con, screen) = x11rb::connect(None)?;
+/// let backend = xcb_wm::backend::X11rbBackend::new(&x11rb_con);
+/// let ewmh_con = xcb_wm::ewmh::Connection::try_connect(&backend, screen as i32)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// `x11rb` cannot poll for a reply, so this is no [`super::PollBackend`] and the `async` API is
+/// not available over it.
+pub struct X11rbBackend<C> {
+    con: C,
+    failed: Mutex<Failed>,
+}
+
+/// Requests that could not be sent, by the sequence number handed out instead
+#[derive(Default)]
+struct Failed {
+    errors: HashMap<u64, ConnectionError>,
+    next: u64,
+}
+
+impl<C: Connection> X11rbBackend<C> {
+    pub fn new(con: C) -> X11rbBackend<C> {
+        X11rbBackend {
+            con,
+            failed: Mutex::new(Failed::default()),
+        }
+    }
+
+    pub fn connection(&self) -> &C {
+        &self.con
+    }
+
+    /// The sequence number of a sent request, or one that remembers why it was not sent
+    fn sequence<T>(
+        &self,
+        sent: std::result::Result<T, ConnectionError>,
+        sequence: impl FnOnce(T) -> u64,
+    ) -> u64 {
+        match sent {
+            Ok(cookie) => sequence(cookie),
+            Err(e) => {
+                let mut failed = self.failed.lock().unwrap();
+                failed.next += 1;
+                let sequence = u64::MAX - failed.next;
+                failed.errors.insert(sequence, e);
+                sequence
+            }
+        }
+    }
+
+    fn reply_sequence<R: TryParse>(
+        &self,
+        sent: std::result::Result<Cookie<'_, C, R>, ConnectionError>,
+    ) -> u64 {
+        self.sequence(sent, |cookie| {
+            let sequence = cookie.sequence_number();
+            // The reply is waited for by its sequence number, dropping the cookie discards it
+            std::mem::forget(cookie);
+            sequence
+        })
+    }
+
+    fn void_sequence(
+        &self,
+        sent: std::result::Result<X11rbVoidCookie<'_, C>, ConnectionError>,
+        checked: bool,
+    ) -> u64 {
+        self.sequence(sent, |cookie| {
+            let sequence = cookie.sequence_number();
+            // Dropping the cookie sends the error of an unchecked request to the event loop
+            if checked {
+                std::mem::forget(cookie);
+            }
+            sequence
+        })
+    }
+
+    /// The error of a request that could not be sent
+    fn send_error(&self, sequence: u64) -> Result<()> {
+        match self.failed.lock().unwrap().errors.remove(&sequence) {
+            Some(e) => Err(backend_error(e)),
+            None => Ok(()),
+        }
+    }
+
+    fn property(&self, sequence: u64) -> Result<Property> {
+        self.send_error(sequence)?;
+        let reply = self
+            .con
+            .wait_for_reply_or_error(sequence)
+            .map_err(backend_error)?;
+        parse_property(reply.as_ref())
+    }
+}
+
+fn backend_error(e: impl std::error::Error + Send + Sync + 'static) -> Error {
+    Error::Backend(Box::new(e))
+}
+
+fn parse_property(reply: &[u8]) -> Result<Property> {
+    let (reply, _) = xproto::GetPropertyReply::try_parse(reply).map_err(backend_error)?;
+    Ok(reply.into())
+}
+
+impl<C: Connection> Backend for X11rbBackend<C> {
+    fn screen_root(&self, screen: i32) -> Result<xcb::x::Window> {
+        usize::try_from(screen)
+            .ok()
+            .and_then(|n| self.con.setup().roots.get(n))
+            .map(|s| xcb::x::Window::new(s.root))
+            .ok_or(Error::NoSuchScreen(screen))
+    }
+
+    fn intern_atoms(&self, names: &[&str]) -> Result<Vec<xcb::x::Atom>> {
+        let cookies = names
+            .iter()
+            .map(|name| self.con.intern_atom(false, name.as_bytes()))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(backend_error)?;
+
+        cookies
+            .into_iter()
+            .map(|cookie| {
+                let reply = cookie.reply().map_err(backend_error)?;
+                Ok(xcb::x::Atom::new(reply.atom))
+            })
+            .collect()
+    }
+
+    fn atom_name(&self, atom: xcb::x::Atom) -> Result<String> {
+        let reply = self
+            .con
+            .get_atom_name(atom.resource_id())
+            .map_err(backend_error)?
+            .reply()
+            .map_err(backend_error)?;
+        Ok(String::from_utf8_lossy(&reply.name).into_owned())
+    }
+
+    fn get_property(&self, request: &xcb::x::GetProperty) -> PropertyCookie {
+        PropertyCookie::new(self.reply_sequence(self.con.get_property(
+            request.delete,
+            request.window.resource_id(),
+            request.property.resource_id(),
+            request.r#type.resource_id(),
+            request.long_offset,
+            request.long_length,
+        )))
+    }
+
+    fn get_property_unchecked(&self, request: &xcb::x::GetProperty) -> PropertyCookieUnchecked {
+        PropertyCookieUnchecked::new(self.get_property(request).sequence())
+    }
+
+    fn wait_for_property(&self, cookie: PropertyCookie) -> Result<Property> {
+        self.property(cookie.sequence())
+    }
+
+    fn wait_for_property_unchecked(
+        &self,
+        cookie: PropertyCookieUnchecked,
+    ) -> Result<Option<Property>> {
+        self.send_error(cookie.sequence())?;
+        match self
+            .con
+            .wait_for_reply(cookie.sequence())
+            .map_err(backend_error)?
+        {
+            Some(reply) => Ok(Some(parse_property(reply.as_ref())?)),
+            None => Ok(None),
+        }
+    }
+
+    fn change_property(&self, request: &PropertyChange) -> VoidCookie {
+        VoidCookie::new(self.void_sequence(send_change_property(&self.con, request), false))
+    }
+
+    fn change_property_checked(&self, request: &PropertyChange) -> VoidCookieChecked {
+        VoidCookieChecked::new(self.void_sequence(send_change_property(&self.con, request), true))
+    }
+
+    fn send_client_message(
+        &self,
+        request: &xcb::x::SendEvent<xcb::x::ClientMessageEvent>,
+    ) -> VoidCookie {
+        VoidCookie::new(self.void_sequence(send_client_message(&self.con, request), false))
+    }
+
+    fn send_client_message_checked(
+        &self,
+        request: &xcb::x::SendEvent<xcb::x::ClientMessageEvent>,
+    ) -> VoidCookieChecked {
+        VoidCookieChecked::new(self.void_sequence(send_client_message(&self.con, request), true))
+    }
+
+    fn check_request(&self, cookie: VoidCookieChecked) -> Result<()> {
+        self.send_error(cookie.sequence())?;
+        self.con
+            .check_for_error(cookie.sequence())
+            .map_err(backend_error)
+    }
+
+    fn event_mask(&self, window: xcb::x::Window) -> Result<xcb::x::EventMask> {
+        let reply = self
+            .con
+            .get_window_attributes(window.resource_id())
+            .map_err(backend_error)?
+            .reply()
+            .map_err(backend_error)?;
+        Ok(xcb::x::EventMask::from_bits_truncate(
+            reply.your_event_mask.into(),
+        ))
+    }
+
+    fn select_events(&self, window: xcb::x::Window, mask: xcb::x::EventMask) -> Result<()> {
+        self.con
+            .change_window_attributes(
+                window.resource_id(),
+                &xproto::ChangeWindowAttributesAux::new()
+                    .event_mask(xproto::EventMask::from(mask.bits())),
+            )
+            .map_err(backend_error)?
+            .check()
+            .map_err(backend_error)
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.con.flush().map_err(backend_error)
+    }
+}
+
+fn send_change_property<'c, C: Connection>(
+    con: &'c C,
+    request: &PropertyChange,
+) -> std::result::Result<X11rbVoidCookie<'c, C>, ConnectionError> {
+    let len = request.data.len() / usize::from(request.format / 8).max(1);
+    con.change_property(
+        xproto::PropMode::from(request.mode as u8),
+        request.window.resource_id(),
+        request.property.resource_id(),
+        request.r#type.resource_id(),
+        request.format,
+        len as u32,
+        request.data,
+    )
+}
+
+fn send_client_message<'c, C: Connection>(
+    con: &'c C,
+    request: &xcb::x::SendEvent<xcb::x::ClientMessageEvent>,
+) -> std::result::Result<X11rbVoidCookie<'c, C>, ConnectionError> {
+    let destination = match request.destination {
+        xcb::x::SendEventDest::PointerWindow => xproto::SendEventDest::POINTER_WINDOW.into(),
+        xcb::x::SendEventDest::ItemFocus => xproto::SendEventDest::ITEM_FOCUS.into(),
+        xcb::x::SendEventDest::Window(window) => window.resource_id(),
+    };
+
+    let event = request.event;
+    let data = match event.data() {
+        xcb::x::ClientMessageData::Data8(data) => xproto::ClientMessageData::from(data),
+        xcb::x::ClientMessageData::Data16(data) => xproto::ClientMessageData::from(data),
+        xcb::x::ClientMessageData::Data32(data) => xproto::ClientMessageData::from(data),
+    };
+
+    con.send_event(
+        request.propagate,
+        destination,
+        xproto::EventMask::from(request.event_mask.bits()),
+        xproto::ClientMessageEvent::new(
+            event.format(),
+            event.window().resource_id(),
+            event.r#type().resource_id(),
+            data,
+        ),
+    )
+}
@@ -0,0 +1,95 @@
+//! Pipelined batches of `ewmh` and `icccm` `GetProperty` requests
+//!
+//! Works like [`crate::ewmh::EwmhBatch`] but a tuple may mix requests of both protocols, e.g. to
+//! fetch everything needed for a taskbar entry in one round-trip:
+//!
+//! ```ignore
+//! let (name, state, class, hints) = con.wait_for_batch(con.send_batch(&(
+//!     ewmh::proto::GetWmName(window),
+//!     ewmh::proto::GetWmState(window),
+//!     icccm::proto::GetWmClass::new(window),
+//!     icccm::proto::GetWmHints::new(window),
+//! )));
+//! ```
+//!
+//! The marker parameter has one [`crate::traits::Ewmh`] or [`crate::traits::Icccm`] marker per
+//! request and is inferred.
+
+use crate::connection::Connection;
+use crate::traits::{WmPropertyCookieChecked, WmRequest};
+
+/// Requests that can be sent by [`Connection::send_batch`]
+pub trait WmBatch<'a, M> {
+    type Cookies;
+
+    fn send(&'a self, con: &Connection) -> Self::Cookies;
+}
+
+/// Cookies that can be retrieved by [`Connection::wait_for_batch`]
+pub trait WmBatchCookies<M> {
+    type Replies;
+
+    fn wait(self, con: &Connection) -> Self::Replies;
+}
+
+impl<'a, R, M> WmBatch<'a, M> for [R]
+where
+    R: WmRequest<'a, M>,
+    R::Cookie: WmPropertyCookieChecked<M>,
+{
+    type Cookies = Vec<R::Cookie>;
+
+    fn send(&'a self, con: &Connection) -> Self::Cookies {
+        self.iter().map(|request| request.send(con)).collect()
+    }
+}
+
+impl<C, M> WmBatchCookies<M> for Vec<C>
+where
+    C: WmPropertyCookieChecked<M>,
+{
+    type Replies = Vec<crate::Result<C::Reply>>;
+
+    fn wait(self, con: &Connection) -> Self::Replies {
+        self.into_iter().map(|cookie| cookie.wait(con)).collect()
+    }
+}
+
+macro_rules! wm_batch_tuple {
+    ($($request:ident $cookie:ident $marker:ident $idx:tt),+) => {
+        impl<'a, $($request, $marker),+> WmBatch<'a, ($($marker,)+)> for ($($request,)+)
+        where
+            $($request: WmRequest<'a, $marker>, $request::Cookie: WmPropertyCookieChecked<$marker>,)+
+        {
+            type Cookies = ($($request::Cookie,)+);
+
+            fn send(&'a self, con: &Connection) -> Self::Cookies {
+                ($(self.$idx.send(con),)+)
+            }
+        }
+
+        impl<$($cookie, $marker),+> WmBatchCookies<($($marker,)+)> for ($($cookie,)+)
+        where
+            $($cookie: WmPropertyCookieChecked<$marker>,)+
+        {
+            type Replies = ($(crate::Result<$cookie::Reply>,)+);
+
+            fn wait(self, con: &Connection) -> Self::Replies {
+                ($(self.$idx.wait(con),)+)
+            }
+        }
+    };
+}
+
+wm_batch_tuple!(A CA MA 0);
+wm_batch_tuple!(A CA MA 0, B CB MB 1);
+wm_batch_tuple!(A CA MA 0, B CB MB 1, C CC MC 2);
+wm_batch_tuple!(A CA MA 0, B CB MB 1, C CC MC 2, D CD MD 3);
+wm_batch_tuple!(A CA MA 0, B CB MB 1, C CC MC 2, D CD MD 3, E CE ME 4);
+wm_batch_tuple!(A CA MA 0, B CB MB 1, C CC MC 2, D CD MD 3, E CE ME 4, F CF MF 5);
+wm_batch_tuple!(A CA MA 0, B CB MB 1, C CC MC 2, D CD MD 3, E CE ME 4, F CF MF 5, G CG MG 6);
+wm_batch_tuple!(A CA MA 0, B CB MB 1, C CC MC 2, D CD MD 3, E CE ME 4, F CF MF 5, G CG MG 6, H CH MH 7);
+wm_batch_tuple!(A CA MA 0, B CB MB 1, C CC MC 2, D CD MD 3, E CE ME 4, F CF MF 5, G CG MG 6, H CH MH 7, I CI MI 8);
+wm_batch_tuple!(A CA MA 0, B CB MB 1, C CC MC 2, D CD MD 3, E CE ME 4, F CF MF 5, G CG MG 6, H CH MH 7, I CI MI 8, J CJ MJ 9);
+wm_batch_tuple!(A CA MA 0, B CB MB 1, C CC MC 2, D CD MD 3, E CE ME 4, F CF MF 5, G CG MG 6, H CH MH 7, I CI MI 8, J CJ MJ 9, K CK MK 10);
+wm_batch_tuple!(A CA MA 0, B CB MB 1, C CC MC 2, D CD MD 3, E CE ME 4, F CF MF 5, G CG MG 6, H CH MH 7, I CI MI 8, J CJ MJ 9, K CK MK 10, L CL ML 11);
@@ -0,0 +1,113 @@
+//! `xcb-wm-check`: check the `ewmh` compliance of the running window manager
+//!
+//! Maps a test window, runs [`ewmh::compliance::Checker`] and prints the report. Exits with 1 if a
+//! check failed.
+//!
+//! Built with the `ewmh` feature: `cargo run --features ewmh --bin xcb-wm-check`
+
+use std::process::exit;
+use std::time::Duration;
+
+use xcb_wm::ewmh::compliance::Checker;
+use xcb_wm::{ewmh, Result};
+
+const USAGE: &str = "\
+usage: xcb-wm-check [options]
+
+    -display <display>  X server to connect to
+    -timeout <ms>       how long to wait for the window manager (default 2000)
+    -no-window          only check the root properties, do not map a test window
+    -help               print this message";
+
+const TEST_WINDOW_NAME: &str = "xcb-wm-check";
+
+struct Options {
+    display: Option<String>,
+    timeout: Duration,
+    window: bool,
+}
+
+fn parse_args() -> std::result::Result<Options, String> {
+    let mut options = Options {
+        display: None,
+        timeout: Duration::from_secs(2),
+        window: true,
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-display" => options.display = Some(args.next().ok_or("-display needs a value")?),
+            "-timeout" => {
+                let ms = args.next().ok_or("-timeout needs a value")?;
+                let ms = ms.parse().map_err(|_| format!("invalid timeout {}", ms))?;
+                options.timeout = Duration::from_millis(ms);
+            }
+            "-no-window" => options.window = false,
+            "-help" | "--help" => {
+                println!("{}", USAGE);
+                exit(0);
+            }
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn main() {
+    let options = parse_args().unwrap_or_else(|e| {
+        eprintln!("xcb-wm-check: {}\n\n{}", e, USAGE);
+        exit(2);
+    });
+
+    match run(&options) {
+        Ok(true) => {}
+        Ok(false) => exit(1),
+        Err(e) => {
+            eprintln!("xcb-wm-check: {}", e);
+            exit(1);
+        }
+    }
+}
+
+/// Whether all checks passed
+fn run(options: &Options) -> Result<bool> {
+    let (xcb_con, screen) = xcb::Connection::connect(options.display.as_deref())?;
+    let con = ewmh::Connection::try_connect(&xcb_con, screen)?;
+    let checker = Checker::new(&con).timeout(options.timeout);
+
+    let report = if options.window {
+        let window = create_test_window(&xcb_con, &con)?;
+        let report = checker.check(Some(window));
+        xcb_con.send_and_check_request(&xcb::x::DestroyWindow { window })?;
+        report?
+    } else {
+        checker.check(None)?
+    };
+
+    println!("{}", report);
+    Ok(report.passed())
+}
+
+/// Create and map a small named top-level window
+fn create_test_window(xcb_con: &xcb::Connection, con: &ewmh::Connection) -> Result<xcb::x::Window> {
+    let window = xcb_con.generate_id();
+    xcb_con.send_and_check_request(&xcb::x::CreateWindow {
+        depth: xcb::x::COPY_FROM_PARENT as u8,
+        wid: window,
+        parent: con.root(),
+        x: 0,
+        y: 0,
+        width: 100,
+        height: 100,
+        border_width: 0,
+        class: xcb::x::WindowClass::InputOutput,
+        visual: xcb::x::COPY_FROM_PARENT,
+        value_list: &[],
+    })?;
+    con.send_and_check_request(&ewmh::proto::SetWmName::new(window, TEST_WINDOW_NAME))?;
+    xcb_con.send_and_check_request(&xcb::x::MapWindow { window })?;
+
+    Ok(window)
+}
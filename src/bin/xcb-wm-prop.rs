@@ -0,0 +1,346 @@
+//! `xcb-wm-prop`: print the `ewmh` and `icccm` properties of a window, like `xprop`
+//!
+//! Every property is fetched with its request from `ewmh::proto` or `icccm::proto` and printed
+//! from the decoded reply. Properties that fail to decode are printed with the error, which makes
+//! this a quick check of the decoders against real clients and window managers.
+//!
+//! Built with the `prop` feature: `cargo run --features prop --bin xcb-wm-prop -- -root`
+
+use std::process::exit;
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use xcb::{Xid, XidNew};
+use xcb_wm::serialize::AtomNamed;
+use xcb_wm::{ewmh, icccm, Connection, Error, Result};
+
+const USAGE: &str = "\
+usage: xcb-wm-prop [options]
+
+    -display <display>  X server to connect to
+    -root               print the properties of the root window (default)
+    -id <id>            print the properties of window <id>
+    -json               print JSON instead of text
+    -spy                keep printing properties as they change
+    -help               print this message";
+
+/// Whether a property is read from the root window or from any window
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Root,
+    Client,
+}
+
+/// How the text form of a reply is printed
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Plain,
+    Windows,
+    Icons,
+}
+
+/// A decoded property
+struct Dump {
+    format: Format,
+    value: Value,
+}
+
+macro_rules! properties {
+    ($($name:literal $scope:ident $format:ident, |$window:ident| $request:expr;)+) => {
+        const PROPERTIES: &[(&str, Scope)] = &[$(($name, Scope::$scope),)+];
+
+        /// Fetch and decode property `name` of `window`, `None` if it is not set
+        fn fetch(con: &Connection, name: &str, window: xcb::x::Window) -> Result<Option<Dump>> {
+            match name {
+                $($name => {
+                    let $window = window;
+                    let reply = con.wait_for_optional_reply(con.send_request(&$request))?;
+                    Ok(reply.map(|reply| Dump {
+                        format: Format::$format,
+                        value: to_value(con, &reply),
+                    }))
+                })+
+                _ => Ok(None),
+            }
+        }
+    };
+}
+
+properties! {
+    "_NET_SUPPORTED" Root Plain, |_w| ewmh::proto::GetSupported;
+    "_NET_CLIENT_LIST" Root Windows, |_w| ewmh::proto::GetClientList;
+    "_NET_CLIENT_LIST_STACKING" Root Windows, |_w| ewmh::proto::GetClientListStacking;
+    "_NET_NUMBER_OF_DESKTOPS" Root Plain, |_w| ewmh::proto::GetNumberOfDesktops;
+    "_NET_DESKTOP_GEOMETRY" Root Plain, |_w| ewmh::proto::GetDesktopGeometry;
+    "_NET_DESKTOP_VIEWPORT" Root Plain, |_w| ewmh::proto::GetDesktopViewport;
+    "_NET_CURRENT_DESKTOP" Root Plain, |_w| ewmh::proto::GetCurrentDesktop;
+    "_NET_DESKTOP_NAMES" Root Plain, |_w| ewmh::proto::GetDesktopNames;
+    "_NET_ACTIVE_WINDOW" Root Windows, |_w| ewmh::proto::GetActiveWindow;
+    "_NET_WORKAREA" Root Plain, |_w| ewmh::proto::GetWorkarea;
+    "_NET_SUPPORTING_WM_CHECK" Root Windows, |_w| ewmh::proto::GetSupportingWmCheck;
+    "_NET_VIRTUAL_ROOTS" Root Windows, |_w| ewmh::proto::GetVirtualRoots;
+    "_NET_DESKTOP_LAYOUT" Root Plain, |_w| ewmh::proto::DesktopLayout;
+    "_NET_SHOWING_DESKTOP" Root Plain, |_w| ewmh::proto::GetShowingDesktop;
+    "WM_ICON_SIZE" Root Plain, |_w| icccm::proto::GetWmIconSize;
+
+    "_NET_WM_NAME" Client Plain, |w| ewmh::proto::GetWmName(w);
+    "_NET_WM_VISIBLE_NAME" Client Plain, |w| ewmh::proto::GetWmVisibleName(w);
+    "_NET_WM_ICON_NAME" Client Plain, |w| ewmh::proto::GetWmIconName(w);
+    "_NET_WM_VISIBLE_ICON_NAME" Client Plain, |w| ewmh::proto::GetWmVisibleIconName(w);
+    "_NET_WM_DESKTOP" Client Plain, |w| ewmh::proto::GetWmDesktop(w);
+    "_NET_WM_WINDOW_TYPE" Client Plain, |w| ewmh::proto::GetWmWindowType(w);
+    "_NET_WM_STATE" Client Plain, |w| ewmh::proto::GetWmState(w);
+    "_NET_WM_ICON" Client Icons, |w| ewmh::proto::GetWmIcon(w);
+    "_NET_WM_PID" Client Plain, |w| ewmh::proto::GetWmPid(w);
+    "_NET_FRAME_EXTENTS" Client Plain, |w| ewmh::proto::GetFrameExtents(w);
+    "WM_NAME" Client Plain, |w| icccm::proto::GetWmName::new(w);
+    "WM_ICON_NAME" Client Plain, |w| icccm::proto::GetWmIconName::new(w);
+    "WM_COLORMAP_WINDOWS" Client Windows, |w| icccm::proto::GetWmColorMapWindows::new(w);
+    "WM_CLIENT_MACHINE" Client Plain, |w| icccm::proto::GetWmClientMachine::new(w);
+    "WM_CLASS" Client Plain, |w| icccm::proto::GetWmClass::new(w);
+    "WM_TRANSIENT_FOR" Client Windows, |w| icccm::proto::GetWmTransientFor::new(w);
+    "WM_NORMAL_HINTS" Client Plain, |w| icccm::proto::GetWmNormalHints::new(w);
+    "WM_HINTS" Client Plain, |w| icccm::proto::GetWmHints::new(w);
+    "WM_CLIENT_LEADER" Client Windows, |w| icccm::proto::GetWmClientLeader::new(w);
+    "WM_WINDOW_ROLE" Client Plain, |w| icccm::proto::GetWmWindowRole::new(w);
+    "SM_CLIENT_ID" Client Plain, |w| icccm::proto::GetSmClientId::new(w);
+    "WM_COMMAND" Client Plain, |w| icccm::proto::GetWmCommand::new(w);
+    "WM_LOCALE_NAME" Client Plain, |w| icccm::proto::GetWmLocaleName::new(w);
+}
+
+/// Fields of `WmHints` that hold window or pixmap ids
+const ID_FIELDS: &[&str] = &["icon_pixmap", "icon_window", "icon_mask", "window_group"];
+
+fn to_value<R: Serialize>(con: &Connection, reply: &R) -> Value {
+    serde_json::to_value(AtomNamed::new(con, reply)).expect("replies serialize to JSON")
+}
+
+struct Options {
+    display: Option<String>,
+    window: Option<xcb::x::Window>,
+    json: bool,
+    spy: bool,
+}
+
+fn parse_args() -> std::result::Result<Options, String> {
+    let mut options = Options {
+        display: None,
+        window: None,
+        json: false,
+        spy: false,
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-display" => options.display = Some(args.next().ok_or("-display needs a value")?),
+            "-root" => options.window = None,
+            "-id" => {
+                let id = args.next().ok_or("-id needs a value")?;
+                options.window = Some(xcb::x::Window::new(parse_id(&id)?));
+            }
+            "-json" => options.json = true,
+            "-spy" => options.spy = true,
+            "-help" | "--help" => {
+                println!("{}", USAGE);
+                exit(0);
+            }
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_id(id: &str) -> std::result::Result<u32, String> {
+    let parsed = match id.strip_prefix("0x").or_else(|| id.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => id.parse(),
+    };
+    parsed.map_err(|_| format!("invalid window id {}", id))
+}
+
+fn main() {
+    let options = parse_args().unwrap_or_else(|e| {
+        eprintln!("xcb-wm-prop: {}\n\n{}", e, USAGE);
+        exit(2);
+    });
+
+    if let Err(e) = run(&options) {
+        eprintln!("xcb-wm-prop: {}", e);
+        exit(1);
+    }
+}
+
+fn run(options: &Options) -> Result<()> {
+    let (xcb_con, screen) = xcb::Connection::connect(options.display.as_deref())?;
+    let con = Connection::try_connect(&xcb_con, screen)?;
+    let window = options.window.unwrap_or_else(|| con.root());
+
+    // Fail early for windows that do not exist instead of once per property
+    xcb_con.wait_for_reply(xcb_con.send_request(&xcb::x::GetWindowAttributes { window }))?;
+
+    let scope = if window == con.root() {
+        Scope::Root
+    } else {
+        Scope::Client
+    };
+    let properties: Vec<&str> = PROPERTIES
+        .iter()
+        .filter(|(_, s)| scope == Scope::Root || *s == Scope::Client)
+        .map(|(name, _)| *name)
+        .collect();
+
+    // Select the events first, so no change between the dump and the spying is lost
+    if options.spy {
+        ewmh::Watcher::new(con.ewmh()).watch(window)?;
+    }
+
+    let mut dumped = Map::new();
+    for name in &properties {
+        dump_property(&con, options, &mut dumped, name, window)?;
+    }
+    if options.json && options.spy {
+        print_json_lines(dumped);
+    } else if options.json {
+        let dump = json!({ "window": window.resource_id(), "properties": dumped });
+        println!("{:#}", dump);
+    }
+
+    if options.spy {
+        spy(&xcb_con, &con, options, window, &properties)?;
+    }
+
+    Ok(())
+}
+
+/// Print one `{"property", "value"}` object per line, the JSON output of `-spy`
+fn print_json_lines(dumped: Map<String, Value>) {
+    for (name, value) in dumped {
+        println!("{}", json!({ "property": name, "value": value }));
+    }
+}
+
+fn spy(
+    xcb_con: &xcb::Connection,
+    con: &Connection,
+    options: &Options,
+    window: xcb::x::Window,
+    properties: &[&str],
+) -> Result<()> {
+    loop {
+        let event = match xcb_con.wait_for_event()? {
+            xcb::Event::X(xcb::x::Event::PropertyNotify(event)) => event,
+            _ => continue,
+        };
+
+        let name = con.atom_name(event.atom())?;
+        let name = match properties.iter().find(|p| **p == name) {
+            Some(name) => *name,
+            None => continue,
+        };
+
+        if event.state() == xcb::x::Property::Delete {
+            if options.json {
+                println!("{}", json!({ "property": name, "deleted": true }));
+            } else {
+                println!("{}:  deleted", name);
+            }
+            continue;
+        }
+
+        let mut dumped = Map::new();
+        dump_property(con, options, &mut dumped, name, window)?;
+        if options.json {
+            print_json_lines(dumped);
+        }
+    }
+}
+
+/// Fetch property `name` and print it, or collect it into `dumped` for JSON output
+///
+/// Properties that are set but cannot be decoded are printed with the error.
+fn dump_property(
+    con: &Connection,
+    options: &Options,
+    dumped: &mut Map<String, Value>,
+    name: &str,
+    window: xcb::x::Window,
+) -> Result<()> {
+    match fetch(con, name, window) {
+        Ok(Some(dump)) => print_property(options, dumped, name, Ok(&dump)),
+        Ok(None) => {}
+        Err(e) if is_decode_error(&e) => print_property(options, dumped, name, Err(&e)),
+        Err(e) => return Err(e),
+    }
+    Ok(())
+}
+
+/// The property is set but malformed, other properties can still be printed
+fn is_decode_error(e: &Error) -> bool {
+    matches!(
+        e,
+        Error::WrongType { .. }
+            | Error::WrongFormat { .. }
+            | Error::Truncated { .. }
+            | Error::InvalidUtf8(_)
+    )
+}
+
+fn print_property(
+    options: &Options,
+    dumped: &mut Map<String, Value>,
+    name: &str,
+    dump: std::result::Result<&Dump, &Error>,
+) {
+    if options.json {
+        let value = match dump {
+            Ok(dump) => dump.value.clone(),
+            Err(e) => json!({ "error": e.to_string() }),
+        };
+        dumped.insert(name.to_owned(), value);
+        return;
+    }
+
+    match dump {
+        Ok(dump) => println!("{} = {}", name, format_reply(dump)),
+        Err(e) => println!("{}:  cannot decode: {}", name, e),
+    }
+}
+
+/// Text form of a reply, single-field replies are printed without the field name
+fn format_reply(dump: &Dump) -> String {
+    match &dump.value {
+        Value::Object(fields) if fields.len() == 1 => {
+            let value = fields.values().next().unwrap();
+            format_value(dump.format, None, value)
+        }
+        value => format_value(dump.format, None, value),
+    }
+}
+
+fn format_value(format: Format, field: Option<&str>, value: &Value) -> String {
+    let is_id = format == Format::Windows || field.is_some_and(|f| ID_FIELDS.contains(&f));
+
+    match value {
+        Value::Number(n) if is_id => format!("window id # {:#x}", n.as_u64().unwrap_or(0)),
+        Value::String(s) => format!("{:?}", s),
+        Value::Array(items) if format == Format::Icons => items
+            .iter()
+            .map(|icon| format!("{}x{}", icon["width"], icon["height"]))
+            .collect::<Vec<_>>()
+            .join(", "),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| format_value(format, field, item))
+            .collect::<Vec<_>>()
+            .join(", "),
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(name, value)| {
+                format!("\n\t{}: {}", name, format_value(format, Some(name), value))
+            })
+            .collect(),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,339 @@
+use std::sync::Arc;
+
+use crate::atoms::AtomCache;
+use crate::backend::{Backend, VoidCookieChecked};
+use crate::batch::{WmBatch, WmBatchCookies};
+use crate::traits::{
+    WmPropertyCookieChecked, WmPropertyCookieUnchecked, WmPropertyRequestUnchecked, WmRequest,
+    WmVoidRequestChecked,
+};
+use crate::{ewmh, icccm};
+
+/// Combined `ewmh` and `icccm` entry point
+///
+/// `Connection` accepts requests of both protocols through the same API as
+/// [`ewmh::Connection`] and [`icccm::Connection`]. A window manager or pager
+/// that needs both protocols does not have to juggle two connections.
+///
+/// Both protocols share one [`AtomCache`]. Atoms needed by both (e.g.
+/// `UTF8_STRING`) are interned only once.
+pub struct Connection<'a> {
+    ewmh: ewmh::Connection<'a>,
+    icccm: icccm::Connection<'a>,
+}
+
+#[allow(dead_code)]
+impl<'a> Connection<'a> {
+    /// Wrap `backend` (usually an [`xcb::Connection`]) for the first screen without any
+    /// round-trip
+    ///
+    /// Atoms are interned on first use.
+    ///
+    /// # Panics
+    ///
+    /// If the X server has no screen. Use [`Connection::try_connect`] to handle this.
+    pub fn connect(backend: &'a dyn Backend) -> Connection<'a> {
+        Connection::try_connect(backend, 0).unwrap()
+    }
+
+    /// Wrap `backend` for `screen` without any round-trip
+    ///
+    /// Fails with [`crate::Error::NoSuchScreen`] if the X server has no such screen.
+    pub fn try_connect(backend: &'a dyn Backend, screen: i32) -> crate::Result<Connection<'a>> {
+        let root = backend.screen_root(screen)?;
+        let cache = Arc::new(AtomCache::new());
+
+        Ok(Connection {
+            ewmh: ewmh::Connection::with_cache(backend, root, cache.clone()),
+            icccm: icccm::Connection::with_cache(backend, root, cache),
+        })
+    }
+
+    /// Wrap `backend` for the first screen and intern the atoms of both protocols up front in
+    /// one round-trip
+    pub fn connect_eager(backend: &'a dyn Backend) -> crate::Result<Connection<'a>> {
+        let con = Connection::try_connect(backend, 0)?;
+
+        let mut names: Vec<&str> = ewmh::atoms::ATOM_NAMES.to_vec();
+        names.extend(icccm::atoms::ATOM_NAMES.iter());
+        con.ewmh.cache().intern_all(backend, &names)?;
+
+        // Both tables are served from the cache now
+        con.ewmh.atoms()?;
+        con.icccm.atoms()?;
+
+        Ok(con)
+    }
+
+    /// Root window of the screen this connection was created for
+    pub fn root(&self) -> xcb::x::Window {
+        self.ewmh.root()
+    }
+
+    /// The atom for `name`, interned on first use
+    pub fn atom(&self, name: &str) -> crate::Result<xcb::x::Atom> {
+        self.ewmh.atom(name)
+    }
+
+    /// The name of `atom`, looked up on first use
+    pub fn atom_name(&self, atom: xcb::x::Atom) -> crate::Result<String> {
+        self.ewmh.atom_name(atom)
+    }
+
+    /// The `ewmh` view of this connection
+    ///
+    /// Needed to construct requests that take an [`ewmh::Connection`], e.g. client messages.
+    pub fn ewmh(&self) -> &ewmh::Connection<'a> {
+        &self.ewmh
+    }
+
+    /// The `icccm` view of this connection
+    pub fn icccm(&self) -> &icccm::Connection<'a> {
+        &self.icccm
+    }
+
+    pub fn send_request<'b, R, M>(&self, request: &'b R) -> R::Cookie
+    where
+        R: WmRequest<'b, M>,
+    {
+        request.send(self)
+    }
+
+    pub fn send_request_checked<'b, R, M>(&self, request: &'b R) -> VoidCookieChecked
+    where
+        R: WmVoidRequestChecked<'b, M>,
+    {
+        request.send(self)
+    }
+
+    pub fn send_request_unchecked<R, M>(&self, request: &R) -> R::Cookie
+    where
+        R: WmPropertyRequestUnchecked<M>,
+    {
+        request.send(self)
+    }
+
+    pub fn send_and_check_request<'b, R, M>(&self, request: &'b R) -> crate::Result<()>
+    where
+        R: WmVoidRequestChecked<'b, M>,
+    {
+        self.check_request(request.send(self))
+    }
+
+    pub fn wait_for_reply<C, M>(&self, cookie: C) -> crate::Result<C::Reply>
+    where
+        C: WmPropertyCookieChecked<M>,
+    {
+        cookie.wait(self)
+    }
+
+    /// Like [`Connection::wait_for_reply`] but a property that is not set on the window is
+    /// `None` instead of [`crate::Error::PropertyMissing`].
+    pub fn wait_for_optional_reply<C, M>(&self, cookie: C) -> crate::Result<Option<C::Reply>>
+    where
+        C: WmPropertyCookieChecked<M>,
+    {
+        match self.wait_for_reply(cookie) {
+            Ok(reply) => Ok(Some(reply)),
+            Err(crate::Error::PropertyMissing) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn wait_for_reply_unchecked<C, M>(&self, cookie: C) -> crate::Result<Option<C::Reply>>
+    where
+        C: WmPropertyCookieUnchecked<M>,
+    {
+        cookie.wait(self)
+    }
+
+    /// Queue all requests of `batch` without waiting for any reply
+    ///
+    /// See [`crate::WmBatch`] for what makes a batch.
+    pub fn send_batch<'b, B, M>(&self, batch: &'b B) -> B::Cookies
+    where
+        B: WmBatch<'b, M> + ?Sized,
+    {
+        batch.send(self)
+    }
+
+    /// Wait for the replies of a batch sent by [`Connection::send_batch`]
+    ///
+    /// Each reply is decoded on its own, so one missing property does not fail the batch.
+    pub fn wait_for_batch<C, M>(&self, cookies: C) -> C::Replies
+    where
+        C: WmBatchCookies<M>,
+    {
+        cookies.wait(self)
+    }
+
+    pub fn check_request(&self, cookie: VoidCookieChecked) -> crate::Result<()> {
+        self.ewmh.check_request(cookie)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "mock")]
+    fn client_store() -> (crate::mock::PropertyStore, xcb::x::Window) {
+        use crate::Property;
+        use xcb::Xid;
+
+        let store = crate::mock::PropertyStore::new();
+        let window = store.create_window();
+        store.set_property(
+            store.root(),
+            "_NET_SUPPORTING_WM_CHECK",
+            Property::format32(xcb::x::ATOM_WINDOW, vec![window.resource_id()]),
+        );
+        store.set_property(
+            window,
+            "_NET_WM_NAME",
+            Property::format8(store.atom("UTF8_STRING"), "Grüße".as_bytes().to_vec()),
+        );
+        store.set_property(
+            window,
+            "WM_CLASS",
+            Property::format8(xcb::x::ATOM_STRING, b"xterm\0XTerm\0".to_vec()),
+        );
+
+        (store, window)
+    }
+
+    #[test]
+    #[cfg(feature = "mock")]
+    fn ewmh_and_icccm_requests() {
+        let (store, window) = client_store();
+        let con = crate::Connection::connect(&store);
+
+        let cookie = con.send_request(&crate::ewmh::proto::GetSupportingWmCheck);
+        assert_eq!(con.wait_for_reply(cookie).unwrap().window, window);
+
+        let cookie = con.send_request(&crate::ewmh::proto::GetWmName(window));
+        assert_eq!(con.wait_for_reply(cookie).unwrap().name, "Grüße");
+
+        let cookie = con.send_request(&crate::icccm::proto::GetWmClass::new(window));
+        let class = con.wait_for_optional_reply(cookie).unwrap().unwrap();
+        assert_eq!(
+            (class.instance.as_str(), class.class.as_str()),
+            ("xterm", "XTerm")
+        );
+
+        let cookie = con.send_request(&crate::icccm::proto::GetWmName::new(window));
+        assert!(con.wait_for_optional_reply(cookie).unwrap().is_none());
+
+        // Both protocols share one atom table
+        assert_eq!(
+            con.ewmh().atom("UTF8_STRING").unwrap(),
+            con.icccm().atom("UTF8_STRING").unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "mock")]
+    fn mixed_batch() {
+        let (store, window) = client_store();
+        let con = crate::Connection::connect(&store);
+
+        let (name, state, class, hints) = con.wait_for_batch(con.send_batch(&(
+            crate::ewmh::proto::GetWmName(window),
+            crate::ewmh::proto::GetWmState(window),
+            crate::icccm::proto::GetWmClass::new(window),
+            crate::icccm::proto::GetWmHints::new(window),
+        )));
+        assert_eq!(name.unwrap().name, "Grüße");
+        assert!(matches!(state, Err(crate::Error::PropertyMissing)));
+        assert_eq!(class.unwrap().class, "XTerm");
+        assert!(matches!(hints, Err(crate::Error::PropertyMissing)));
+
+        let names = con.wait_for_batch(con.send_batch(
+            &[
+                crate::ewmh::proto::GetWmName(window),
+                crate::ewmh::proto::GetWmName(con.root()),
+            ][..],
+        ));
+        assert_eq!(names.len(), 2);
+        assert_eq!(names[0].as_ref().unwrap().name, "Grüße");
+        assert!(matches!(names[1], Err(crate::Error::PropertyMissing)));
+    }
+
+    #[test]
+    #[cfg(feature = "mock")]
+    fn window_info() {
+        use crate::Property;
+        use xcb::Xid;
+
+        let (store, window) = client_store();
+        let parent = store.create_window();
+        let normal = store.atom("_NET_WM_WINDOW_TYPE_NORMAL");
+        let hidden = store.atom("_NET_WM_STATE_HIDDEN");
+        let hints = vec![3, 1, 1, 0, 0, 0, 0, 0, 0];
+        for (name, property) in [
+            (
+                "_NET_WM_WINDOW_TYPE",
+                Property::format32(xcb::x::ATOM_ATOM, vec![normal.resource_id()]),
+            ),
+            (
+                "_NET_WM_STATE",
+                Property::format32(xcb::x::ATOM_ATOM, vec![hidden.resource_id()]),
+            ),
+            (
+                "_NET_WM_DESKTOP",
+                Property::format32(xcb::x::ATOM_CARDINAL, vec![2]),
+            ),
+            (
+                "_NET_WM_PID",
+                Property::format32(xcb::x::ATOM_CARDINAL, vec![4242]),
+            ),
+            (
+                "_NET_WM_ICON",
+                Property::format32(xcb::x::ATOM_CARDINAL, vec![1, 2, 0xff00_0000, 0xffff_ffff]),
+            ),
+            (
+                "WM_TRANSIENT_FOR",
+                Property::format32(xcb::x::ATOM_WINDOW, vec![parent.resource_id()]),
+            ),
+            (
+                "WM_HINTS",
+                Property::format32(xcb::x::ATOM_WM_HINTS, hints.clone()),
+            ),
+            (
+                "_NET_FRAME_EXTENTS",
+                Property::format32(xcb::x::ATOM_CARDINAL, vec![1, 2, 20, 3]),
+            ),
+        ] {
+            store.set_property(window, name, property);
+        }
+        let con = crate::Connection::connect(&store);
+
+        let info = crate::WindowInfo::fetch(&con, window).unwrap();
+        assert_eq!(info.window, window);
+        assert_eq!(info.title.as_deref(), Some("Grüße"));
+        assert_eq!(info.instance.as_deref(), Some("xterm"));
+        assert_eq!(info.class.as_deref(), Some("XTerm"));
+        assert_eq!(info.window_types, vec![normal]);
+        assert_eq!(info.states, vec![hidden]);
+        assert_eq!(info.desktop, Some(2));
+        assert_eq!(info.pid, Some(4242));
+        assert_eq!(
+            info.icons,
+            vec![crate::ewmh::proto::WmIcon {
+                width: 1,
+                height: 2,
+                data: vec![0xff00_0000, 0xffff_ffff],
+            }]
+        );
+        assert_eq!(info.transient_for, Some(parent));
+        assert_eq!(info.hints.unwrap().as_data(), hints);
+        let extents = info.frame_extents.unwrap();
+        assert_eq!(
+            (extents.left, extents.right, extents.top, extents.bottom),
+            (1, 2, 20, 3)
+        );
+
+        let root = crate::WindowInfo::fetch(&con, con.root()).unwrap();
+        assert_eq!(root.title, None);
+        assert!(root.window_types.is_empty());
+        assert!(root.hints.is_none());
+    }
+}
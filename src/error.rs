@@ -0,0 +1,105 @@
+//! Errors of `xcb-wm` requests
+
+/// Error returned when a reply cannot be retrieved or decoded
+#[derive(Debug)]
+pub enum Error {
+    /// The property is not set on the window
+    PropertyMissing,
+
+    /// The property is set but has a different type than requested
+    WrongType { actual: xcb::x::Atom },
+
+    /// The property has a different format (8, 16 or 32 bit) than expected
+    WrongFormat { expected: u8, actual: u8 },
+
+    /// The property has fewer elements than expected
+    Truncated { expected: usize, actual: usize },
+
+    /// The property is expected to be UTF-8 but is not
+    InvalidUtf8(std::string::FromUtf8Error),
+
+    /// The X server has no screen with this number
+    NoSuchScreen(i32),
+
+    /// The window manager has no desktop with this number
+    NoSuchDesktop(u32),
+
+    /// Waiting for the connection to become readable failed
+    Io(std::io::Error),
+
+    /// Error from the underlying [`xcb`] connection or protocol
+    Xcb(xcb::Error),
+
+    /// Error of a [`crate::backend::Backend`] other than [`xcb::Connection`]
+    Backend(Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// `Result` with an [`Error`]
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::PropertyMissing => write!(f, "property is not set"),
+            Error::WrongType { actual } => write!(f, "property has wrong type {:?}", actual),
+            Error::WrongFormat { expected, actual } => write!(
+                f,
+                "property has format {}, expected format {}",
+                actual, expected
+            ),
+            Error::Truncated { expected, actual } => write!(
+                f,
+                "property has {} elements, expected at least {}",
+                actual, expected
+            ),
+            Error::InvalidUtf8(e) => write!(f, "property is not valid UTF-8: {}", e),
+            Error::NoSuchScreen(screen) => write!(f, "X server has no screen {}", screen),
+            Error::NoSuchDesktop(desktop) => write!(f, "there is no desktop {}", desktop),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Xcb(e) => write!(f, "{}", e),
+            Error::Backend(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::InvalidUtf8(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::Xcb(e) => Some(e),
+            Error::Backend(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<xcb::Error> for Error {
+    fn from(e: xcb::Error) -> Self {
+        Error::Xcb(e)
+    }
+}
+
+impl From<xcb::ConnError> for Error {
+    fn from(e: xcb::ConnError) -> Self {
+        Error::Xcb(xcb::Error::Connection(e))
+    }
+}
+
+impl From<xcb::ProtocolError> for Error {
+    fn from(e: xcb::ProtocolError) -> Self {
+        Error::Xcb(xcb::Error::Protocol(e))
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<std::string::FromUtf8Error> for Error {
+    fn from(e: std::string::FromUtf8Error) -> Self {
+        Error::InvalidUtf8(e)
+    }
+}
@@ -1,7 +1,9 @@
 use std::collections::HashMap;
 
+use crate::atoms::AtomCache;
+
 // TODO use xcb::atom_struct!{} for this?
-const ATOM_NAMES: [&str; 82] = [
+pub(crate) const ATOM_NAMES: [&str; 82] = [
     "_NET_SUPPORTED",
     "_NET_CLIENT_LIST",
     "_NET_CLIENT_LIST_STACKING",
@@ -180,25 +182,15 @@ pub struct Atoms {
 }
 
 impl Atoms {
-    pub(crate) fn intern(con: &xcb::Connection) -> Atoms {
-        let mut cookies: HashMap<&'static str, xcb::x::InternAtomCookie> = HashMap::new();
-
-        for atom in ATOM_NAMES {
-            let intern_atom = xcb::x::InternAtom {
-                only_if_exists: false,
-                name: atom.as_bytes(),
-            };
-
-            cookies.insert(atom, con.send_request(&intern_atom));
-        }
-
-        let interned_atoms: HashMap<&'static str, xcb::x::Atom> = cookies
-            .into_iter()
-            .map(|(atom_name, cookie)| (atom_name, con.wait_for_reply(cookie).unwrap()))
-            .map(|(atom_name, reply)| (atom_name, reply.atom()))
-            .collect();
+    pub(crate) fn intern(
+        con: &dyn crate::backend::Backend,
+        cache: &AtomCache,
+    ) -> crate::Result<Atoms> {
+        let atoms = cache.intern_all(con, &ATOM_NAMES)?;
 
-        Atoms::from_interned_atoms(interned_atoms)
+        Ok(Atoms::from_interned_atoms(
+            ATOM_NAMES.iter().copied().zip(atoms).collect(),
+        ))
     }
 
     fn from_interned_atoms(mut atoms: HashMap<&'static str, xcb::x::Atom>) -> Atoms {
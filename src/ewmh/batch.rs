@@ -0,0 +1,93 @@
+//! Pipelined batches of `GetProperty` requests
+//!
+//! A batch is a tuple of requests (possibly for different windows) or a slice of requests of the
+//! same type. [`Connection::send_batch`] queues all of them before anything is waited for, so the
+//! whole batch costs one round-trip. [`Connection::wait_for_batch`] returns one
+//! [`crate::Result`] per request in the same shape as the batch.
+//!
+//! # Example
+//! ```ignore
+//! let (name, state, desktop) = ewmh_con.wait_for_batch(ewmh_con.send_batch(&(
+//!     GetWmName(window),
+//!     GetWmState(window),
+//!     GetWmDesktop(window),
+//! )));
+//! ```
+
+use crate::ewmh::connection::Connection;
+use crate::ewmh::traits::{EwmhPropertyCookieChecked, EwmhRequest};
+
+/// Requests that can be sent by [`Connection::send_batch`]
+pub trait EwmhBatch<'a> {
+    type Cookies: EwmhBatchCookies;
+
+    fn send(&'a self, con: &Connection) -> Self::Cookies;
+}
+
+/// Cookies that can be retrieved by [`Connection::wait_for_batch`]
+pub trait EwmhBatchCookies {
+    type Replies;
+
+    fn wait(self, con: &Connection) -> Self::Replies;
+}
+
+impl<'a, R> EwmhBatch<'a> for [R]
+where
+    R: EwmhRequest<'a>,
+    R::EwmhCookie: EwmhPropertyCookieChecked,
+{
+    type Cookies = Vec<R::EwmhCookie>;
+
+    fn send(&'a self, con: &Connection) -> Self::Cookies {
+        self.iter().map(|request| request.send(con)).collect()
+    }
+}
+
+impl<C: EwmhPropertyCookieChecked> EwmhBatchCookies for Vec<C> {
+    type Replies = Vec<crate::Result<C::Reply>>;
+
+    fn wait(self, con: &Connection) -> Self::Replies {
+        self.into_iter()
+            .map(|cookie| con.wait_for_reply(cookie))
+            .collect()
+    }
+}
+
+macro_rules! ewmh_batch_tuple {
+    ($($request:ident $cookie:ident $idx:tt),+) => {
+        impl<'a, $($request),+> EwmhBatch<'a> for ($($request,)+)
+        where
+            $($request: EwmhRequest<'a>, $request::EwmhCookie: EwmhPropertyCookieChecked,)+
+        {
+            type Cookies = ($($request::EwmhCookie,)+);
+
+            fn send(&'a self, con: &Connection) -> Self::Cookies {
+                ($(self.$idx.send(con),)+)
+            }
+        }
+
+        impl<$($cookie),+> EwmhBatchCookies for ($($cookie,)+)
+        where
+            $($cookie: EwmhPropertyCookieChecked,)+
+        {
+            type Replies = ($(crate::Result<$cookie::Reply>,)+);
+
+            fn wait(self, con: &Connection) -> Self::Replies {
+                ($(con.wait_for_reply(self.$idx),)+)
+            }
+        }
+    };
+}
+
+ewmh_batch_tuple!(A CA 0);
+ewmh_batch_tuple!(A CA 0, B CB 1);
+ewmh_batch_tuple!(A CA 0, B CB 1, C CC 2);
+ewmh_batch_tuple!(A CA 0, B CB 1, C CC 2, D CD 3);
+ewmh_batch_tuple!(A CA 0, B CB 1, C CC 2, D CD 3, E CE 4);
+ewmh_batch_tuple!(A CA 0, B CB 1, C CC 2, D CD 3, E CE 4, F CF 5);
+ewmh_batch_tuple!(A CA 0, B CB 1, C CC 2, D CD 3, E CE 4, F CF 5, G CG 6);
+ewmh_batch_tuple!(A CA 0, B CB 1, C CC 2, D CD 3, E CE 4, F CF 5, G CG 6, H CH 7);
+ewmh_batch_tuple!(A CA 0, B CB 1, C CC 2, D CD 3, E CE 4, F CF 5, G CG 6, H CH 7, I CI 8);
+ewmh_batch_tuple!(A CA 0, B CB 1, C CC 2, D CD 3, E CE 4, F CF 5, G CG 6, H CH 7, I CI 8, J CJ 9);
+ewmh_batch_tuple!(A CA 0, B CB 1, C CC 2, D CD 3, E CE 4, F CF 5, G CG 6, H CH 7, I CI 8, J CJ 9, K CK 10);
+ewmh_batch_tuple!(A CA 0, B CB 1, C CC 2, D CD 3, E CE 4, F CF 5, G CG 6, H CH 7, I CI 8, J CJ 9, K CK 10, L CL 11);
@@ -0,0 +1,1038 @@
+//! Automated check of the `ewmh` compliance of the running window manager
+//!
+//! [`Checker`] reads `_NET_SUPPORTED` and verifies that every advertised root property is set and
+//! decodes with the type and format of its `Get*` request. It validates
+//! `_NET_SUPPORTING_WM_CHECK` and, given a test window, checks the properties the window manager
+//! sets on it, sends the advertised client messages and waits for the property changes the window
+//! manager has to make in response. Advertised hints that none of this covers are reported as
+//! skipped.
+//!
+//! The client messages change the state of the session (e.g. the current desktop), which is
+//! restored afterwards. `_NET_CLOSE_WINDOW` is not exercised, window managers may kill the client
+//! that owns the window.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use crate::ewmh::proto::*;
+use crate::ewmh::traits::EwmhPropertyCookieChecked;
+use crate::ewmh::Connection;
+use crate::{Error, Result};
+
+/// Source indication of the client messages, the checker acts like a pager
+const SOURCE_INDICATION: u32 = 2;
+
+/// Root properties and whether the window manager has to set them if it advertises them
+///
+/// `_NET_DESKTOP_NAMES` and `_NET_DESKTOP_LAYOUT` may be left to pagers, `_NET_VIRTUAL_ROOTS`
+/// is only set by window managers that use virtual roots.
+const ROOT_PROPERTIES: &[(&str, bool)] = &[
+    ("_NET_CLIENT_LIST", true),
+    ("_NET_CLIENT_LIST_STACKING", true),
+    ("_NET_NUMBER_OF_DESKTOPS", true),
+    ("_NET_DESKTOP_GEOMETRY", true),
+    ("_NET_DESKTOP_VIEWPORT", true),
+    ("_NET_CURRENT_DESKTOP", true),
+    ("_NET_DESKTOP_NAMES", false),
+    ("_NET_ACTIVE_WINDOW", true),
+    ("_NET_WORKAREA", true),
+    ("_NET_VIRTUAL_ROOTS", false),
+    ("_NET_DESKTOP_LAYOUT", false),
+    ("_NET_SHOWING_DESKTOP", true),
+];
+
+/// Properties of the test window and whether the window manager has to set them if it advertises
+/// them
+///
+/// `_NET_WM_STATE` may be left unset while the window has no state, the visible names are only set
+/// when the window manager shows a name other than the one of the client.
+const CLIENT_PROPERTIES: &[(&str, bool)] = &[
+    ("_NET_WM_DESKTOP", true),
+    ("_NET_WM_STATE", false),
+    ("_NET_WM_ALLOWED_ACTIONS", true),
+    ("_NET_FRAME_EXTENTS", true),
+    ("_NET_WM_VISIBLE_NAME", false),
+    ("_NET_WM_VISIBLE_ICON_NAME", false),
+];
+
+/// States that are toggled on the test window, the first advertised one is used
+const TEST_STATES: &[&str] = &[
+    "_NET_WM_STATE_ABOVE",
+    "_NET_WM_STATE_BELOW",
+    "_NET_WM_STATE_STICKY",
+    "_NET_WM_STATE_SHADED",
+    "_NET_WM_STATE_DEMANDS_ATTENTION",
+];
+
+/// Result of a single check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    Fail(String),
+
+    /// The check does not apply, e.g. an optional property is not set
+    Skip(String),
+}
+
+/// A single check of a hint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    /// The hint, e.g. `_NET_ACTIVE_WINDOW`
+    pub hint: String,
+
+    /// What was checked, e.g. `is set`
+    pub what: String,
+
+    pub outcome: Outcome,
+}
+
+/// All checks of a [`Checker::check`] run, in the order they were made
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    /// No check failed
+    pub fn passed(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &Check> {
+        self.checks
+            .iter()
+            .filter(|check| matches!(check.outcome, Outcome::Fail(_)))
+    }
+
+    /// The check of `hint` and `what`, if it was made
+    pub fn check(&self, hint: &str, what: &str) -> Option<&Check> {
+        self.checks
+            .iter()
+            .find(|check| check.hint == hint && check.what == what)
+    }
+
+    fn record(&mut self, hint: &str, what: &str, outcome: Outcome) {
+        self.checks.push(Check {
+            hint: hint.to_owned(),
+            what: what.to_owned(),
+            outcome,
+        });
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (mut passed, mut failed, mut skipped) = (0, 0, 0);
+
+        for check in &self.checks {
+            let (label, detail) = match &check.outcome {
+                Outcome::Pass => {
+                    passed += 1;
+                    ("PASS", None)
+                }
+                Outcome::Fail(reason) => {
+                    failed += 1;
+                    ("FAIL", Some(reason))
+                }
+                Outcome::Skip(reason) => {
+                    skipped += 1;
+                    ("SKIP", Some(reason))
+                }
+            };
+
+            write!(f, "{}  {} {}", label, check.hint, check.what)?;
+            match detail {
+                Some(detail) => writeln!(f, ": {}", detail)?,
+                None => writeln!(f)?,
+            }
+        }
+
+        write!(
+            f,
+            "{} passed, {} failed, {} skipped",
+            passed, failed, skipped
+        )
+    }
+}
+
+/// A property as fetched by the checker
+///
+/// `None` if it is not set, `Some(Err)` if it cannot be decoded or is invalid.
+type Checked<T> = Option<std::result::Result<T, String>>;
+
+/// Checks the window manager behind a [`Connection`]
+pub struct Checker<'a> {
+    con: &'a Connection<'a>,
+    timeout: Duration,
+}
+
+impl<'a> Checker<'a> {
+    pub fn new(con: &'a Connection<'a>) -> Checker<'a> {
+        Checker {
+            con,
+            timeout: Duration::from_secs(2),
+        }
+    }
+
+    /// How long to wait for the response to a client message, 2 seconds by default
+    pub fn timeout(mut self, timeout: Duration) -> Checker<'a> {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Check the root properties and, if `test_window` is given, the client messages
+    ///
+    /// `test_window` has to be a mapped top-level window of the caller. Compliance problems are
+    /// reported as failed checks, only connection and protocol errors fail the run.
+    pub fn check(&self, test_window: Option<xcb::x::Window>) -> Result<Report> {
+        let mut report = Report::default();
+
+        let supported = match self.get(self.con.send_request(&GetSupported))? {
+            Some(Ok(reply)) => reply
+                .atoms
+                .iter()
+                .map(|atom| self.con.atom_name(*atom))
+                .collect::<Result<Vec<String>>>()?,
+            Some(Err(e)) => {
+                report.record("_NET_SUPPORTED", "is set", Outcome::Fail(e));
+                return Ok(report);
+            }
+            None => {
+                report.record(
+                    "_NET_SUPPORTED",
+                    "is set",
+                    Outcome::Fail("not set, no ewmh window manager is running".to_owned()),
+                );
+                return Ok(report);
+            }
+        };
+        report.record("_NET_SUPPORTED", "is set", Outcome::Pass);
+
+        self.check_supporting_wm_check(&mut report)?;
+
+        for (name, required) in ROOT_PROPERTIES {
+            if !supported.iter().any(|s| s == name) {
+                continue;
+            }
+
+            let outcome = match self.root_property(name)? {
+                Some(Ok(())) => Outcome::Pass,
+                Some(Err(e)) => Outcome::Fail(e),
+                None if *required => Outcome::Fail("advertised but not set".to_owned()),
+                None => Outcome::Skip("not set".to_owned()),
+            };
+            report.record(name, "is set and valid", outcome);
+        }
+
+        if let Some(window) = test_window {
+            self.check_client_messages(&supported, window, &mut report)?;
+        }
+
+        for name in &supported {
+            let checked = report.checks.iter().any(|check| {
+                check.hint == *name || check.what.split_whitespace().any(|word| word == name)
+            });
+            if !checked {
+                report.record(
+                    name,
+                    "is supported",
+                    Outcome::Skip("not checked".to_owned()),
+                );
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// The root window and the check window point to the check window, which has a name
+    fn check_supporting_wm_check(&self, report: &mut Report) -> Result<()> {
+        const HINT: &str = "_NET_SUPPORTING_WM_CHECK";
+
+        let check_window = match self.get(self.con.send_request(&GetSupportingWmCheck))? {
+            Some(Ok(reply)) => reply.window,
+            Some(Err(e)) => {
+                report.record(HINT, "is set on the root window", Outcome::Fail(e));
+                return Ok(());
+            }
+            None => {
+                let reason = "not set, required by the specification".to_owned();
+                report.record(HINT, "is set on the root window", Outcome::Fail(reason));
+                return Ok(());
+            }
+        };
+        report.record(HINT, "is set on the root window", Outcome::Pass);
+
+        // `GetSupportingWmCheck` always reads the root window
+        let request = xcb::x::GetProperty {
+            delete: false,
+            window: check_window,
+            property: self.con.atom(HINT)?,
+            r#type: xcb::x::ATOM_WINDOW,
+            long_offset: 0,
+            long_length: 1,
+        };
+        let property = self
+            .con
+            .con
+            .wait_for_property(self.con.con.get_property(&request))?;
+        let outcome = match GetSupportingWmCheckReply::try_from(property) {
+            Ok(reply) if reply.window == check_window => Outcome::Pass,
+            Ok(reply) => Outcome::Fail(format!("points to {:?} instead of itself", reply.window)),
+            Err(e) => Outcome::Fail(e.to_string()),
+        };
+        report.record(HINT, "is set on the check window", outcome);
+
+        let outcome = match self.get(self.con.send_request(&GetWmName(check_window)))? {
+            Some(Ok(_)) => Outcome::Pass,
+            Some(Err(e)) => Outcome::Fail(e),
+            None => Outcome::Fail("_NET_WM_NAME is not set".to_owned()),
+        };
+        report.record(HINT, "window has a name", outcome);
+
+        Ok(())
+    }
+
+    /// Fetch and validate the root property `name`
+    fn root_property(&self, name: &str) -> Result<Checked<()>> {
+        let checked = match name {
+            "_NET_CLIENT_LIST" => valid(self.get(self.con.send_request(&GetClientList))?),
+            "_NET_CLIENT_LIST_STACKING" => {
+                let stacking = self.get(self.con.send_request(&GetClientListStacking))?;
+                let clients = self.get(self.con.send_request(&GetClientList))?;
+
+                validate(stacking, |stacking| {
+                    let mut stacking = stacking.clients.clone();
+                    let mut clients = match clients {
+                        Some(Ok(reply)) => reply.clients,
+                        _ => return Ok(()),
+                    };
+                    stacking.sort_by_key(xcb::Xid::resource_id);
+                    clients.sort_by_key(xcb::Xid::resource_id);
+                    ensure(
+                        stacking == clients,
+                        "lists other windows than _NET_CLIENT_LIST",
+                    )
+                })
+            }
+            "_NET_NUMBER_OF_DESKTOPS" => validate(
+                self.get(self.con.send_request(&GetNumberOfDesktops))?,
+                |r| ensure(r.desktops > 0, "is 0"),
+            ),
+            "_NET_DESKTOP_GEOMETRY" => {
+                validate(self.get(self.con.send_request(&GetDesktopGeometry))?, |r| {
+                    ensure(r.width > 0 && r.height > 0, "is empty")
+                })
+            }
+            "_NET_DESKTOP_VIEWPORT" => valid(self.get(self.con.send_request(&GetDesktopViewport))?),
+            "_NET_CURRENT_DESKTOP" => {
+                let desktops = self.get(self.con.send_request(&GetNumberOfDesktops))?;
+                validate(
+                    self.get(self.con.send_request(&GetCurrentDesktop))?,
+                    |r| match desktops {
+                        Some(Ok(desktops)) => ensure(
+                            r.desktop < desktops.desktops,
+                            "is not less than _NET_NUMBER_OF_DESKTOPS",
+                        ),
+                        _ => Ok(()),
+                    },
+                )
+            }
+            "_NET_DESKTOP_NAMES" => valid(self.get(self.con.send_request(&GetDesktopNames))?),
+            "_NET_ACTIVE_WINDOW" => valid(self.get(self.con.send_request(&GetActiveWindow))?),
+            "_NET_WORKAREA" => valid(self.get(self.con.send_request(&GetWorkarea))?),
+            "_NET_VIRTUAL_ROOTS" => valid(self.get(self.con.send_request(&GetVirtualRoots))?),
+            "_NET_DESKTOP_LAYOUT" => valid(self.get(self.con.send_request(&DesktopLayout))?),
+            "_NET_SHOWING_DESKTOP" => valid(self.get(self.con.send_request(&GetShowingDesktop))?),
+            _ => None,
+        };
+
+        Ok(checked)
+    }
+
+    /// Fetch and validate the property `name` of the test window
+    fn client_property(
+        &self,
+        name: &str,
+        window: xcb::x::Window,
+        desktops: u32,
+    ) -> Result<Checked<()>> {
+        let checked = match name {
+            "_NET_WM_DESKTOP" => validate(
+                self.get(self.con.send_request(&GetWmDesktop(window)))?,
+                |r| {
+                    ensure(
+                        desktops == 0 || r.desktop < desktops || r.desktop == 0xFFFF_FFFF,
+                        "is neither a desktop nor 0xFFFFFFFF",
+                    )
+                },
+            ),
+            "_NET_WM_STATE" => valid(self.get(self.con.send_request(&GetWmState(window)))?),
+            "_NET_WM_ALLOWED_ACTIONS" => {
+                valid(self.get(self.con.send_request(&GetWmAllowedActions(window)))?)
+            }
+            "_NET_FRAME_EXTENTS" => {
+                valid(self.get(self.con.send_request(&GetFrameExtents(window)))?)
+            }
+            "_NET_WM_VISIBLE_NAME" => {
+                valid(self.get(self.con.send_request(&GetWmVisibleName(window)))?)
+            }
+            "_NET_WM_VISIBLE_ICON_NAME" => {
+                valid(self.get(self.con.send_request(&GetWmVisibleIconName(window)))?)
+            }
+            _ => None,
+        };
+
+        Ok(checked)
+    }
+
+    /// Send the advertised client messages concerning `window` and wait for their effect
+    fn check_client_messages(
+        &self,
+        supported: &[String],
+        window: xcb::x::Window,
+        report: &mut Report,
+    ) -> Result<()> {
+        let supports = |name: &str| supported.iter().any(|s| s == name);
+
+        if supports("_NET_CLIENT_LIST") {
+            let managed = self.wait_until(|| {
+                Ok(matches!(
+                    self.get(self.con.send_request(&GetClientList))?,
+                    Some(Ok(reply)) if reply.clients.contains(&window)
+                ))
+            })?;
+            report.record(
+                "_NET_CLIENT_LIST",
+                "lists the test window",
+                self.outcome(managed),
+            );
+        }
+
+        let desktops = match self.get(self.con.send_request(&GetNumberOfDesktops))? {
+            Some(Ok(reply)) => reply.desktops,
+            _ => 0,
+        };
+
+        for (name, required) in CLIENT_PROPERTIES {
+            if !supports(name) {
+                continue;
+            }
+
+            // The window manager sets the required properties when it manages the window
+            let mut checked = None;
+            if *required {
+                self.wait_until(|| {
+                    checked = self.client_property(name, window, desktops)?;
+                    Ok(checked.is_some())
+                })?;
+            } else {
+                checked = self.client_property(name, window, desktops)?;
+            }
+
+            let outcome = match checked {
+                Some(Ok(())) => Outcome::Pass,
+                Some(Err(e)) => Outcome::Fail(e),
+                None if *required => Outcome::Fail("advertised but not set".to_owned()),
+                None => Outcome::Skip("not set".to_owned()),
+            };
+            report.record(name, "is set and valid on the test window", outcome);
+        }
+
+        if supports("_NET_WM_DESKTOP") {
+            self.check_wm_desktop(window, desktops, report)?;
+        }
+        if supports("_NET_CURRENT_DESKTOP") {
+            self.check_current_desktop(desktops, report)?;
+        }
+
+        if supports("_NET_ACTIVE_WINDOW") {
+            self.con.send_and_check_request(&SendActiveWindow::new(
+                self.con,
+                window,
+                SOURCE_INDICATION,
+                0,
+                None,
+            )?)?;
+            let active = self.wait_until(|| {
+                Ok(matches!(
+                    self.get(self.con.send_request(&GetActiveWindow))?,
+                    Some(Ok(reply)) if reply.window == window
+                ))
+            })?;
+            report.record(
+                "_NET_ACTIVE_WINDOW",
+                "activates the test window",
+                self.outcome(active),
+            );
+        }
+
+        if supports("_NET_WM_STATE") {
+            match TEST_STATES.iter().find(|state| supports(state)) {
+                Some(state) => self.check_wm_state(window, state, report)?,
+                None => report.record(
+                    "_NET_WM_STATE",
+                    "changes the state of the test window",
+                    Outcome::Skip("no state to test is advertised".to_owned()),
+                ),
+            }
+        }
+
+        if supports("_NET_REQUEST_FRAME_EXTENTS") {
+            self.con
+                .send_and_check_request(&SendRequestFrameExtents::new(self.con, window)?)?;
+            let set = self.wait_until(|| {
+                Ok(matches!(
+                    self.get(self.con.send_request(&GetFrameExtents(window)))?,
+                    Some(Ok(_))
+                ))
+            })?;
+            report.record(
+                "_NET_REQUEST_FRAME_EXTENTS",
+                "sets _NET_FRAME_EXTENTS on the test window",
+                self.outcome(set),
+            );
+        }
+
+        if supports("_NET_SHOWING_DESKTOP") {
+            self.check_showing_desktop(report)?;
+        }
+
+        Ok(())
+    }
+
+    fn check_wm_desktop(
+        &self,
+        window: xcb::x::Window,
+        desktops: u32,
+        report: &mut Report,
+    ) -> Result<()> {
+        const HINT: &str = "_NET_WM_DESKTOP";
+        let wm_desktop = || -> Result<Option<u32>> {
+            Ok(
+                match self.get(self.con.send_request(&GetWmDesktop(window)))? {
+                    Some(Ok(reply)) => Some(reply.desktop),
+                    _ => None,
+                },
+            )
+        };
+
+        let original = match wm_desktop()? {
+            Some(desktop) if desktops >= 2 && desktop < desktops => desktop,
+            _ => {
+                let reason = "needs two desktops and a test window on one of them".to_owned();
+                report.record(HINT, "moves the test window", Outcome::Skip(reason));
+                return Ok(());
+            }
+        };
+
+        let other = (original + 1) % desktops;
+        self.con.send_and_check_request(&SendWmDesktop::new(
+            self.con,
+            window,
+            other,
+            SOURCE_INDICATION,
+        )?)?;
+        let moved = self.wait_until(|| Ok(wm_desktop()? == Some(other)))?;
+        report.record(HINT, "moves the test window", self.outcome(moved));
+
+        self.con.send_and_check_request(&SendWmDesktop::new(
+            self.con,
+            window,
+            original,
+            SOURCE_INDICATION,
+        )?)
+    }
+
+    fn check_current_desktop(&self, desktops: u32, report: &mut Report) -> Result<()> {
+        const HINT: &str = "_NET_CURRENT_DESKTOP";
+        let current_desktop = || -> Result<Option<u32>> {
+            Ok(match self.get(self.con.send_request(&GetCurrentDesktop))? {
+                Some(Ok(reply)) => Some(reply.desktop),
+                _ => None,
+            })
+        };
+
+        let original = match current_desktop()? {
+            Some(desktop) if desktops >= 2 && desktop < desktops => desktop,
+            _ => {
+                let reason = "needs two desktops and a valid current desktop".to_owned();
+                report.record(HINT, "switches the desktop", Outcome::Skip(reason));
+                return Ok(());
+            }
+        };
+
+        let other = (original + 1) % desktops;
+        self.con
+            .send_and_check_request(&SendCurrentDesktop::new(self.con, other)?)?;
+        let switched = self.wait_until(|| Ok(current_desktop()? == Some(other)))?;
+        report.record(HINT, "switches the desktop", self.outcome(switched));
+
+        self.con
+            .send_and_check_request(&SendCurrentDesktop::new(self.con, original)?)
+    }
+
+    fn check_wm_state(
+        &self,
+        window: xcb::x::Window,
+        state: &str,
+        report: &mut Report,
+    ) -> Result<()> {
+        const HINT: &str = "_NET_WM_STATE";
+        let atom = self.con.atom(state)?;
+        let has_state = || -> Result<bool> {
+            Ok(matches!(
+                self.get(self.con.send_request(&GetWmState(window)))?,
+                Some(Ok(reply)) if reply.states.contains(&atom)
+            ))
+        };
+
+        // Change the state and toggle it back, which leaves it as it was
+        let original = has_state()?;
+        let (change, changed) = if original {
+            (
+                WmStateAction::Remove,
+                format!("removes {} from the test window", state),
+            )
+        } else {
+            (
+                WmStateAction::Add,
+                format!("adds {} to the test window", state),
+            )
+        };
+        let toggled = format!(
+            "toggles {} {} again",
+            state,
+            if original { "on" } else { "off" }
+        );
+        let steps = [
+            (change, !original, changed),
+            (WmStateAction::Toggle, original, toggled),
+        ];
+
+        let states = [atom, xcb::x::ATOM_NONE];
+        for (action, set, what) in steps {
+            self.con.send_and_check_request(&SendWmState::new(
+                self.con,
+                window,
+                action,
+                states,
+                SOURCE_INDICATION,
+            )?)?;
+            let done = self.wait_until(|| Ok(has_state()? == set))?;
+            report.record(HINT, &what, self.outcome(done));
+        }
+
+        Ok(())
+    }
+
+    fn check_showing_desktop(&self, report: &mut Report) -> Result<()> {
+        const HINT: &str = "_NET_SHOWING_DESKTOP";
+        let showing = || -> Result<Option<bool>> {
+            Ok(match self.get(self.con.send_request(&GetShowingDesktop))? {
+                Some(Ok(reply)) => Some(reply.is_showing_desktop),
+                _ => None,
+            })
+        };
+
+        // Toggle the mode and back, which leaves it as it was
+        let original = showing()?.unwrap_or(false);
+        for show in [!original, original] {
+            let what = if show { "enters" } else { "leaves" };
+            self.con
+                .send_and_check_request(&SetShowingDesktop::new(self.con, show)?)?;
+            let changed = self.wait_until(|| Ok(showing()? == Some(show)))?;
+            report.record(
+                HINT,
+                &format!("{} showing the desktop", what),
+                self.outcome(changed),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// The reply to `cookie` as a [`Checked`] value, connection and protocol errors abort the run
+    fn get<C: EwmhPropertyCookieChecked>(&self, cookie: C) -> Result<Checked<C::Reply>> {
+        match self.con.wait_for_reply(cookie) {
+            Ok(reply) => Ok(Some(Ok(reply))),
+            Err(Error::PropertyMissing) => Ok(None),
+            Err(e @ Error::Xcb(_)) | Err(e @ Error::Backend(_)) | Err(e @ Error::Io(_)) => Err(e),
+            Err(e) => Ok(Some(Err(e.to_string()))),
+        }
+    }
+
+    /// Poll `condition` until it holds or the timeout expires
+    fn wait_until(&self, mut condition: impl FnMut() -> Result<bool>) -> Result<bool> {
+        let deadline = Instant::now() + self.timeout;
+
+        loop {
+            if condition()? {
+                return Ok(true);
+            }
+            if Instant::now() >= deadline {
+                return Ok(false);
+            }
+            sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn outcome(&self, happened: bool) -> Outcome {
+        if happened {
+            Outcome::Pass
+        } else {
+            Outcome::Fail(format!("no change within {:?}", self.timeout))
+        }
+    }
+}
+
+fn valid<T>(checked: Checked<T>) -> Checked<()> {
+    validate(checked, |_| Ok(()))
+}
+
+fn validate<T>(
+    checked: Checked<T>,
+    f: impl FnOnce(&T) -> std::result::Result<(), String>,
+) -> Checked<()> {
+    checked.map(|reply| reply.and_then(|reply| f(&reply)))
+}
+
+fn ensure(condition: bool, problem: &str) -> std::result::Result<(), String> {
+    if condition {
+        Ok(())
+    } else {
+        Err(problem.to_owned())
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use std::time::Duration;
+
+    use xcb::Xid;
+
+    use super::*;
+    use crate::mock::PropertyStore;
+    use crate::Property;
+
+    /// A root window as set up by a window manager that supports `supported`
+    fn window_manager(store: &PropertyStore, supported: &[&str]) -> xcb::x::Window {
+        let root = store.root();
+        let check = store.create_window();
+        let client = store.create_window();
+        let atoms = supported.iter().map(|s| store.atom(s).resource_id());
+        let cardinal = |value: Vec<u32>| Property::format32(xcb::x::ATOM_CARDINAL, value);
+        let window = |value: Vec<u32>| Property::format32(xcb::x::ATOM_WINDOW, value);
+
+        store.set_property(
+            root,
+            "_NET_SUPPORTED",
+            Property::format32(xcb::x::ATOM_ATOM, atoms.collect()),
+        );
+        for w in [root, check] {
+            store.set_property(
+                w,
+                "_NET_SUPPORTING_WM_CHECK",
+                window(vec![check.resource_id()]),
+            );
+        }
+        store.set_property(
+            check,
+            "_NET_WM_NAME",
+            Property::format8(store.atom("UTF8_STRING"), b"wm".to_vec()),
+        );
+
+        store.set_property(root, "_NET_NUMBER_OF_DESKTOPS", cardinal(vec![2]));
+        store.set_property(root, "_NET_CURRENT_DESKTOP", cardinal(vec![0]));
+        store.set_property(root, "_NET_CLIENT_LIST", window(vec![client.resource_id()]));
+        store.set_property(
+            root,
+            "_NET_CLIENT_LIST_STACKING",
+            window(vec![client.resource_id()]),
+        );
+
+        client
+    }
+
+    #[test]
+    fn root_properties() {
+        let store = PropertyStore::new();
+        let client = window_manager(
+            &store,
+            &[
+                "_NET_SUPPORTED",
+                "_NET_CLIENT_LIST",
+                "_NET_CLIENT_LIST_STACKING",
+                "_NET_NUMBER_OF_DESKTOPS",
+                "_NET_CURRENT_DESKTOP",
+                "_NET_DESKTOP_NAMES",
+            ],
+        );
+        let ewmh_con = Connection::connect(&store);
+
+        let report = Checker::new(&ewmh_con).check(None).unwrap();
+        assert!(report.passed(), "{}", report);
+        assert_eq!(
+            report
+                .check("_NET_DESKTOP_NAMES", "is set and valid")
+                .unwrap()
+                .outcome,
+            Outcome::Skip("not set".to_owned())
+        );
+
+        store.set_property(
+            store.root(),
+            "_NET_CURRENT_DESKTOP",
+            Property::format32(xcb::x::ATOM_CARDINAL, vec![2]),
+        );
+        store.set_property(
+            store.root(),
+            "_NET_CLIENT_LIST_STACKING",
+            Property::format32(xcb::x::ATOM_WINDOW, vec![client.resource_id(), 1]),
+        );
+        store.delete_property(store.root(), "_NET_CLIENT_LIST");
+
+        let report = Checker::new(&ewmh_con).check(None).unwrap();
+        let failures: Vec<_> = report.failures().map(|c| c.hint.as_str()).collect();
+        assert_eq!(failures, ["_NET_CLIENT_LIST", "_NET_CURRENT_DESKTOP"]);
+
+        store.set_property(
+            store.root(),
+            "_NET_NUMBER_OF_DESKTOPS",
+            Property::format16(xcb::x::ATOM_CARDINAL, vec![2]),
+        );
+        let report = Checker::new(&ewmh_con).check(None).unwrap();
+        assert!(matches!(
+            report
+                .check("_NET_NUMBER_OF_DESKTOPS", "is set and valid")
+                .unwrap()
+                .outcome,
+            Outcome::Fail(_)
+        ));
+    }
+
+    #[test]
+    fn supporting_wm_check() {
+        let store = PropertyStore::new();
+        window_manager(&store, &["_NET_SUPPORTED"]);
+        let ewmh_con = Connection::connect(&store);
+        let check = "_NET_SUPPORTING_WM_CHECK";
+
+        let report = Checker::new(&ewmh_con).check(None).unwrap();
+        assert!(report.passed(), "{}", report);
+
+        let other = store.create_window();
+        store.set_property(
+            store.root(),
+            check,
+            Property::format32(xcb::x::ATOM_WINDOW, vec![other.resource_id()]),
+        );
+        let report = Checker::new(&ewmh_con).check(None).unwrap();
+        let failures: Vec<_> = report.failures().map(|c| c.what.as_str()).collect();
+        assert_eq!(
+            failures,
+            ["is set on the check window", "window has a name"]
+        );
+
+        store.delete_property(store.root(), "_NET_SUPPORTED");
+        let report = Checker::new(&ewmh_con).check(None).unwrap();
+        assert_eq!(report.checks.len(), 1);
+        assert!(!report.passed());
+    }
+
+    #[test]
+    fn client_messages_without_response() {
+        let store = PropertyStore::new();
+        let client = window_manager(
+            &store,
+            &[
+                "_NET_SUPPORTED",
+                "_NET_CLIENT_LIST",
+                "_NET_NUMBER_OF_DESKTOPS",
+                "_NET_CURRENT_DESKTOP",
+                "_NET_ACTIVE_WINDOW",
+                "_NET_WM_STATE",
+            ],
+        );
+        let ewmh_con = Connection::connect(&store);
+
+        let report = Checker::new(&ewmh_con)
+            .timeout(Duration::ZERO)
+            .check(Some(client))
+            .unwrap();
+
+        assert_eq!(
+            report
+                .check("_NET_CLIENT_LIST", "lists the test window")
+                .unwrap()
+                .outcome,
+            Outcome::Pass
+        );
+        assert!(matches!(
+            report
+                .check("_NET_CURRENT_DESKTOP", "switches the desktop")
+                .unwrap()
+                .outcome,
+            Outcome::Fail(_)
+        ));
+        assert!(matches!(
+            report
+                .check("_NET_WM_STATE", "changes the state of the test window")
+                .unwrap()
+                .outcome,
+            Outcome::Skip(_)
+        ));
+
+        // The switch to desktop 1 and back to 0, the activation
+        let messages = store.client_messages();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].data32(), Some([1, 0, 0, 0, 0]));
+        assert_eq!(messages[1].data32(), Some([0, 0, 0, 0, 0]));
+        assert_eq!(messages[2].window, client);
+    }
+
+    #[test]
+    fn client_properties() {
+        let store = PropertyStore::new();
+        let client = window_manager(
+            &store,
+            &[
+                "_NET_SUPPORTED",
+                "_NET_NUMBER_OF_DESKTOPS",
+                "_NET_WM_DESKTOP",
+                "_NET_WM_ALLOWED_ACTIONS",
+                "_NET_FRAME_EXTENTS",
+                "_NET_WM_VISIBLE_NAME",
+                "_NET_WM_PING",
+            ],
+        );
+        let close = store.atom("_NET_WM_ACTION_CLOSE");
+        store.set_property(
+            client,
+            "_NET_WM_ALLOWED_ACTIONS",
+            Property::format32(xcb::x::ATOM_ATOM, vec![close.resource_id()]),
+        );
+        store.set_property(
+            client,
+            "_NET_WM_DESKTOP",
+            Property::format16(xcb::x::ATOM_CARDINAL, vec![1]),
+        );
+        let ewmh_con = Connection::connect(&store);
+        let checker = Checker::new(&ewmh_con).timeout(Duration::ZERO);
+        let outcome = |report: &Report, hint: &str, what: &str| {
+            report.check(hint, what).unwrap().outcome.clone()
+        };
+        let on_window = "is set and valid on the test window";
+
+        let report = checker.check(Some(client)).unwrap();
+        assert_eq!(
+            outcome(&report, "_NET_WM_ALLOWED_ACTIONS", on_window),
+            Outcome::Pass
+        );
+        assert!(matches!(
+            outcome(&report, "_NET_WM_DESKTOP", on_window),
+            Outcome::Fail(_)
+        ));
+        assert_eq!(
+            outcome(&report, "_NET_FRAME_EXTENTS", on_window),
+            Outcome::Fail("advertised but not set".to_owned())
+        );
+        assert_eq!(
+            outcome(&report, "_NET_WM_VISIBLE_NAME", on_window),
+            Outcome::Skip("not set".to_owned())
+        );
+        assert_eq!(
+            outcome(&report, "_NET_WM_PING", "is supported"),
+            Outcome::Skip("not checked".to_owned())
+        );
+
+        store.set_property(
+            client,
+            "_NET_WM_DESKTOP",
+            Property::format32(xcb::x::ATOM_CARDINAL, vec![2]),
+        );
+        let report = checker.check(Some(client)).unwrap();
+        assert_eq!(
+            outcome(&report, "_NET_WM_DESKTOP", on_window),
+            Outcome::Fail("is neither a desktop nor 0xFFFFFFFF".to_owned())
+        );
+
+        // Without a test window the client properties are not checked
+        let report = checker.check(None).unwrap();
+        assert!(report.passed(), "{}", report);
+        assert_eq!(
+            outcome(&report, "_NET_WM_ALLOWED_ACTIONS", "is supported"),
+            Outcome::Skip("not checked".to_owned())
+        );
+    }
+
+    #[test]
+    fn showing_desktop_is_restored() {
+        let store = PropertyStore::new();
+        let client = window_manager(&store, &["_NET_SUPPORTED", "_NET_SHOWING_DESKTOP"]);
+        store.set_property(
+            store.root(),
+            "_NET_SHOWING_DESKTOP",
+            Property::format32(xcb::x::ATOM_CARDINAL, vec![1]),
+        );
+        let ewmh_con = Connection::connect(&store);
+
+        Checker::new(&ewmh_con)
+            .timeout(Duration::ZERO)
+            .check(Some(client))
+            .unwrap();
+
+        // Leave the showing desktop mode, then enter it again
+        let messages: Vec<_> = store
+            .client_messages()
+            .iter()
+            .map(|message| message.data32().unwrap()[0])
+            .collect();
+        assert_eq!(messages, [0, 1]);
+    }
+
+    #[test]
+    fn wm_state_is_restored() {
+        let store = PropertyStore::new();
+        let client = window_manager(
+            &store,
+            &["_NET_SUPPORTED", "_NET_WM_STATE", "_NET_WM_STATE_ABOVE"],
+        );
+        let above = store.atom("_NET_WM_STATE_ABOVE");
+        store.set_property(
+            client,
+            "_NET_WM_STATE",
+            Property::format32(xcb::x::ATOM_ATOM, vec![above.resource_id()]),
+        );
+        let ewmh_con = Connection::connect(&store);
+
+        let report = Checker::new(&ewmh_con)
+            .timeout(Duration::ZERO)
+            .check(Some(client))
+            .unwrap();
+        assert!(report
+            .check(
+                "_NET_WM_STATE",
+                "removes _NET_WM_STATE_ABOVE from the test window"
+            )
+            .is_some());
+        assert!(report
+            .check(
+                "_NET_WM_STATE",
+                "adds _NET_WM_STATE_ABOVE to the test window"
+            )
+            .is_none());
+
+        // Remove the preset state, then toggle it back on
+        let messages: Vec<_> = store
+            .client_messages()
+            .iter()
+            .map(|message| message.data32().unwrap()[..2].to_vec())
+            .collect();
+        assert_eq!(
+            messages,
+            [vec![0, above.resource_id()], vec![2, above.resource_id()]]
+        );
+    }
+}
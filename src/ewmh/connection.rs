@@ -1,3 +1,12 @@
+use std::convert::TryFrom;
+use std::sync::{Arc, OnceLock};
+
+use crate::atoms::AtomCache;
+#[cfg(feature = "async")]
+use crate::backend::PollBackend;
+use crate::backend::{Backend, VoidCookieChecked};
+use crate::ewmh::batch::{EwmhBatch, EwmhBatchCookies};
+
 use crate::ewmh::atoms::Atoms;
 use crate::ewmh::traits::{
     EwmhPropertyCookieChecked, EwmhPropertyCookieUnchecked, EwmhPropertyRequestUnchecked,
@@ -18,21 +27,106 @@ use crate::ewmh::traits::{
 /// `ewmh` requests and store additional data on it. Concretely, this mostly
 /// means interning atoms.
 pub struct Connection<'a> {
-    pub(crate) con: &'a xcb::Connection,
+    pub(crate) con: &'a dyn Backend,
+
+    /// Root window of the screen the connection was created for
+    root: xcb::x::Window,
 
-    /// Interned [`Atoms`] for the `ewmh` protocol
-    pub atoms: Atoms,
+    /// Interned atoms, possibly shared with other connections
+    cache: Arc<AtomCache>,
+
+    /// [`Atoms`] of the `ewmh` protocol, interned on first access
+    atoms: OnceLock<Atoms>,
 }
 
 #[allow(dead_code)]
 impl<'a> Connection<'a> {
-    pub fn connect(xcb_con: &'a xcb::Connection) -> Connection<'a> {
+    /// Wrap `backend` (usually an [`xcb::Connection`]) for the first screen without any
+    /// round-trip
+    ///
+    /// Atoms are interned on first use.
+    ///
+    /// # Panics
+    ///
+    /// If the X server has no screen. Use [`Connection::try_connect`] to handle this.
+    pub fn connect(backend: &'a dyn Backend) -> Connection<'a> {
+        Connection::try_connect(backend, 0).unwrap()
+    }
+
+    /// Wrap `backend` for `screen` without any round-trip
+    ///
+    /// `screen` is usually the preferred screen returned by [`xcb::Connection::connect`].
+    /// Fails with [`crate::Error::NoSuchScreen`] if the X server has no such screen.
+    pub fn try_connect(backend: &'a dyn Backend, screen: i32) -> crate::Result<Connection<'a>> {
+        Ok(Connection::with_cache(
+            backend,
+            backend.screen_root(screen)?,
+            Arc::new(AtomCache::new()),
+        ))
+    }
+
+    /// Wrap `backend` for the first screen and intern all [`Atoms`] up front in one round-trip
+    pub fn connect_eager(backend: &'a dyn Backend) -> crate::Result<Connection<'a>> {
+        let con = Connection::try_connect(backend, 0)?;
+        con.atoms()?;
+        Ok(con)
+    }
+
+    pub(crate) fn with_cache(
+        backend: &'a dyn Backend,
+        root: xcb::x::Window,
+        cache: Arc<AtomCache>,
+    ) -> Connection<'a> {
         Connection {
-            atoms: Atoms::intern(xcb_con),
-            con: xcb_con,
+            con: backend,
+            root,
+            cache,
+            atoms: OnceLock::new(),
         }
     }
 
+    /// Root window of the screen this connection was created for
+    pub fn root(&self) -> xcb::x::Window {
+        self.root
+    }
+
+    /// All [`Atoms`] of the `ewmh` protocol
+    ///
+    /// The first call interns all of them in one round-trip.
+    pub fn atoms(&self) -> crate::Result<&Atoms> {
+        if let Some(atoms) = self.atoms.get() {
+            return Ok(atoms);
+        }
+
+        let atoms = Atoms::intern(self.con, &self.cache)?;
+        Ok(self.atoms.get_or_init(|| atoms))
+    }
+
+    pub(crate) fn cache(&self) -> &AtomCache {
+        &self.cache
+    }
+
+    /// The atom for `name`, interned on first use
+    pub fn atom(&self, name: &str) -> crate::Result<xcb::x::Atom> {
+        self.cache.intern(self.con, name)
+    }
+
+    /// The name of `atom`, looked up on first use
+    pub fn atom_name(&self, atom: xcb::x::Atom) -> crate::Result<String> {
+        self.cache.name(self.con, atom)
+    }
+
+    /// The atom for `name` to put into a request
+    ///
+    /// If interning fails the request is built with `ATOM_NONE`. The server rejects it and the
+    /// error surfaces when the request is checked or its reply is retrieved.
+    ///
+    /// Only use this for property requests. The server accepts a client message of type
+    /// `ATOM_NONE`, so client messages intern their atoms with [`Self::atom`] instead.
+    pub(crate) fn request_atom(&self, name: &str) -> xcb::x::Atom {
+        self.atom(name).unwrap_or(xcb::x::ATOM_NONE)
+    }
+
     pub fn send_request<'b, R>(&self, request: &'b R) -> R::EwmhCookie
     where
         R: EwmhRequest<'b>,
@@ -40,7 +134,7 @@ impl<'a> Connection<'a> {
         request.send(self)
     }
 
-    pub fn send_request_checked<'b, R>(&self, request: &'b R) -> xcb::VoidCookieChecked
+    pub fn send_request_checked<'b, R>(&self, request: &'b R) -> VoidCookieChecked
     where
         R: EwmhVoidRequestChecked<'b>,
     {
@@ -54,50 +148,233 @@ impl<'a> Connection<'a> {
         request.send(self)
     }
 
-    pub fn send_and_check_request<'b, R>(&self, request: &'b R) -> xcb::ProtocolResult<()>
+    pub fn send_and_check_request<'b, R>(&self, request: &'b R) -> crate::Result<()>
     where
         R: EwmhVoidRequestChecked<'b>,
     {
         self.con.check_request(request.send(self))
     }
 
-    pub fn wait_for_reply<C>(&self, cookie: C) -> xcb::Result<C::Reply>
+    pub fn wait_for_reply<C>(&self, cookie: C) -> crate::Result<C::Reply>
+    where
+        C: EwmhPropertyCookieChecked,
+    {
+        let property = self.con.wait_for_property(cookie.inner())?;
+        C::Reply::try_from(property)
+    }
+
+    /// Like [`Connection::wait_for_reply`] but a property that is not set on the window is
+    /// `None` instead of [`crate::Error::PropertyMissing`].
+    ///
+    /// A property that is set to an empty list is still `Some`.
+    pub fn wait_for_optional_reply<C>(&self, cookie: C) -> crate::Result<Option<C::Reply>>
     where
         C: EwmhPropertyCookieChecked,
     {
-        let xcb_reply = self.con.wait_for_reply(cookie.inner());
-        xcb_reply.map(|reply| reply.into())
+        match self.wait_for_reply(cookie) {
+            Ok(reply) => Ok(Some(reply)),
+            Err(crate::Error::PropertyMissing) => Ok(None),
+            Err(e) => Err(e),
+        }
     }
 
-    pub fn wait_for_reply_unchecked<C>(&self, cookie: C) -> xcb::ConnResult<Option<C::Reply>>
+    pub fn wait_for_reply_unchecked<C>(&self, cookie: C) -> crate::Result<Option<C::Reply>>
     where
         C: EwmhPropertyCookieUnchecked,
     {
-        let xcb_reply = self.con.wait_for_reply_unchecked(cookie.inner());
-        xcb_reply.map(|reply| reply.map(|reply| reply.into()))
+        let property = self.con.wait_for_property_unchecked(cookie.inner())?;
+        property.map(C::Reply::try_from).transpose()
+    }
+
+    /// Queue all requests of `batch` without waiting for any reply
+    ///
+    /// See [`crate::ewmh::EwmhBatch`] for what makes a batch.
+    pub fn send_batch<'b, B>(&self, batch: &'b B) -> B::Cookies
+    where
+        B: EwmhBatch<'b> + ?Sized,
+    {
+        batch.send(self)
+    }
+
+    /// Wait for the replies of a batch sent by [`Connection::send_batch`]
+    ///
+    /// Each reply is decoded on its own, so one missing property does not fail the batch.
+    pub fn wait_for_batch<C>(&self, cookies: C) -> C::Replies
+    where
+        C: EwmhBatchCookies,
+    {
+        cookies.wait(self)
     }
 
-    pub fn check_request(&self, cookie: xcb::VoidCookieChecked) -> xcb::ProtocolResult<()> {
+    pub fn check_request(&self, cookie: VoidCookieChecked) -> crate::Result<()> {
         self.con.check_request(cookie)
     }
 }
 
+/// A [`Connection`] over a [`PollBackend`] that can wait for replies without blocking
+///
+/// It derefs to [`Connection`] for everything else. Backends that cannot poll for a reply, like
+/// `X11rbBackend`, are rejected at compile time.
+#[cfg(feature = "async")]
+pub struct AsyncConnection<'a> {
+    con: Connection<'a>,
+    backend: &'a dyn PollBackend,
+}
+
+#[cfg(feature = "async")]
+impl<'a> AsyncConnection<'a> {
+    /// Like [`Connection::connect`]
+    pub fn connect<B: PollBackend>(backend: &'a B) -> AsyncConnection<'a> {
+        AsyncConnection::try_connect(backend, 0).unwrap()
+    }
+
+    /// Like [`Connection::try_connect`]
+    pub fn try_connect<B: PollBackend>(
+        backend: &'a B,
+        screen: i32,
+    ) -> crate::Result<AsyncConnection<'a>> {
+        Ok(AsyncConnection {
+            con: Connection::try_connect(backend, screen)?,
+            backend,
+        })
+    }
+
+    /// Like [`Connection::send_request`] but also flushes the request to the server
+    pub async fn send_request_async<'b, R>(&self, request: &'b R) -> crate::Result<R::EwmhCookie>
+    where
+        R: EwmhRequest<'b>,
+    {
+        let cookie = request.send(&self.con);
+        self.backend.flush()?;
+        Ok(cookie)
+    }
+
+    /// Like [`Connection::wait_for_reply`] but does not block
+    ///
+    /// `readable` is called whenever the reply has not arrived yet. It must return a future that
+    /// resolves once the file descriptor of the [`xcb::Connection`] (see
+    /// [`std::os::unix::io::AsRawFd`]) is readable. With tokio, for example, this is
+    /// `AsyncFd::readable` plus clearing the readiness.
+    pub async fn wait_for_reply_async<C, R, F>(
+        &self,
+        cookie: C,
+        readable: R,
+    ) -> crate::Result<C::Reply>
+    where
+        C: EwmhPropertyCookieChecked,
+        R: FnMut() -> F,
+        F: std::future::Future<Output = std::io::Result<()>>,
+    {
+        let property = crate::util::poll_for_reply(self.backend, &cookie.inner(), readable).await?;
+        C::Reply::try_from(property)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'a> std::ops::Deref for AsyncConnection<'a> {
+    type Target = Connection<'a>;
+
+    fn deref(&self) -> &Connection<'a> {
+        &self.con
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    #[test]
+    #[cfg(feature = "mock")]
+    fn try_connect_screen() {
+        let store = crate::mock::PropertyStore::new();
+
+        let ewmh_con = crate::ewmh::Connection::try_connect(&store, 0).unwrap();
+        assert_eq!(ewmh_con.root(), store.root());
+        for screen in [-1, 1] {
+            assert!(matches!(
+                crate::ewmh::Connection::try_connect(&store, screen),
+                Err(crate::Error::NoSuchScreen(s)) if s == screen
+            ));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "mock")]
+    fn batch() {
+        use crate::Property;
+        use xcb::Xid;
+
+        let store = crate::mock::PropertyStore::new();
+        let active = store.atom("_NET_ACTIVE_WINDOW");
+        store.set_property(
+            store.root(),
+            "_NET_SUPPORTED",
+            Property::format32(xcb::x::ATOM_ATOM, vec![active.resource_id()]),
+        );
+        store.set_property(
+            store.root(),
+            "_NET_NUMBER_OF_DESKTOPS",
+            Property::format32(xcb::x::ATOM_CARDINAL, vec![4]),
+        );
+        let ewmh_con = crate::ewmh::Connection::connect(&store);
+
+        let (supported, desktops, current) = ewmh_con.wait_for_batch(ewmh_con.send_batch(&(
+            crate::ewmh::proto::GetSupported,
+            crate::ewmh::proto::GetNumberOfDesktops,
+            crate::ewmh::proto::GetCurrentDesktop,
+        )));
+        assert_eq!(supported.unwrap().atoms, vec![active]);
+        assert_eq!(desktops.unwrap().desktops, 4);
+        assert!(matches!(current, Err(crate::Error::PropertyMissing)));
+    }
+
+    #[cfg(all(feature = "async", feature = "mock"))]
+    #[test]
+    fn wait_for_reply_async() {
+        use crate::Property;
+        use std::future::Future;
+        use std::task::{Context, Poll, Waker};
+        use xcb::Xid;
+
+        let store = crate::mock::PropertyStore::new();
+        let active = store.atom("_NET_ACTIVE_WINDOW");
+        store.set_property(
+            store.root(),
+            "_NET_SUPPORTED",
+            Property::format32(xcb::x::ATOM_ATOM, vec![active.resource_id()]),
+        );
+        let ewmh_con = crate::ewmh::AsyncConnection::connect(&store);
+
+        // Busy-polling stand-in for a reactor
+        let mut future = Box::pin(async {
+            let cookie = ewmh_con
+                .send_request_async(&crate::ewmh::proto::GetSupported)
+                .await
+                .unwrap();
+            ewmh_con
+                .wait_for_reply_async(cookie, || std::future::ready(Ok(())))
+                .await
+        });
+
+        let mut cx = Context::from_waker(Waker::noop());
+        let reply = loop {
+            if let Poll::Ready(reply) = future.as_mut().poll(&mut cx) {
+                break reply;
+            }
+        };
+        assert_eq!(reply.unwrap().atoms, vec![active]);
+    }
+
     #[test]
     fn supported_atoms_list() {
         let xcb_con = xcb::Connection::connect(Option::None).unwrap().0;
         let ewmh_con = crate::ewmh::Connection::connect(&xcb_con);
 
-        let request = crate::ewmh::proto::GetSupported;
-        let cookie = ewmh_con.send_request(&request);
-        let reply = ewmh_con.wait_for_reply(cookie);
-        println!("{:?}", reply);
-
-        for atom in reply.unwrap().atoms {
-            let cookie = xcb_con.send_request(&xcb::x::GetAtomName { atom: atom });
+        let supported = crate::ewmh::SupportedHints::fetch(&ewmh_con).unwrap();
+        for hint in supported.hints() {
+            println!("{}", hint.name());
+        }
 
-            println!("{}", xcb_con.wait_for_reply(cookie).unwrap().name());
+        for atom in supported.unknown() {
+            println!("{}", ewmh_con.atom_name(*atom).unwrap());
         }
     }
     //
@@ -218,7 +495,7 @@ mod tests {
 
         let request = crate::ewmh::proto::SetDesktopNames::new(vec!["A", "B", "Z"]);
         let cookie = ewmh_con.send_request_checked(&request);
-        let reply = xcb_con.check_request(cookie);
+        let reply = ewmh_con.check_request(cookie);
         println!("{:?}", reply);
     }
     //
@@ -303,9 +580,9 @@ mod tests {
         let xcb_con = xcb::Connection::connect(Option::None).unwrap().0;
         let ewmh_con = crate::ewmh::Connection::connect(&xcb_con);
 
-        let request = crate::ewmh::proto::SetShowingDesktop::new(&ewmh_con, true);
+        let request = crate::ewmh::proto::SetShowingDesktop::new(&ewmh_con, true).unwrap();
         let cookie = ewmh_con.send_request_checked(&request);
-        let reply = xcb_con.check_request(cookie);
+        let reply = ewmh_con.check_request(cookie);
         println!("{:?}", reply);
     }
 
@@ -402,8 +679,8 @@ mod tests {
     //     let request = crate::ewmh::proto::SetWmWindowType::new(
     //         window,
     //         vec![
-    //             ewmh_con.atoms._NET_WM_WINDOW_TYPE_UTILITY,
-    //             ewmh_con.atoms._NET_WM_WINDOW_TYPE_SPLASH,
+    //             ewmh_con.atoms().unwrap()._NET_WM_WINDOW_TYPE_UTILITY,
+    //             ewmh_con.atoms().unwrap()._NET_WM_WINDOW_TYPE_SPLASH,
     //         ],
     //     );
     //
@@ -440,8 +717,8 @@ mod tests {
     //     let request = crate::ewmh::proto::SetWmState::new(
     //         window,
     //         vec![
-    //             ewmh_con.atoms._NET_WM_STATE_STICKY,
-    //             ewmh_con.atoms._NET_WM_STATE_DEMANDS_ATTENTION,
+    //             ewmh_con.atoms().unwrap()._NET_WM_STATE_STICKY,
+    //             ewmh_con.atoms().unwrap()._NET_WM_STATE_DEMANDS_ATTENTION,
     //         ],
     //     );
     //
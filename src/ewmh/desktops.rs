@@ -0,0 +1,290 @@
+//! Desktop management for pagers
+//!
+//! [`Desktops`] reads the desktop properties of the root window in one round-trip and changes
+//! them with the root client messages and [`SetDesktopNames`]. `_NET_DESKTOP_NAMES` may name
+//! more desktops than there are, the extra names are kept for desktops added later. Only
+//! removing a desktop drops its name.
+
+use crate::ewmh::proto::{
+    GetCurrentDesktop, GetDesktopNames, GetNumberOfDesktops, GetShowingDesktop, SendCurrentDesktop,
+    SendNumberOfDesktops, SetDesktopNames, SetShowingDesktop,
+};
+use crate::ewmh::Connection;
+use crate::util::optional;
+use crate::{Error, Result};
+
+/// The desktops of the window manager and the actions of a pager on them
+///
+/// The state is read once by [`Desktops::load`]. Actions update it to what they asked for, so
+/// several actions can follow each other without a round-trip. The window manager may refuse a
+/// request though (e.g. to remove a desktop that still has windows), [`Desktops::reload`] reads
+/// what it actually did.
+pub struct Desktops<'a> {
+    con: &'a Connection<'a>,
+    count: u32,
+    current: Option<u32>,
+    names: Vec<String>,
+    showing_desktop: bool,
+}
+
+impl<'a> Desktops<'a> {
+    /// Fetch the number, names and current desktop in one round-trip
+    ///
+    /// Properties that are not set count as no desktops, no names and not showing the desktop.
+    pub fn load(con: &'a Connection<'a>) -> Result<Desktops<'a>> {
+        let mut desktops = Desktops {
+            con,
+            count: 0,
+            current: None,
+            names: vec![],
+            showing_desktop: false,
+        };
+        desktops.reload()?;
+
+        Ok(desktops)
+    }
+
+    /// Fetch the state again, e.g. after the window manager handled the actions
+    pub fn reload(&mut self) -> Result<()> {
+        let (count, current, names, showing_desktop) =
+            self.con.wait_for_batch(self.con.send_batch(&(
+                GetNumberOfDesktops,
+                GetCurrentDesktop,
+                GetDesktopNames,
+                GetShowingDesktop,
+            )));
+
+        self.count = optional(count)?.map_or(0, |r| r.desktops);
+        self.current = optional(current)?.map(|r| r.desktop);
+        self.names = optional(names)?.map_or(vec![], |r| r.names);
+        self.showing_desktop = optional(showing_desktop)?.is_some_and(|r| r.is_showing_desktop);
+
+        Ok(())
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn current(&self) -> Option<u32> {
+        self.current
+    }
+
+    /// Name of `desktop`, `None` if it does not exist or has no name
+    pub fn name(&self, desktop: u32) -> Option<&str> {
+        if desktop >= self.count {
+            return None;
+        }
+
+        self.names
+            .get(desktop as usize)
+            .map(String::as_str)
+            .filter(|name| !name.is_empty())
+    }
+
+    /// One name per desktop, empty for desktops without a name
+    pub fn names(&self) -> Vec<&str> {
+        (0..self.count)
+            .map(|desktop| self.name(desktop).unwrap_or(""))
+            .collect()
+    }
+
+    pub fn is_showing_desktop(&self) -> bool {
+        self.showing_desktop
+    }
+
+    /// Make `desktop` the current desktop
+    pub fn switch_to(&mut self, desktop: u32) -> Result<()> {
+        self.check(desktop)?;
+        self.con
+            .send_and_check_request(&SendCurrentDesktop::new(self.con, desktop)?)?;
+        self.current = Some(desktop);
+
+        Ok(())
+    }
+
+    pub fn rename(&mut self, desktop: u32, name: &str) -> Result<()> {
+        self.check(desktop)?;
+        self.set_names(self.names_with(desktop, name))
+    }
+
+    /// Append a desktop named `name`
+    ///
+    /// The name is set first, so the desktop has it when it appears.
+    pub fn add(&mut self, name: &str) -> Result<()> {
+        self.set_names(self.names_with(self.count, name))?;
+        self.set_count(self.count + 1)
+    }
+
+    /// Remove the last desktop
+    ///
+    /// `ewmh` can only change the number of desktops, so the last one is removed. Window managers
+    /// move its windows to the new last desktop or refuse the request.
+    pub fn remove(&mut self) -> Result<()> {
+        if self.count == 0 {
+            return Err(Error::NoSuchDesktop(0));
+        }
+
+        let count = self.count - 1;
+        self.set_count(count)?;
+
+        let mut names = self.names.clone();
+        names.truncate(count as usize);
+        self.set_names(names)
+    }
+
+    /// Enter or leave the "show desktop" mode
+    pub fn set_showing_desktop(&mut self, show: bool) -> Result<()> {
+        self.con
+            .send_and_check_request(&SetShowingDesktop::new(self.con, show)?)?;
+        self.showing_desktop = show;
+
+        Ok(())
+    }
+
+    pub fn toggle_showing_desktop(&mut self) -> Result<()> {
+        self.set_showing_desktop(!self.showing_desktop)
+    }
+
+    fn check(&self, desktop: u32) -> Result<()> {
+        if desktop < self.count {
+            Ok(())
+        } else {
+            Err(Error::NoSuchDesktop(desktop))
+        }
+    }
+
+    /// The names with `desktop` named `name`, padded with empty names up to it
+    fn names_with(&self, desktop: u32, name: &str) -> Vec<String> {
+        let mut names = self.names.clone();
+        if names.len() <= desktop as usize {
+            names.resize(desktop as usize + 1, String::new());
+        }
+        names[desktop as usize] = name.to_owned();
+        names
+    }
+
+    fn set_names(&mut self, names: Vec<String>) -> Result<()> {
+        self.con.send_and_check_request(&SetDesktopNames::new(
+            names.iter().map(String::as_str).collect(),
+        ))?;
+        self.names = names;
+
+        Ok(())
+    }
+
+    fn set_count(&mut self, count: u32) -> Result<()> {
+        self.con
+            .send_and_check_request(&SendNumberOfDesktops::new(self.con, count)?)?;
+        self.count = count;
+        if self.current.is_some_and(|current| current >= count) {
+            self.current = count.checked_sub(1);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::mock::PropertyStore;
+    use crate::Property;
+
+    fn assert_names(store: &PropertyStore, names: &[u8]) {
+        assert_eq!(
+            store.property(store.root(), "_NET_DESKTOP_NAMES"),
+            Some(Property::format8(store.atom("UTF8_STRING"), names.to_vec()))
+        );
+    }
+
+    #[test]
+    fn names_follow_the_count() {
+        let store = PropertyStore::new();
+        let cardinal = |value| Property::format32(xcb::x::ATOM_CARDINAL, vec![value]);
+        store.set_property(store.root(), "_NET_NUMBER_OF_DESKTOPS", cardinal(3));
+        store.set_property(store.root(), "_NET_CURRENT_DESKTOP", cardinal(2));
+        store.set_property(
+            store.root(),
+            "_NET_DESKTOP_NAMES",
+            Property::format8(store.atom("UTF8_STRING"), b"one\0\0three\0four\0".to_vec()),
+        );
+        let ewmh_con = Connection::connect(&store);
+
+        let mut desktops = Desktops::load(&ewmh_con).unwrap();
+        assert_eq!(desktops.count(), 3);
+        assert_eq!(desktops.current(), Some(2));
+        assert_eq!(desktops.names(), ["one", "", "three"]);
+        assert_eq!(desktops.name(1), None);
+        assert!(!desktops.is_showing_desktop());
+
+        // The name of a desktop that does not exist yet is kept
+        desktops.rename(1, "two").unwrap();
+        assert_names(&store, b"one\0two\0three\0four\0");
+
+        desktops.add("new").unwrap();
+        assert_names(&store, b"one\0two\0three\0new\0");
+        assert_eq!(desktops.count(), 4);
+
+        desktops.remove().unwrap();
+        desktops.remove().unwrap();
+        assert_names(&store, b"one\0two\0");
+        assert_eq!(desktops.names(), ["one", "two"]);
+        assert_eq!(desktops.current(), Some(1));
+
+        assert!(matches!(
+            desktops.switch_to(2),
+            Err(Error::NoSuchDesktop(2))
+        ));
+        assert!(matches!(
+            desktops.rename(5, "five"),
+            Err(Error::NoSuchDesktop(5))
+        ));
+        desktops.switch_to(0).unwrap();
+        desktops.toggle_showing_desktop().unwrap();
+
+        let messages: Vec<_> = store
+            .client_messages()
+            .iter()
+            .map(|m| m.data32().unwrap()[0])
+            .collect();
+        // add, remove, remove, switch, show desktop
+        assert_eq!(messages, [4, 3, 2, 0, 1]);
+    }
+
+    #[test]
+    fn no_window_manager() {
+        let store = PropertyStore::new();
+        let ewmh_con = Connection::connect(&store);
+
+        let mut desktops = Desktops::load(&ewmh_con).unwrap();
+        assert_eq!(desktops.count(), 0);
+        assert_eq!(desktops.current(), None);
+        assert!(matches!(desktops.remove(), Err(Error::NoSuchDesktop(0))));
+
+        desktops.add("first").unwrap();
+        assert_names(&store, b"first\0");
+        assert_eq!(desktops.names(), ["first"]);
+    }
+
+    #[test]
+    fn rename_pads_missing_names() {
+        let store = PropertyStore::new();
+        store.set_property(
+            store.root(),
+            "_NET_NUMBER_OF_DESKTOPS",
+            Property::format32(xcb::x::ATOM_CARDINAL, vec![3]),
+        );
+        store.set_property(
+            store.root(),
+            "_NET_DESKTOP_NAMES",
+            Property::format8(store.atom("UTF8_STRING"), b"one\0".to_vec()),
+        );
+        let ewmh_con = Connection::connect(&store);
+
+        let mut desktops = Desktops::load(&ewmh_con).unwrap();
+        desktops.rename(2, "three").unwrap();
+        assert_names(&store, b"one\0\0three\0");
+        assert_eq!(desktops.names(), ["one", "", "three"]);
+    }
+}
@@ -1,13 +1,32 @@
 //! see: <https://specifications.freedesktop.org/wm-spec/1.5/ar01s03.html#idm45539547193552>
 
 #[macro_use]
-mod traits;
+pub(crate) mod traits;
+pub use traits::{
+    EwmhCookie, EwmhPropertyCookieChecked, EwmhPropertyCookieUnchecked, EwmhPropertyReply,
+    EwmhPropertyRequestUnchecked, EwmhRequest, EwmhVoidRequestChecked,
+};
 
-mod atoms;
+pub(crate) mod atoms;
+mod batch;
+pub mod compliance;
 mod connection;
+mod desktops;
+mod state;
+mod supported;
+mod watch;
+mod window;
 
 pub use atoms::Atoms;
+pub use batch::{EwmhBatch, EwmhBatchCookies};
+#[cfg(feature = "async")]
+pub use connection::AsyncConnection;
 pub use connection::Connection;
+pub use desktops::Desktops;
+pub use state::{EwmhState, EwmhStateChange};
+pub use supported::{Hint, SupportedHints};
+pub use watch::{EwmhEvent, EwmhProperty, Watcher};
+pub use window::{WindowHandle, SOURCE_APPLICATION, SOURCE_PAGER};
 
 pub mod proto;
 pub use proto::*;
@@ -4,26 +4,34 @@
 
 #![allow(dead_code)]
 
+use std::convert::TryFrom;
 use xcb::Xid;
 
-use crate::ewmh::proto::util::{strings_to_x_buffer, x_buffer_to_strings};
 use crate::ewmh::traits::*;
 use crate::ewmh::Connection;
+use crate::util::{property_value, strings_to_x_buffer, x_buffer_to_strings};
+use crate::{Error, Property, Result};
 
 use paste::paste; // Needed for macros
 
 // _NET_WM_NAME, UTF8_STRING
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetWmNameReply {
     pub name: String,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetWmNameReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetWmNameReply {
-            name: x_buffer_to_strings(reply.value::<u8>())[0].to_owned(),
-        }
+impl TryFrom<Property> for GetWmNameReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        Ok(GetWmNameReply {
+            name: x_buffer_to_strings(property_value(&reply, 0)?)?
+                .into_iter()
+                .next()
+                .unwrap_or_default(),
+        })
     }
 }
 
@@ -63,15 +71,21 @@ ewmh_set_property! {
 // _NET_WM_VISIBLE_NAME, UTF8_STRING
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetWmVisibleNameReply {
     pub name: String,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetWmVisibleNameReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetWmVisibleNameReply {
-            name: x_buffer_to_strings(reply.value::<u8>())[0].to_owned(),
-        }
+impl TryFrom<Property> for GetWmVisibleNameReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        Ok(GetWmVisibleNameReply {
+            name: x_buffer_to_strings(property_value(&reply, 0)?)?
+                .into_iter()
+                .next()
+                .unwrap_or_default(),
+        })
     }
 }
 
@@ -88,15 +102,21 @@ ewmh_get_property! {
 // _NET_WM_ICON_NAME, UTF8_STRING
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetWmIconNameReply {
     pub name: String,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetWmIconNameReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetWmIconNameReply {
-            name: x_buffer_to_strings(reply.value::<u8>())[0].to_owned(),
-        }
+impl TryFrom<Property> for GetWmIconNameReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        Ok(GetWmIconNameReply {
+            name: x_buffer_to_strings(property_value(&reply, 0)?)?
+                .into_iter()
+                .next()
+                .unwrap_or_default(),
+        })
     }
 }
 
@@ -113,15 +133,21 @@ ewmh_get_property! {
 // _NET_WM_VISIBLE_ICON_NAME, UTF8_STRING
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetWmVisibleIconNameReply {
     pub name: String,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetWmVisibleIconNameReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetWmVisibleIconNameReply {
-            name: x_buffer_to_strings(reply.value::<u8>())[0].to_owned(),
-        }
+impl TryFrom<Property> for GetWmVisibleIconNameReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        Ok(GetWmVisibleIconNameReply {
+            name: x_buffer_to_strings(property_value(&reply, 0)?)?
+                .into_iter()
+                .next()
+                .unwrap_or_default(),
+        })
     }
 }
 
@@ -138,15 +164,18 @@ ewmh_get_property! {
 // _NET_WM_DESKTOP, CARDINAL/32
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetWmDesktopReply {
     pub desktop: u32,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetWmDesktopReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetWmDesktopReply {
-            desktop: reply.value::<u32>()[0],
-        }
+impl TryFrom<Property> for GetWmDesktopReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        let value = property_value::<u32>(&reply, 1)?;
+
+        Ok(GetWmDesktopReply { desktop: value[0] })
     }
 }
 
@@ -186,14 +215,19 @@ pub struct SendWmDesktop {
 }
 
 impl SendWmDesktop {
-    pub fn new(connection: &Connection, desktop: u32, source_indication: u32) -> SendWmDesktop {
-        SendWmDesktop {
+    pub fn new(
+        connection: &Connection,
+        window: xcb::x::Window,
+        desktop: u32,
+        source_indication: u32,
+    ) -> Result<SendWmDesktop> {
+        Ok(SendWmDesktop {
             client_message: xcb::x::ClientMessageEvent::new(
-                connection.con.get_setup().roots().next().unwrap().root(),
-                connection.atoms._NET_WM_DESKTOP,
+                window,
+                connection.atom("_NET_WM_DESKTOP")?,
                 xcb::x::ClientMessageData::Data32([desktop, source_indication, 0x00, 0x00, 0x00]),
             ),
-        }
+        })
     }
 }
 
@@ -205,15 +239,19 @@ ewmh_client_message! {
 // _NET_WM_WINDOW_TYPE, ATOM[]/32
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetWmWindowTypeReply {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::atoms"))]
     pub window_types: Vec<xcb::x::Atom>,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetWmWindowTypeReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetWmWindowTypeReply {
-            window_types: reply.value::<xcb::x::Atom>().into(),
-        }
+impl TryFrom<Property> for GetWmWindowTypeReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        Ok(GetWmWindowTypeReply {
+            window_types: property_value::<xcb::x::Atom>(&reply, 0)?.into(),
+        })
     }
 }
 
@@ -253,15 +291,19 @@ ewmh_set_property! {
 // _NET_WM_STATE, ATOM[]/32
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetWmStateReply {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::atoms"))]
     pub states: Vec<xcb::x::Atom>,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetWmStateReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetWmStateReply {
-            states: reply.value::<xcb::x::Atom>().into(),
-        }
+impl TryFrom<Property> for GetWmStateReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        Ok(GetWmStateReply {
+            states: property_value::<xcb::x::Atom>(&reply, 0)?.into(),
+        })
     }
 }
 
@@ -274,6 +316,16 @@ ewmh_get_property! {
     reply=GetWmStateReply
 }
 
+/// What a `_NET_WM_STATE` message does with its states
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum WmStateAction {
+    Remove = 0,
+    Add = 1,
+    Toggle = 2,
+}
+
 pub struct SendWmState {
     client_message: xcb::x::ClientMessageEvent,
 }
@@ -282,25 +334,25 @@ impl SendWmState {
     pub fn new(
         connection: &Connection,
         window: xcb::x::Window,
-        action: xcb::x::PropMode,
+        action: WmStateAction,
         states: [xcb::x::Atom; 2],
         source_indication: u32,
-    ) -> SendWmState {
+    ) -> Result<SendWmState> {
         let data = [
-            unsafe { std::mem::transmute::<_, u32>(action) },
+            action as u32,
             states[0].resource_id(),
             states[1].resource_id(),
             source_indication,
             0x00,
         ];
 
-        SendWmState {
+        Ok(SendWmState {
             client_message: xcb::x::ClientMessageEvent::new(
                 window,
-                connection.atoms._NET_WM_DESKTOP,
+                connection.atom("_NET_WM_STATE")?,
                 xcb::x::ClientMessageData::Data32(data),
             ),
-        }
+        })
     }
 }
 
@@ -308,3 +360,446 @@ ewmh_client_message! {
     request=SendWmState{destination: root}
 }
 // }}}
+
+// WM_CHANGE_STATE, IconicState
+// {{{
+/// Ask the window manager to iconify (minimize) `window`
+///
+/// This is the icccm `WM_CHANGE_STATE` client message with `IconicState`, the only state it
+/// allows. `_NET_WM_STATE_HIDDEN` is managed by the window manager, the ewmh specification asks
+/// clients to minimize windows with this message instead.
+pub struct SendWmChangeState {
+    client_message: xcb::x::ClientMessageEvent,
+}
+
+impl SendWmChangeState {
+    pub fn new(connection: &Connection, window: xcb::x::Window) -> Result<SendWmChangeState> {
+        // `IconicState` of `WM_STATE`
+        const ICONIC_STATE: u32 = 3;
+
+        Ok(SendWmChangeState {
+            client_message: xcb::x::ClientMessageEvent::new(
+                window,
+                connection.atom("WM_CHANGE_STATE")?,
+                xcb::x::ClientMessageData::Data32([ICONIC_STATE, 0x00, 0x00, 0x00, 0x00]),
+            ),
+        })
+    }
+}
+
+ewmh_client_message! {
+    request=SendWmChangeState{destination: root}
+}
+// }}}
+
+// _NET_WM_ALLOWED_ACTIONS, ATOM[]/32
+// {{{
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GetWmAllowedActionsReply {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::atoms"))]
+    pub actions: Vec<xcb::x::Atom>,
+}
+
+impl TryFrom<Property> for GetWmAllowedActionsReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        Ok(GetWmAllowedActionsReply {
+            actions: property_value::<xcb::x::Atom>(&reply, 0)?.into(),
+        })
+    }
+}
+
+ewmh_get_property! {
+    request=GetWmAllowedActions{
+        window: client,
+        property: _NET_WM_ALLOWED_ACTIONS,
+        xtype: ATOM_ATOM
+    },
+    reply=GetWmAllowedActionsReply
+}
+// }}}
+
+// _NET_WM_ICON, CARDINAL[][2+n]/32
+// {{{
+/// One icon of `_NET_WM_ICON`
+///
+/// `data` holds `width * height` ARGB pixels in row-major order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WmIcon {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u32>,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GetWmIconReply {
+    pub icons: Vec<WmIcon>,
+}
+
+impl GetWmIconReply {
+    /// The smallest icon at least `size` pixels wide, or the largest icon if none is
+    pub fn best_for(&self, size: u32) -> Option<&WmIcon> {
+        self.icons
+            .iter()
+            .filter(|icon| icon.width >= size)
+            .min_by_key(|icon| icon.width)
+            .or_else(|| self.icons.iter().max_by_key(|icon| icon.width))
+    }
+}
+
+impl WmIcon {
+    /// Encode `icons` as the value of `_NET_WM_ICON`
+    pub fn as_data(icons: &[WmIcon]) -> Vec<u32> {
+        let mut data = vec![];
+
+        for icon in icons {
+            data.push(icon.width);
+            data.push(icon.height);
+            data.extend(&icon.data);
+        }
+
+        data
+    }
+
+    /// Decode the value of `_NET_WM_ICON`
+    ///
+    /// Fails with [`Error::Truncated`] if an icon has fewer pixels than its size says. A single
+    /// trailing element that cannot start an icon is ignored.
+    pub fn from_data(mut value: &[u32]) -> Result<Vec<WmIcon>> {
+        let mut icons = vec![];
+
+        while value.len() >= 2 {
+            let (width, height) = (value[0], value[1]);
+            let len = (width as usize).saturating_mul(height as usize);
+
+            if value.len() - 2 < len {
+                return Err(Error::Truncated {
+                    expected: len.saturating_add(2),
+                    actual: value.len(),
+                });
+            }
+
+            icons.push(WmIcon {
+                width,
+                height,
+                data: value[2..2 + len].to_vec(),
+            });
+            value = &value[2 + len..];
+        }
+
+        Ok(icons)
+    }
+}
+
+impl TryFrom<Property> for GetWmIconReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        Ok(GetWmIconReply {
+            icons: WmIcon::from_data(property_value(&reply, 0)?)?,
+        })
+    }
+}
+
+ewmh_get_property! {
+    request=GetWmIcon{
+        window: client,
+        property: _NET_WM_ICON,
+        xtype: ATOM_CARDINAL
+    },
+    reply=GetWmIconReply
+}
+
+pub struct SetWmIcon {
+    window: xcb::x::Window,
+    data: Vec<u32>,
+}
+
+impl SetWmIcon {
+    pub fn new(window: xcb::x::Window, icons: &[WmIcon]) -> SetWmIcon {
+        SetWmIcon {
+            window,
+            data: WmIcon::as_data(icons),
+        }
+    }
+}
+
+ewmh_set_property! {
+    request=SetWmIcon{
+        window: client,
+        property: _NET_WM_ICON,
+        xtype: ATOM_CARDINAL
+    }
+}
+// }}}
+
+// _NET_WM_PID, CARDINAL/32
+// {{{
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GetWmPidReply {
+    pub pid: u32,
+}
+
+impl TryFrom<Property> for GetWmPidReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        let value = property_value::<u32>(&reply, 1)?;
+
+        Ok(GetWmPidReply { pid: value[0] })
+    }
+}
+
+ewmh_get_property! {
+    request=GetWmPid{
+        window: client,
+        property: _NET_WM_PID,
+        xtype: ATOM_CARDINAL
+    },
+    reply=GetWmPidReply
+}
+
+pub struct SetWmPid {
+    window: xcb::x::Window,
+    data: Vec<u32>,
+}
+
+impl SetWmPid {
+    pub fn new(window: xcb::x::Window, pid: u32) -> SetWmPid {
+        SetWmPid {
+            window,
+            data: vec![pid],
+        }
+    }
+}
+
+ewmh_set_property! {
+    request=SetWmPid{
+        window: client,
+        property: _NET_WM_PID,
+        xtype: ATOM_CARDINAL
+    }
+}
+// }}}
+
+// _NET_FRAME_EXTENTS, left, right, top, bottom, CARDINAL[4]/32
+// {{{
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GetFrameExtentsReply {
+    pub left: u32,
+    pub right: u32,
+    pub top: u32,
+    pub bottom: u32,
+}
+
+impl TryFrom<Property> for GetFrameExtentsReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        let value = property_value::<u32>(&reply, 4)?;
+
+        Ok(GetFrameExtentsReply {
+            left: value[0],
+            right: value[1],
+            top: value[2],
+            bottom: value[3],
+        })
+    }
+}
+
+ewmh_get_property! {
+    request=GetFrameExtents{
+        window: client,
+        property: _NET_FRAME_EXTENTS,
+        xtype: ATOM_CARDINAL
+    },
+    reply=GetFrameExtentsReply
+}
+
+pub struct SetFrameExtents {
+    window: xcb::x::Window,
+    data: Vec<u32>,
+}
+
+impl SetFrameExtents {
+    pub fn new(
+        window: xcb::x::Window,
+        left: u32,
+        right: u32,
+        top: u32,
+        bottom: u32,
+    ) -> SetFrameExtents {
+        SetFrameExtents {
+            window,
+            data: vec![left, right, top, bottom],
+        }
+    }
+}
+
+ewmh_set_property! {
+    request=SetFrameExtents{
+        window: client,
+        property: _NET_FRAME_EXTENTS,
+        xtype: ATOM_CARDINAL
+    }
+}
+// }}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xcb::XidNew;
+
+    // `UTF8_STRING` is not predefined, any other atom will do
+    fn utf8_string(data: Vec<u8>) -> Property {
+        Property::format8(xcb::x::Atom::new(400), data)
+    }
+
+    fn cardinals(data: Vec<u32>) -> Property {
+        Property::format32(xcb::x::ATOM_CARDINAL, data)
+    }
+
+    fn window(id: u32) -> xcb::x::Window {
+        xcb::x::Window::new(id)
+    }
+
+    #[test]
+    fn name_round_trip() {
+        for name in ["", "Terminal", "Ünïcödé → ✓"] {
+            let request = SetWmName::new(window(1), name);
+            let reply = GetWmNameReply::try_from(utf8_string(request.data)).unwrap();
+
+            assert_eq!(reply.name, name);
+        }
+    }
+
+    #[test]
+    fn name_malformed() {
+        assert!(matches!(
+            GetWmNameReply::try_from(utf8_string(b"\xc3\x28".to_vec())),
+            Err(Error::InvalidUtf8(_))
+        ));
+        assert!(matches!(
+            GetWmNameReply::try_from(cardinals(vec![1])),
+            Err(Error::WrongFormat {
+                expected: 8,
+                actual: 32
+            })
+        ));
+
+        // Without the trailing NULL
+        let reply = GetWmNameReply::try_from(utf8_string(b"Terminal".to_vec())).unwrap();
+        assert_eq!(reply.name, "Terminal");
+    }
+
+    #[test]
+    fn icon_round_trip() {
+        let icons = vec![
+            WmIcon {
+                width: 2,
+                height: 1,
+                data: vec![0xff00_0000, 0xffff_ffff],
+            },
+            WmIcon {
+                width: 0,
+                height: 0,
+                data: vec![],
+            },
+            WmIcon {
+                width: 1,
+                height: 2,
+                data: vec![1, 2],
+            },
+        ];
+
+        let request = SetWmIcon::new(window(1), &icons);
+        assert_eq!(request.data, WmIcon::as_data(&icons));
+
+        let reply = GetWmIconReply::try_from(cardinals(request.data)).unwrap();
+        assert_eq!(reply.icons, icons);
+        assert_eq!(reply.best_for(2).unwrap().width, 2);
+        assert_eq!(reply.best_for(16).unwrap().width, 2);
+        assert_eq!(WmIcon::from_data(&[]).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn icon_malformed() {
+        assert!(matches!(
+            WmIcon::from_data(&[2, 2, 1, 2, 3]),
+            Err(Error::Truncated {
+                expected: 6,
+                actual: 5
+            })
+        ));
+        assert!(matches!(
+            WmIcon::from_data(&[u32::MAX, u32::MAX]),
+            Err(Error::Truncated { .. })
+        ));
+
+        // A dangling width is ignored
+        let icons = WmIcon::from_data(&[1, 1, 7, 16]).unwrap();
+        assert_eq!(icons.len(), 1);
+        assert_eq!(icons[0].data, vec![7]);
+    }
+
+    #[test]
+    fn cardinals_round_trip() {
+        let desktop = SetWmDesktop::new(window(1), 0xffff_ffff);
+        let reply = GetWmDesktopReply::try_from(cardinals(desktop.data)).unwrap();
+        assert_eq!(reply.desktop, 0xffff_ffff);
+
+        let pid = SetWmPid::new(window(1), 4242);
+        let reply = GetWmPidReply::try_from(cardinals(pid.data)).unwrap();
+        assert_eq!(reply.pid, 4242);
+
+        let extents = SetFrameExtents::new(window(1), 1, 2, 20, 3);
+        let reply = GetFrameExtentsReply::try_from(cardinals(extents.data)).unwrap();
+        assert_eq!(
+            (reply.left, reply.right, reply.top, reply.bottom),
+            (1, 2, 20, 3)
+        );
+    }
+
+    #[test]
+    fn cardinals_malformed() {
+        assert!(matches!(
+            GetWmPidReply::try_from(cardinals(vec![])),
+            Err(Error::Truncated {
+                expected: 1,
+                actual: 0
+            })
+        ));
+        assert!(matches!(
+            GetFrameExtentsReply::try_from(cardinals(vec![1, 2, 3])),
+            Err(Error::Truncated {
+                expected: 4,
+                actual: 3
+            })
+        ));
+        assert!(matches!(
+            GetWmDesktopReply::try_from(Property::missing()),
+            Err(Error::PropertyMissing)
+        ));
+    }
+
+    #[test]
+    fn atoms_round_trip() {
+        let types = vec![xcb::x::Atom::new(400), xcb::x::Atom::new(401)];
+        let request = SetWmWindowType::new(window(1), types.clone());
+        let data = request.data.iter().map(|a| a.resource_id()).collect();
+
+        let reply =
+            GetWmWindowTypeReply::try_from(Property::format32(xcb::x::ATOM_ATOM, data)).unwrap();
+        assert_eq!(reply.window_types, types);
+
+        let reply =
+            GetWmStateReply::try_from(Property::format32(xcb::x::ATOM_ATOM, vec![])).unwrap();
+        assert!(reply.states.is_empty());
+    }
+}
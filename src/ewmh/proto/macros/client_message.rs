@@ -3,9 +3,7 @@ macro_rules! _client_message_base {
         fn xcb_request(&'a self, con: &Connection) -> Self::XcbRequest {
             xcb::x::SendEvent {
                 propagate: false,
-                destination: xcb::x::SendEventDest::Window(
-                    con.con.get_setup().roots().next().unwrap().root(),
-                ),
+                destination: xcb::x::SendEventDest::Window(con.root()),
                 event_mask: xcb::x::EventMask::SUBSTRUCTURE_NOTIFY
                     | xcb::x::EventMask::SUBSTRUCTURE_REDIRECT,
                 event: &self.client_message,
@@ -30,11 +28,14 @@ macro_rules! ewmh_client_message {
     (request=$request:ident{destination: $destination:ident}) => {
         impl<'a> EwmhRequest<'a> for $request {
             type XcbRequest = xcb::x::SendEvent<'a, xcb::x::ClientMessageEvent>;
-            type EwmhCookie = xcb::VoidCookie;
+            type EwmhCookie = crate::backend::VoidCookie;
 
             _client_message_base! {$destination}
 
-            fn convert_cookie(&'a self, xcb_cookie: xcb::VoidCookie) -> Self::EwmhCookie {
+            fn convert_cookie(
+                &'a self,
+                xcb_cookie: crate::backend::VoidCookie,
+            ) -> Self::EwmhCookie {
                 xcb_cookie
             }
         }
@@ -2,13 +2,13 @@ macro_rules! _get_property_cookies {
     (@ewmh_priv $request:ident, $reply:ident) => {
         paste! {
             impl EwmhCookie for [<$request Cookie>] {
-                type XcbCookie = xcb::x::GetPropertyCookie;
+                type XcbCookie = crate::backend::PropertyCookie;
             }
 
             impl EwmhPropertyCookieChecked for [<$request Cookie>] {
                 type Reply = $reply;
 
-                fn inner(self) -> xcb::x::GetPropertyCookie {
+                fn inner(self) -> crate::backend::PropertyCookie {
                     self.0
                 }
             }
@@ -16,7 +16,7 @@ macro_rules! _get_property_cookies {
             impl EwmhPropertyCookieUnchecked for [<$request CookieUnchecked>] {
                 type Reply = $reply;
 
-                fn inner(self) -> xcb::x::GetPropertyCookieUnchecked {
+                fn inner(self) -> crate::backend::PropertyCookieUnchecked {
                     self.0
                 }
             }
@@ -29,9 +29,9 @@ macro_rules! _get_property_request {
         fn xcb_request(&self, con: &Connection) -> xcb::x::GetProperty {
             xcb::x::GetProperty {
                 delete: false,
-                window: con.con.get_setup().roots().next().unwrap().root(),
-                property: con.atoms.$property,
-                r#type: con.atoms.UTF8_STRING,
+                window: con.root(),
+                property: con.request_atom(stringify!($property)),
+                r#type: con.request_atom("UTF8_STRING"),
                 long_offset: 0,
                 long_length: u32::MAX,
             }
@@ -42,8 +42,8 @@ macro_rules! _get_property_request {
         fn xcb_request(&self, con: &Connection) -> xcb::x::GetProperty {
             xcb::x::GetProperty {
                 delete: false,
-                window: con.con.get_setup().roots().next().unwrap().root(),
-                property: con.atoms.$property,
+                window: con.root(),
+                property: con.request_atom(stringify!($property)),
                 r#type: xcb::x::$xtype,
                 long_offset: 0,
                 long_length: u32::MAX,
@@ -56,8 +56,8 @@ macro_rules! _get_property_request {
             xcb::x::GetProperty {
                 delete: false,
                 window: self.0,
-                property: con.atoms.$property,
-                r#type: con.atoms.UTF8_STRING,
+                property: con.request_atom(stringify!($property)),
+                r#type: con.request_atom("UTF8_STRING"),
                 long_offset: 0,
                 long_length: u32::MAX,
             }
@@ -69,7 +69,7 @@ macro_rules! _get_property_request {
             xcb::x::GetProperty {
                 delete: false,
                 window: self.0,
-                property: con.atoms.$property,
+                property: con.request_atom(stringify!($property)),
                 r#type: xcb::x::$xtype,
                 long_offset: 0,
                 long_length: u32::MAX,
@@ -82,15 +82,15 @@ macro_rules! _get_property_structs {
     (@ewmh_priv $request:ident, root) => {
         paste! {
             pub struct $request;
-            pub struct [<$request Cookie>](xcb::x::GetPropertyCookie);
-            pub struct [<$request CookieUnchecked>](xcb::x::GetPropertyCookieUnchecked);
+            pub struct [<$request Cookie>](crate::backend::PropertyCookie);
+            pub struct [<$request CookieUnchecked>](crate::backend::PropertyCookieUnchecked);
         }
     };
     (@ewmh_priv $request:ident, client) => {
         paste! {
             pub struct $request(pub xcb::x::Window);
-            pub struct [<$request Cookie>](xcb::x::GetPropertyCookie);
-            pub struct [<$request CookieUnchecked>](xcb::x::GetPropertyCookieUnchecked);
+            pub struct [<$request Cookie>](crate::backend::PropertyCookie);
+            pub struct [<$request CookieUnchecked>](crate::backend::PropertyCookieUnchecked);
 
             impl $request {
                 fn new(window: xcb::x::Window) -> $request {
@@ -117,7 +117,7 @@ macro_rules! ewmh_get_property {
 
                 _get_property_request! {@ewmh_priv $window, $property, $xtype}
 
-                fn convert_cookie(&'a self, xcb_cookie: xcb::x::GetPropertyCookie) -> Self::EwmhCookie {
+                fn convert_cookie(&'a self, xcb_cookie: crate::backend::PropertyCookie) -> Self::EwmhCookie {
                     [<$request Cookie>](xcb_cookie)
                 }
             }
@@ -126,7 +126,7 @@ macro_rules! ewmh_get_property {
                 paste!{type EwmhCookie = [<$request CookieUnchecked>];}
 
                 #[rustfmt::skip]
-                fn convert_cookie(&self, xcb_cookie: xcb::x::GetPropertyCookieUnchecked) -> Self::EwmhCookie {
+                fn convert_cookie(&self, xcb_cookie: crate::backend::PropertyCookieUnchecked) -> Self::EwmhCookie {
                     [<$request CookieUnchecked>](xcb_cookie)
                 }
 
@@ -3,9 +3,9 @@ macro_rules! _set_property_base {
         fn xcb_request(&'a self, con: &Connection) -> xcb::x::ChangeProperty<'a, u8> {
             xcb::x::ChangeProperty {
                 mode: xcb::x::PropMode::Replace,
-                window: con.con.get_setup().roots().next().unwrap().root(),
-                property: con.atoms.$property,
-                r#type: con.atoms.UTF8_STRING,
+                window: con.root(),
+                property: con.request_atom(stringify!($property)),
+                r#type: con.request_atom("UTF8_STRING"),
                 data: &self.data,
             }
         }
@@ -15,8 +15,8 @@ macro_rules! _set_property_base {
         fn xcb_request(&'a self, con: &Connection) -> xcb::x::ChangeProperty<'a, u32> {
             xcb::x::ChangeProperty {
                 mode: xcb::x::PropMode::Replace,
-                window: con.con.get_setup().roots().next().unwrap().root(),
-                property: con.atoms.$property,
+                window: con.root(),
+                property: con.request_atom(stringify!($property)),
                 r#type: xcb::x::$xtype,
                 data: &self.data,
             }
@@ -28,8 +28,8 @@ macro_rules! _set_property_base {
             xcb::x::ChangeProperty {
                 mode: xcb::x::PropMode::Replace,
                 window: self.window,
-                property: con.atoms.$property,
-                r#type: con.atoms.UTF8_STRING,
+                property: con.request_atom(stringify!($property)),
+                r#type: con.request_atom("UTF8_STRING"),
                 data: &self.data,
             }
         }
@@ -40,7 +40,7 @@ macro_rules! _set_property_base {
             xcb::x::ChangeProperty {
                 mode: xcb::x::PropMode::Replace,
                 window: self.window,
-                property: con.atoms.$property,
+                property: con.request_atom(stringify!($property)),
                 r#type: xcb::x::ATOM_CARDINAL,
                 data: &self.data,
             }
@@ -52,7 +52,7 @@ macro_rules! _set_property_base {
             xcb::x::ChangeProperty {
                 mode: xcb::x::PropMode::Replace,
                 window: self.window,
-                property: con.atoms.$property,
+                property: con.request_atom(stringify!($property)),
                 r#type: xcb::x::ATOM_ATOM,
                 data: &self.data,
             }
@@ -68,11 +68,14 @@ macro_rules! ewmh_set_property {
      }) => {
         impl<'a> EwmhRequest<'a> for $request {
             type XcbRequest = xcb::x::ChangeProperty<'a, u8>;
-            type EwmhCookie = xcb::VoidCookie;
+            type EwmhCookie = crate::backend::VoidCookie;
 
             _set_property_base! {$window, $property, UTF8_STRING}
 
-            fn convert_cookie(&'a self, xcb_cookie: xcb::VoidCookie) -> Self::EwmhCookie {
+            fn convert_cookie(
+                &'a self,
+                xcb_cookie: crate::backend::VoidCookie,
+            ) -> Self::EwmhCookie {
                 xcb_cookie
             }
         }
@@ -91,11 +94,14 @@ macro_rules! ewmh_set_property {
      }) => {
         impl<'a> EwmhRequest<'a> for $request {
             type XcbRequest = xcb::x::ChangeProperty<'a, u32>;
-            type EwmhCookie = xcb::VoidCookie;
+            type EwmhCookie = crate::backend::VoidCookie;
 
             _set_property_base! {$window, $property, ATOM_CARDINAL}
 
-            fn convert_cookie(&'a self, xcb_cookie: xcb::VoidCookie) -> Self::EwmhCookie {
+            fn convert_cookie(
+                &'a self,
+                xcb_cookie: crate::backend::VoidCookie,
+            ) -> Self::EwmhCookie {
                 xcb_cookie
             }
         }
@@ -114,11 +120,14 @@ macro_rules! ewmh_set_property {
      }) => {
         impl<'a> EwmhRequest<'a> for $request {
             type XcbRequest = xcb::x::ChangeProperty<'a, xcb::x::Atom>;
-            type EwmhCookie = xcb::VoidCookie;
+            type EwmhCookie = crate::backend::VoidCookie;
 
             _set_property_base! {$window, $property, ATOM_ATOM }
 
-            fn convert_cookie(&'a self, xcb_cookie: xcb::VoidCookie) -> Self::EwmhCookie {
+            fn convert_cookie(
+                &'a self,
+                xcb_cookie: crate::backend::VoidCookie,
+            ) -> Self::EwmhCookie {
                 xcb_cookie
             }
         }
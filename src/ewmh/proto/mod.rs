@@ -17,8 +17,6 @@
 #[macro_use]
 mod macros;
 
-pub(crate) mod util;
-
 mod root_props;
 pub use root_props::*;
 
@@ -2,26 +2,32 @@
 //!
 //! see: <https://specifications.freedesktop.org/wm-spec/1.5/ar01s03.html#idm45539547193552>
 
+use std::convert::TryFrom;
 use xcb::{Xid, XidNew};
 
-use crate::ewmh::proto::util::{strings_to_x_buffer, x_buffer_to_strings};
 use crate::ewmh::traits::*;
 use crate::ewmh::Connection;
+use crate::util::{property_value, strings_to_x_buffer, x_buffer_to_string_list};
+use crate::{Error, Property, Result};
 
 use paste::paste; // Needed for macros
 
 // _NET_SUPPORTED, ATOM[]/32
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetSupportedReply {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::atoms"))]
     pub atoms: Vec<xcb::x::Atom>,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetSupportedReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetSupportedReply {
-            atoms: reply.value().into(),
-        }
+impl TryFrom<Property> for GetSupportedReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        Ok(GetSupportedReply {
+            atoms: property_value(&reply, 0)?.into(),
+        })
     }
 }
 
@@ -38,15 +44,19 @@ ewmh_get_property! {
 // _NET_CLIENT_LIST, WINDOW[]/32
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetClientListReply {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::xids"))]
     pub clients: Vec<xcb::x::Window>,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetClientListReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetClientListReply {
-            clients: reply.value().into(),
-        }
+impl TryFrom<Property> for GetClientListReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        Ok(GetClientListReply {
+            clients: property_value(&reply, 0)?.into(),
+        })
     }
 }
 
@@ -63,15 +73,19 @@ ewmh_get_property! {
 // _NET_CLIENT_LIST_STACKING, WINDOW[]/32
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetClientListStackingReply {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::xids"))]
     pub clients: Vec<xcb::x::Window>,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetClientListStackingReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetClientListStackingReply {
-            clients: reply.value().into(),
-        }
+impl TryFrom<Property> for GetClientListStackingReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        Ok(GetClientListStackingReply {
+            clients: property_value(&reply, 0)?.into(),
+        })
     }
 }
 
@@ -88,15 +102,18 @@ ewmh_get_property! {
 // _NET_NUMBER_OF_DESKTOPS, CARDINAL/32
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetNumberOfDesktopsReply {
     pub desktops: u32,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetNumberOfDesktopsReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetNumberOfDesktopsReply {
-            desktops: reply.value::<u32>()[0],
-        }
+impl TryFrom<Property> for GetNumberOfDesktopsReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        let value = property_value::<u32>(&reply, 1)?;
+
+        Ok(GetNumberOfDesktopsReply { desktops: value[0] })
     }
 }
 
@@ -114,14 +131,14 @@ pub struct SendNumberOfDesktops {
 }
 
 impl SendNumberOfDesktops {
-    pub fn new(connection: &Connection, desktops: u32) -> SendNumberOfDesktops {
-        SendNumberOfDesktops {
+    pub fn new(connection: &Connection, desktops: u32) -> Result<SendNumberOfDesktops> {
+        Ok(SendNumberOfDesktops {
             client_message: xcb::x::ClientMessageEvent::new(
-                connection.con.get_setup().roots().next().unwrap().root(),
-                connection.atoms._NET_NUMBER_OF_DESKTOPS,
+                connection.root(),
+                connection.atom("_NET_NUMBER_OF_DESKTOPS")?,
                 xcb::x::ClientMessageData::Data32([desktops, 0x00, 0x00, 0x00, 0x00]),
             ),
-        }
+        })
     }
 }
 
@@ -133,17 +150,22 @@ ewmh_client_message! {
 // _NET_DESKTOP_GEOMETRY width, height, CARDINAL[2]/32
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetDesktopGeometryReply {
     pub width: u32,
     pub height: u32,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetDesktopGeometryReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetDesktopGeometryReply {
-            width: reply.value::<u32>()[0],
-            height: reply.value::<u32>()[1],
-        }
+impl TryFrom<Property> for GetDesktopGeometryReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        let value = property_value::<u32>(&reply, 2)?;
+
+        Ok(GetDesktopGeometryReply {
+            width: value[0],
+            height: value[1],
+        })
     }
 }
 
@@ -161,14 +183,14 @@ pub struct SendDesktopGeometry {
 }
 
 impl SendDesktopGeometry {
-    pub fn new(connection: &Connection, width: u32, height: u32) -> SendDesktopGeometry {
-        SendDesktopGeometry {
+    pub fn new(connection: &Connection, width: u32, height: u32) -> Result<SendDesktopGeometry> {
+        Ok(SendDesktopGeometry {
             client_message: xcb::x::ClientMessageEvent::new(
-                connection.con.get_setup().roots().next().unwrap().root(),
-                connection.atoms._NET_DESKTOP_GEOMETRY,
+                connection.root(),
+                connection.atom("_NET_DESKTOP_GEOMETRY")?,
                 xcb::x::ClientMessageData::Data32([width, height, 0x00, 0x00, 0x00]),
             ),
-        }
+        })
     }
 }
 
@@ -180,17 +202,22 @@ ewmh_client_message! {
 // _NET_DESTKOP_VIEWPORT x, y, CARDINAL[][2]/32
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetDesktopViewportReply {
     pub x: u32,
     pub y: u32,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetDesktopViewportReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetDesktopViewportReply {
-            x: reply.value::<u32>()[0],
-            y: reply.value::<u32>()[1],
-        }
+impl TryFrom<Property> for GetDesktopViewportReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        let value = property_value::<u32>(&reply, 2)?;
+
+        Ok(GetDesktopViewportReply {
+            x: value[0],
+            y: value[1],
+        })
     }
 }
 
@@ -208,14 +235,14 @@ pub struct SendDesktopViewport {
 }
 
 impl SendDesktopViewport {
-    pub fn new(connection: &Connection, x: u32, y: u32) -> SendDesktopViewport {
-        SendDesktopViewport {
+    pub fn new(connection: &Connection, x: u32, y: u32) -> Result<SendDesktopViewport> {
+        Ok(SendDesktopViewport {
             client_message: xcb::x::ClientMessageEvent::new(
-                connection.con.get_setup().roots().next().unwrap().root(),
-                connection.atoms._NET_DESKTOP_VIEWPORT,
+                connection.root(),
+                connection.atom("_NET_DESKTOP_VIEWPORT")?,
                 xcb::x::ClientMessageData::Data32([x, y, 0x00, 0x00, 0x00]),
             ),
-        }
+        })
     }
 }
 
@@ -227,15 +254,18 @@ ewmh_client_message! {
 // _NET_CURRENT_DESKTOP desktop, CARDINAL/32
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetCurrentDesktopReply {
     pub desktop: u32,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetCurrentDesktopReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetCurrentDesktopReply {
-            desktop: reply.value::<u32>()[0],
-        }
+impl TryFrom<Property> for GetCurrentDesktopReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        let value = property_value::<u32>(&reply, 1)?;
+
+        Ok(GetCurrentDesktopReply { desktop: value[0] })
     }
 }
 
@@ -253,14 +283,14 @@ pub struct SendCurrentDesktop {
 }
 
 impl SendCurrentDesktop {
-    pub fn new(connection: &Connection, desktop: u32) -> SendCurrentDesktop {
-        SendCurrentDesktop {
+    pub fn new(connection: &Connection, desktop: u32) -> Result<SendCurrentDesktop> {
+        Ok(SendCurrentDesktop {
             client_message: xcb::x::ClientMessageEvent::new(
-                connection.con.get_setup().roots().next().unwrap().root(),
-                connection.atoms._NET_CURRENT_DESKTOP,
+                connection.root(),
+                connection.atom("_NET_CURRENT_DESKTOP")?,
                 xcb::x::ClientMessageData::Data32([desktop, 0x00, 0x00, 0x00, 0x00]),
             ),
-        }
+        })
     }
 }
 
@@ -272,15 +302,19 @@ ewmh_client_message! {
 // _NET_DESKTOP_NAMES desktop, UTF8_STRING[]
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetDesktopNamesReply {
+    /// One name per desktop, empty for a desktop without a name
     pub names: Vec<String>,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetDesktopNamesReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetDesktopNamesReply {
-            names: x_buffer_to_strings(reply.value::<u8>()),
-        }
+impl TryFrom<Property> for GetDesktopNamesReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        Ok(GetDesktopNamesReply {
+            names: x_buffer_to_string_list(property_value(&reply, 0)?)?,
+        })
     }
 }
 
@@ -317,15 +351,21 @@ ewmh_set_property! {
 // _NET_ACTIVE_WINDOW, WINDOW/32
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetActiveWindowReply {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::xid"))]
     pub window: xcb::x::Window,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetActiveWindowReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetActiveWindowReply {
-            window: unsafe { xcb::x::Window::new(reply.value::<u32>()[0]) },
-        }
+impl TryFrom<Property> for GetActiveWindowReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        let value = property_value::<u32>(&reply, 1)?;
+
+        Ok(GetActiveWindowReply {
+            window: unsafe { xcb::x::Window::new(value[0]) },
+        })
     }
 }
 
@@ -349,11 +389,11 @@ impl SendActiveWindow {
         source_indication: u32,
         timestamp: u32,
         requestor_window: Option<xcb::x::Window>,
-    ) -> SendActiveWindow {
-        SendActiveWindow {
+    ) -> Result<SendActiveWindow> {
+        Ok(SendActiveWindow {
             client_message: xcb::x::ClientMessageEvent::new(
                 window,
-                connection.atoms._NET_ACTIVE_WINDOW,
+                connection.atom("_NET_ACTIVE_WINDOW")?,
                 xcb::x::ClientMessageData::Data32([
                     source_indication,
                     timestamp,
@@ -362,7 +402,7 @@ impl SendActiveWindow {
                     0x00,
                 ]),
             ),
-        }
+        })
     }
 }
 
@@ -373,7 +413,8 @@ ewmh_client_message! {
 
 // // _NET_WORKAREA, x, y, width, height, CARDINAL[][4]/32
 // {{{
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetWorkareaReply {
     pub x: u32,
     pub y: u32,
@@ -381,14 +422,18 @@ pub struct GetWorkareaReply {
     pub height: u32,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetWorkareaReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetWorkareaReply {
-            x: reply.value::<u32>()[0],
-            y: reply.value::<u32>()[1],
-            width: reply.value::<u32>()[2],
-            height: reply.value::<u32>()[3],
-        }
+impl TryFrom<Property> for GetWorkareaReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        let value = property_value::<u32>(&reply, 4)?;
+
+        Ok(GetWorkareaReply {
+            x: value[0],
+            y: value[1],
+            width: value[2],
+            height: value[3],
+        })
     }
 }
 
@@ -405,15 +450,21 @@ ewmh_get_property! {
 // // _NET_SUPPORTING_WM_CHECK, WINDOW/32
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetSupportingWmCheckReply {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::xid"))]
     pub window: xcb::x::Window,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetSupportingWmCheckReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetSupportingWmCheckReply {
-            window: unsafe { xcb::x::Window::new(reply.value::<u32>()[0]) },
-        }
+impl TryFrom<Property> for GetSupportingWmCheckReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        let value = property_value::<u32>(&reply, 1)?;
+
+        Ok(GetSupportingWmCheckReply {
+            window: unsafe { xcb::x::Window::new(value[0]) },
+        })
     }
 }
 
@@ -430,15 +481,21 @@ ewmh_get_property! {
 // _NET_VIRTUAL_ROOTS, WINDOW/32
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetVirtualRootsReply {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::xid"))]
     pub window: xcb::x::Window,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetVirtualRootsReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetVirtualRootsReply {
-            window: unsafe { xcb::x::Window::new(reply.value::<u32>()[0]) },
-        }
+impl TryFrom<Property> for GetVirtualRootsReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        let value = property_value::<u32>(&reply, 1)?;
+
+        Ok(GetVirtualRootsReply {
+            window: unsafe { xcb::x::Window::new(value[0]) },
+        })
     }
 }
 
@@ -455,6 +512,7 @@ ewmh_get_property! {
 // _NET_DESKTOP_LAYOUT, orientation, columns, rows, starting_corner, CARDINAL[4]/32
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DesktopLayoutReply {
     pub orientation: u32,
     pub columns: u32,
@@ -462,14 +520,18 @@ pub struct DesktopLayoutReply {
     pub starting_corner: u32,
 }
 
-impl From<xcb::x::GetPropertyReply> for DesktopLayoutReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        DesktopLayoutReply {
-            orientation: reply.value::<u32>()[0],
-            columns: reply.value::<u32>()[1],
-            rows: reply.value::<u32>()[2],
-            starting_corner: reply.value::<u32>()[3],
-        }
+impl TryFrom<Property> for DesktopLayoutReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        let value = property_value::<u32>(&reply, 4)?;
+
+        Ok(DesktopLayoutReply {
+            orientation: value[0],
+            columns: value[1],
+            rows: value[2],
+            starting_corner: value[3],
+        })
     }
 }
 
@@ -486,15 +548,20 @@ ewmh_get_property! {
 // _NET_SHOWING_DESKTOP desktop, CARDINAL/32
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetShowingDesktopReply {
     pub is_showing_desktop: bool,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetShowingDesktopReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetShowingDesktopReply {
-            is_showing_desktop: { reply.value::<u32>()[0] == 1 },
-        }
+impl TryFrom<Property> for GetShowingDesktopReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        let value = property_value::<u32>(&reply, 1)?;
+
+        Ok(GetShowingDesktopReply {
+            is_showing_desktop: { value[0] == 1 },
+        })
     }
 }
 
@@ -512,16 +579,16 @@ pub struct SetShowingDesktop {
 }
 
 impl SetShowingDesktop {
-    pub fn new(connection: &Connection, show_desktop: bool) -> SetShowingDesktop {
+    pub fn new(connection: &Connection, show_desktop: bool) -> Result<SetShowingDesktop> {
         let data = if show_desktop { 1 } else { 0 };
 
-        SetShowingDesktop {
+        Ok(SetShowingDesktop {
             client_message: xcb::x::ClientMessageEvent::new(
-                connection.con.get_setup().roots().next().unwrap().root(),
-                connection.atoms._NET_SHOWING_DESKTOP,
+                connection.root(),
+                connection.atom("_NET_SHOWING_DESKTOP")?,
                 xcb::x::ClientMessageData::Data32([data, 0x00, 0x00, 0x00, 0x00]),
             ),
-        }
+        })
     }
 }
 
@@ -542,14 +609,14 @@ impl SendCloseWindow {
         window: xcb::x::Window,
         source_indication: u32,
         timestamp: u32,
-    ) -> SendCloseWindow {
-        SendCloseWindow {
+    ) -> Result<SendCloseWindow> {
+        Ok(SendCloseWindow {
             client_message: xcb::x::ClientMessageEvent::new(
                 window,
-                connection.atoms._NET_CLOSE_WINDOW,
+                connection.atom("_NET_CLOSE_WINDOW")?,
                 xcb::x::ClientMessageData::Data32([timestamp, source_indication, 0x00, 0x00, 0x00]),
             ),
-        }
+        })
     }
 }
 
@@ -557,27 +624,157 @@ ewmh_client_message! {
     request=SendCloseWindow{destination: root}
 }
 
-// // _NET_MOVERESIZE_WINDOW
-// // {{{
-//
-// // TODO
-//
-// // }}}
-//
-// // _NET_WM_MOVERESIZE
-// // {{{
-//
-// // TODO
-//
-// // }}}
-//
-// // _NET_RESTACK_WINDOW
-// // {{{
-//
-// // TODO
-//
-// // }}}
-//
+// _NET_MOVERESIZE_WINDOW
+// {{{
+pub struct SendMoveresizeWindow {
+    client_message: xcb::x::ClientMessageEvent,
+}
+
+impl SendMoveresizeWindow {
+    /// Move and/or resize `window`, fields that are `None` are left unchanged
+    ///
+    /// `gravity` is applied to `x` and `y` like a `win_gravity` in `WM_NORMAL_HINTS`.
+    /// [`xcb::x::Gravity::BitForget`] uses the gravity of the window.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        connection: &Connection,
+        window: xcb::x::Window,
+        gravity: xcb::x::Gravity,
+        source_indication: u32,
+        x: Option<i32>,
+        y: Option<i32>,
+        width: Option<u32>,
+        height: Option<u32>,
+    ) -> Result<SendMoveresizeWindow> {
+        // Bits 0-7 gravity, 8-11 which fields are set, 12-15 source indication
+        let mut flags = gravity as u32 | (source_indication & 0xf) << 12;
+        for (bit, set) in [x.is_some(), y.is_some(), width.is_some(), height.is_some()]
+            .iter()
+            .enumerate()
+        {
+            if *set {
+                flags |= 1 << (8 + bit);
+            }
+        }
+
+        Ok(SendMoveresizeWindow {
+            client_message: xcb::x::ClientMessageEvent::new(
+                window,
+                connection.atom("_NET_MOVERESIZE_WINDOW")?,
+                xcb::x::ClientMessageData::Data32([
+                    flags,
+                    x.unwrap_or(0) as u32,
+                    y.unwrap_or(0) as u32,
+                    width.unwrap_or(0),
+                    height.unwrap_or(0),
+                ]),
+            ),
+        })
+    }
+}
+
+ewmh_client_message! {
+    request=SendMoveresizeWindow{destination: root}
+}
+// }}}
+
+// _NET_WM_MOVERESIZE
+// {{{
+
+/// Edge or corner of a `_NET_WM_MOVERESIZE`, or how it is driven
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum WmMoveresizeDirection {
+    SizeTopLeft = 0,
+    SizeTop = 1,
+    SizeTopRight = 2,
+    SizeRight = 3,
+    SizeBottomRight = 4,
+    SizeBottom = 5,
+    SizeBottomLeft = 6,
+    SizeLeft = 7,
+    Move = 8,
+    SizeKeyboard = 9,
+    MoveKeyboard = 10,
+    Cancel = 11,
+}
+
+pub struct SendWmMoveresize {
+    client_message: xcb::x::ClientMessageEvent,
+}
+
+impl SendWmMoveresize {
+    /// Start (or cancel) an interactive move or resize of `window`
+    ///
+    /// `x_root` and `y_root` are the pointer position, `button` the pressed button (0 for a
+    /// keyboard initiated move or resize).
+    pub fn new(
+        connection: &Connection,
+        window: xcb::x::Window,
+        x_root: i32,
+        y_root: i32,
+        direction: WmMoveresizeDirection,
+        button: u32,
+        source_indication: u32,
+    ) -> Result<SendWmMoveresize> {
+        Ok(SendWmMoveresize {
+            client_message: xcb::x::ClientMessageEvent::new(
+                window,
+                connection.atom("_NET_WM_MOVERESIZE")?,
+                xcb::x::ClientMessageData::Data32([
+                    x_root as u32,
+                    y_root as u32,
+                    direction as u32,
+                    button,
+                    source_indication,
+                ]),
+            ),
+        })
+    }
+}
+
+ewmh_client_message! {
+    request=SendWmMoveresize{destination: root}
+}
+// }}}
+
+// _NET_RESTACK_WINDOW
+// {{{
+pub struct SendRestackWindow {
+    client_message: xcb::x::ClientMessageEvent,
+}
+
+impl SendRestackWindow {
+    /// Restack `window` relative to `sibling` like `ConfigureWindow` with a `stack_mode`
+    pub fn new(
+        connection: &Connection,
+        window: xcb::x::Window,
+        source_indication: u32,
+        sibling: Option<xcb::x::Window>,
+        detail: xcb::x::StackMode,
+    ) -> Result<SendRestackWindow> {
+        Ok(SendRestackWindow {
+            client_message: xcb::x::ClientMessageEvent::new(
+                window,
+                connection.atom("_NET_RESTACK_WINDOW")?,
+                xcb::x::ClientMessageData::Data32([
+                    source_indication,
+                    sibling.map_or(0, |w| w.resource_id()),
+                    detail as u32,
+                    0x00,
+                    0x00,
+                ]),
+            ),
+        })
+    }
+}
+
+ewmh_client_message! {
+    request=SendRestackWindow{destination: root}
+}
+// }}}
+
 // _NET_REQUEST_FRAME_EXTENTS
 // {{{
 pub struct SendRequestFrameExtents {
@@ -585,14 +782,14 @@ pub struct SendRequestFrameExtents {
 }
 
 impl SendRequestFrameExtents {
-    pub fn new(connection: &Connection, window: xcb::x::Window) -> SendRequestFrameExtents {
-        SendRequestFrameExtents {
+    pub fn new(connection: &Connection, window: xcb::x::Window) -> Result<SendRequestFrameExtents> {
+        Ok(SendRequestFrameExtents {
             client_message: xcb::x::ClientMessageEvent::new(
                 window,
-                connection.atoms._NET_REQUEST_FRAME_EXTENTS,
+                connection.atom("_NET_REQUEST_FRAME_EXTENTS")?,
                 xcb::x::ClientMessageData::Data32([0x00, 0x00, 0x00, 0x00, 0x00]),
             ),
-        }
+        })
     }
 }
 
@@ -600,3 +797,112 @@ ewmh_client_message! {
     request=SendRequestFrameExtents{destination: root}
 }
 // }}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xcb::Xid;
+
+    fn cardinals(data: Vec<u32>) -> Property {
+        Property::format32(xcb::x::ATOM_CARDINAL, data)
+    }
+
+    #[test]
+    fn desktop_names_round_trip() {
+        let names = vec!["web", "code", "Ünïcödé"];
+        let request = SetDesktopNames::new(names.clone());
+
+        // `UTF8_STRING` is not predefined, any other atom will do
+        let property = Property::format8(xcb::x::Atom::new(400), request.data);
+        let reply = GetDesktopNamesReply::try_from(property).unwrap();
+        assert_eq!(reply.names, names);
+
+        let empty = Property::format8(xcb::x::Atom::new(400), vec![]);
+        assert!(GetDesktopNamesReply::try_from(empty)
+            .unwrap()
+            .names
+            .is_empty());
+    }
+
+    #[test]
+    fn windows_and_atoms() {
+        let reply =
+            GetClientListReply::try_from(Property::format32(xcb::x::ATOM_WINDOW, vec![1, 2]))
+                .unwrap();
+        assert_eq!(
+            reply.clients,
+            vec![xcb::x::Window::new(1), xcb::x::Window::new(2)]
+        );
+
+        let reply = GetSupportedReply::try_from(Property::format32(
+            xcb::x::ATOM_ATOM,
+            vec![xcb::x::ATOM_STRING.resource_id()],
+        ))
+        .unwrap();
+        assert_eq!(reply.atoms, vec![xcb::x::ATOM_STRING]);
+
+        let reply =
+            GetActiveWindowReply::try_from(Property::format32(xcb::x::ATOM_WINDOW, vec![7]))
+                .unwrap();
+        assert_eq!(reply.window, xcb::x::Window::new(7));
+    }
+
+    #[test]
+    fn cardinals_decode() {
+        let reply = GetWorkareaReply::try_from(cardinals(vec![0, 24, 1920, 1056, 9, 9, 9, 9]));
+        assert_eq!(
+            reply.unwrap(),
+            GetWorkareaReply {
+                x: 0,
+                y: 24,
+                width: 1920,
+                height: 1056
+            }
+        );
+
+        let reply = GetDesktopGeometryReply::try_from(cardinals(vec![3840, 1080])).unwrap();
+        assert_eq!((reply.width, reply.height), (3840, 1080));
+
+        assert!(
+            GetShowingDesktopReply::try_from(cardinals(vec![1]))
+                .unwrap()
+                .is_showing_desktop
+        );
+        assert!(
+            !GetShowingDesktopReply::try_from(cardinals(vec![0]))
+                .unwrap()
+                .is_showing_desktop
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(matches!(
+            GetWorkareaReply::try_from(cardinals(vec![0, 0, 0])),
+            Err(Error::Truncated {
+                expected: 4,
+                actual: 3
+            })
+        ));
+        assert!(matches!(
+            GetNumberOfDesktopsReply::try_from(Property::format8(xcb::x::ATOM_STRING, vec![4])),
+            Err(Error::WrongFormat {
+                expected: 32,
+                actual: 8
+            })
+        ));
+        assert!(matches!(
+            GetCurrentDesktopReply::try_from(
+                Property::format32(xcb::x::ATOM_WINDOW, vec![]).with_bytes_after(4)
+            ),
+            Err(Error::WrongType { actual }) if actual == xcb::x::ATOM_WINDOW
+        ));
+        assert!(matches!(
+            GetDesktopNamesReply::try_from(Property::format8(
+                xcb::x::Atom::new(400),
+                b"ok\0\xff\0".to_vec()
+            )),
+            Err(Error::InvalidUtf8(_))
+        ));
+    }
+}
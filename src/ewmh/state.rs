@@ -0,0 +1,308 @@
+//! In-memory mirror of the root window `ewmh` properties
+//!
+//! [`EwmhState::load`] fetches the properties once. Afterwards [`EwmhState::handle`] keeps them
+//! current from the `PropertyNotify` events of the root window and reports what changed.
+
+use crate::ewmh::proto::{
+    GetActiveWindow, GetClientList, GetClientListStacking, GetCurrentDesktop, GetDesktopNames,
+    GetNumberOfDesktops, GetWorkarea, GetWorkareaReply,
+};
+use crate::ewmh::{Connection, EwmhEvent, EwmhProperty, Watcher};
+use crate::util::optional;
+use crate::{Error, Result};
+
+/// The root window properties a bar or pager shows
+///
+/// Properties that are not set are `None` (or empty).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EwmhState {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::xids"))]
+    pub client_list: Vec<xcb::x::Window>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::xids"))]
+    pub client_list_stacking: Vec<xcb::x::Window>,
+    pub number_of_desktops: Option<u32>,
+    pub current_desktop: Option<u32>,
+    pub desktop_names: Vec<String>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::optional_xid"))]
+    pub active_window: Option<xcb::x::Window>,
+    pub workarea: Option<GetWorkareaReply>,
+}
+
+/// A change of [`EwmhState`], as returned by [`EwmhState::handle`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EwmhStateChange {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::xid"))]
+    ClientAdded(xcb::x::Window),
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::xid"))]
+    ClientRemoved(xcb::x::Window),
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::xids"))]
+    StackingChanged(Vec<xcb::x::Window>),
+    NumberOfDesktopsChanged(Option<u32>),
+    CurrentDesktopChanged(Option<u32>),
+    DesktopNamesChanged(Vec<String>),
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::optional_xid"))]
+    ActiveWindowChanged(Option<xcb::x::Window>),
+    WorkareaChanged(Option<GetWorkareaReply>),
+}
+
+impl EwmhState {
+    /// Fetch all properties in one round-trip
+    ///
+    /// To keep the state current, watch the root window with [`Watcher::watch`] and pass its
+    /// events to [`EwmhState::handle`].
+    pub fn load(con: &Connection) -> Result<EwmhState> {
+        let (
+            client_list,
+            client_list_stacking,
+            number_of_desktops,
+            current_desktop,
+            desktop_names,
+            active_window,
+            workarea,
+        ) = con.wait_for_batch(con.send_batch(&(
+            GetClientList,
+            GetClientListStacking,
+            GetNumberOfDesktops,
+            GetCurrentDesktop,
+            GetDesktopNames,
+            GetActiveWindow,
+            GetWorkarea,
+        )));
+
+        Ok(EwmhState {
+            client_list: optional(client_list)?.map_or(vec![], |r| r.clients),
+            client_list_stacking: optional(client_list_stacking)?.map_or(vec![], |r| r.clients),
+            number_of_desktops: optional(number_of_desktops)?.map(|r| r.desktops),
+            current_desktop: optional(current_desktop)?.map(|r| r.desktop),
+            desktop_names: optional(desktop_names)?.map_or(vec![], |r| r.names),
+            active_window: optional(active_window)?.map(|r| r.window),
+            workarea: optional(workarea)?,
+        })
+    }
+
+    /// Update the state from a `PropertyNotify` event and return what changed
+    ///
+    /// Events for other windows than the root window or for properties that are not mirrored
+    /// change nothing. A property that was deleted before it could be fetched counts as unset.
+    /// A new value that cannot be decoded is an error and leaves the state as it was.
+    pub fn handle(
+        &mut self,
+        con: &Connection,
+        watcher: &Watcher,
+        event: &xcb::x::PropertyNotifyEvent,
+    ) -> Result<Vec<EwmhStateChange>> {
+        if event.window() != con.root() {
+            return Ok(vec![]);
+        }
+
+        match watcher.fetch(event) {
+            Ok(Some(event)) => Ok(self.apply(event)),
+            Ok(None) => Ok(vec![]),
+            Err(Error::PropertyMissing) => match watcher.property(event)? {
+                Some(property) => Ok(self.apply(EwmhEvent::Deleted(property))),
+                None => Ok(vec![]),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Update the state from an already decoded event and return what changed
+    pub fn apply(&mut self, event: EwmhEvent) -> Vec<EwmhStateChange> {
+        use EwmhEvent::*;
+        use EwmhStateChange as Change;
+
+        let mut changes = vec![];
+
+        match event {
+            ClientListChanged(clients) => self.set_client_list(clients, &mut changes),
+            Deleted(EwmhProperty::ClientList) => self.set_client_list(vec![], &mut changes),
+            ClientListStackingChanged(clients) => set(
+                &mut self.client_list_stacking,
+                clients,
+                &mut changes,
+                Change::StackingChanged,
+            ),
+            Deleted(EwmhProperty::ClientListStacking) => set(
+                &mut self.client_list_stacking,
+                vec![],
+                &mut changes,
+                Change::StackingChanged,
+            ),
+            NumberOfDesktopsChanged(desktops) => set(
+                &mut self.number_of_desktops,
+                Some(desktops),
+                &mut changes,
+                Change::NumberOfDesktopsChanged,
+            ),
+            Deleted(EwmhProperty::NumberOfDesktops) => set(
+                &mut self.number_of_desktops,
+                None,
+                &mut changes,
+                Change::NumberOfDesktopsChanged,
+            ),
+            CurrentDesktopChanged(desktop) => set(
+                &mut self.current_desktop,
+                Some(desktop),
+                &mut changes,
+                Change::CurrentDesktopChanged,
+            ),
+            Deleted(EwmhProperty::CurrentDesktop) => set(
+                &mut self.current_desktop,
+                None,
+                &mut changes,
+                Change::CurrentDesktopChanged,
+            ),
+            DesktopNamesChanged(names) => set(
+                &mut self.desktop_names,
+                names,
+                &mut changes,
+                Change::DesktopNamesChanged,
+            ),
+            Deleted(EwmhProperty::DesktopNames) => set(
+                &mut self.desktop_names,
+                vec![],
+                &mut changes,
+                Change::DesktopNamesChanged,
+            ),
+            ActiveWindowChanged(window) => set(
+                &mut self.active_window,
+                Some(window),
+                &mut changes,
+                Change::ActiveWindowChanged,
+            ),
+            Deleted(EwmhProperty::ActiveWindow) => set(
+                &mut self.active_window,
+                None,
+                &mut changes,
+                Change::ActiveWindowChanged,
+            ),
+            WorkareaChanged(workarea) => set(
+                &mut self.workarea,
+                Some(workarea),
+                &mut changes,
+                Change::WorkareaChanged,
+            ),
+            Deleted(EwmhProperty::Workarea) => set(
+                &mut self.workarea,
+                None,
+                &mut changes,
+                Change::WorkareaChanged,
+            ),
+            _ => {}
+        }
+
+        changes
+    }
+
+    fn set_client_list(
+        &mut self,
+        clients: Vec<xcb::x::Window>,
+        changes: &mut Vec<EwmhStateChange>,
+    ) {
+        for window in clients.iter().filter(|w| !self.client_list.contains(w)) {
+            changes.push(EwmhStateChange::ClientAdded(*window));
+        }
+        for window in self.client_list.iter().filter(|w| !clients.contains(w)) {
+            changes.push(EwmhStateChange::ClientRemoved(*window));
+        }
+
+        self.client_list = clients;
+    }
+}
+
+/// Replace `field` with `value` and record the change if they differ
+fn set<T: Clone + PartialEq>(
+    field: &mut T,
+    value: T,
+    changes: &mut Vec<EwmhStateChange>,
+    change: fn(T) -> EwmhStateChange,
+) {
+    if *field != value {
+        changes.push(change(value.clone()));
+        *field = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EwmhState, EwmhStateChange};
+    use crate::ewmh::{EwmhEvent, EwmhProperty};
+    use xcb::XidNew;
+
+    #[test]
+    fn apply_diffs() {
+        let (a, b, c) = (
+            xcb::x::Window::new(1),
+            xcb::x::Window::new(2),
+            xcb::x::Window::new(3),
+        );
+        let mut state = EwmhState {
+            client_list: vec![a, b],
+            ..EwmhState::default()
+        };
+
+        assert_eq!(
+            state.apply(EwmhEvent::ClientListChanged(vec![b, c])),
+            vec![
+                EwmhStateChange::ClientAdded(c),
+                EwmhStateChange::ClientRemoved(a)
+            ]
+        );
+        assert_eq!(
+            state.apply(EwmhEvent::CurrentDesktopChanged(1)),
+            vec![EwmhStateChange::CurrentDesktopChanged(Some(1))]
+        );
+        assert_eq!(state.apply(EwmhEvent::CurrentDesktopChanged(1)), vec![]);
+        assert_eq!(
+            state.apply(EwmhEvent::Deleted(EwmhProperty::CurrentDesktop)),
+            vec![EwmhStateChange::CurrentDesktopChanged(None)]
+        );
+        assert_eq!(state.client_list, vec![b, c]);
+    }
+
+    #[cfg(feature = "mock")]
+    fn changed(store: &crate::mock::PropertyStore, property: &str) -> xcb::x::PropertyNotifyEvent {
+        xcb::x::PropertyNotifyEvent::new(
+            store.root(),
+            store.atom(property),
+            xcb::x::CURRENT_TIME,
+            xcb::x::Property::NewValue,
+        )
+    }
+
+    #[test]
+    #[cfg(feature = "mock")]
+    fn handle_decode_errors() {
+        let store = crate::mock::PropertyStore::new();
+        store.set_property(
+            store.root(),
+            "_NET_CURRENT_DESKTOP",
+            crate::Property::format32(xcb::x::ATOM_CARDINAL, vec![1]),
+        );
+        let ewmh_con = crate::ewmh::Connection::connect(&store);
+        let watcher = crate::ewmh::Watcher::new(&ewmh_con);
+        let mut state = EwmhState::load(&ewmh_con).unwrap();
+        assert_eq!(state.current_desktop, Some(1));
+
+        store.set_property(
+            store.root(),
+            "_NET_CURRENT_DESKTOP",
+            crate::Property::format8(xcb::x::ATOM_STRING, b"2".to_vec()),
+        );
+        let event = changed(&store, "_NET_CURRENT_DESKTOP");
+        assert!(matches!(
+            state.handle(&ewmh_con, &watcher, &event),
+            Err(crate::Error::WrongType { .. })
+        ));
+        assert_eq!(state.current_desktop, Some(1));
+
+        // Deleted before the event was handled
+        store.delete_property(store.root(), "_NET_CURRENT_DESKTOP");
+        assert_eq!(
+            state.handle(&ewmh_con, &watcher, &event).unwrap(),
+            vec![EwmhStateChange::CurrentDesktopChanged(None)]
+        );
+    }
+}
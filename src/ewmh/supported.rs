@@ -0,0 +1,257 @@
+//! Typed view of `_NET_SUPPORTED`
+//!
+//! [`SupportedHints`] maps the atoms a window manager lists in `_NET_SUPPORTED` to [`Hint`]s
+//! through the interned [`Atoms`], without a `GetAtomName` round-trip per atom. Clients check a
+//! hint with [`SupportedHints::supports`] before relying on it.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::ewmh::proto::GetSupported;
+use crate::ewmh::{Atoms, Connection};
+use crate::Result;
+
+macro_rules! hints {
+    ($($hint:ident = $atom:ident;)+) => {
+        /// A hint of the `ewmh` specification a window manager can list in `_NET_SUPPORTED`
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub enum Hint {
+            $($hint,)+
+        }
+
+        impl Hint {
+            /// All hints in the order of the specification
+            pub const ALL: &'static [Hint] = &[$(Hint::$hint,)+];
+
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Hint::$hint => stringify!($atom),)+
+                }
+            }
+
+            pub fn from_name(name: &str) -> Option<Hint> {
+                match name {
+                    $(stringify!($atom) => Some(Hint::$hint),)+
+                    _ => None,
+                }
+            }
+
+            /// The atom of this hint in `atoms`
+            pub fn atom(self, atoms: &Atoms) -> xcb::x::Atom {
+                match self {
+                    $(Hint::$hint => atoms.$atom,)+
+                }
+            }
+        }
+    };
+}
+
+hints! {
+    Supported = _NET_SUPPORTED;
+    ClientList = _NET_CLIENT_LIST;
+    ClientListStacking = _NET_CLIENT_LIST_STACKING;
+    NumberOfDesktops = _NET_NUMBER_OF_DESKTOPS;
+    DesktopGeometry = _NET_DESKTOP_GEOMETRY;
+    DesktopViewport = _NET_DESKTOP_VIEWPORT;
+    CurrentDesktop = _NET_CURRENT_DESKTOP;
+    DesktopNames = _NET_DESKTOP_NAMES;
+    ActiveWindow = _NET_ACTIVE_WINDOW;
+    Workarea = _NET_WORKAREA;
+    SupportingWmCheck = _NET_SUPPORTING_WM_CHECK;
+    VirtualRoots = _NET_VIRTUAL_ROOTS;
+    DesktopLayout = _NET_DESKTOP_LAYOUT;
+    ShowingDesktop = _NET_SHOWING_DESKTOP;
+    CloseWindow = _NET_CLOSE_WINDOW;
+    MoveresizeWindow = _NET_MOVERESIZE_WINDOW;
+    WmMoveresize = _NET_WM_MOVERESIZE;
+    RestackWindow = _NET_RESTACK_WINDOW;
+    RequestFrameExtents = _NET_REQUEST_FRAME_EXTENTS;
+    WmName = _NET_WM_NAME;
+    WmVisibleName = _NET_WM_VISIBLE_NAME;
+    WmIconName = _NET_WM_ICON_NAME;
+    WmVisibleIconName = _NET_WM_VISIBLE_ICON_NAME;
+    WmDesktop = _NET_WM_DESKTOP;
+    WmWindowType = _NET_WM_WINDOW_TYPE;
+    WmState = _NET_WM_STATE;
+    WmAllowedActions = _NET_WM_ALLOWED_ACTIONS;
+    WmStrut = _NET_WM_STRUT;
+    WmStrutPartial = _NET_WM_STRUT_PARTIAL;
+    WmIconGeometry = _NET_WM_ICON_GEOMETRY;
+    WmIcon = _NET_WM_ICON;
+    WmPid = _NET_WM_PID;
+    WmHandledIcons = _NET_WM_HANDLED_ICONS;
+    WmUserTime = _NET_WM_USER_TIME;
+    WmUserTimeWindow = _NET_WM_USER_TIME_WINDOW;
+    FrameExtents = _NET_FRAME_EXTENTS;
+    WmPing = _NET_WM_PING;
+    WmSyncRequest = _NET_WM_SYNC_REQUEST;
+    WmSyncRequestCounter = _NET_WM_SYNC_REQUEST_COUNTER;
+    WmFullscreenMonitors = _NET_WM_FULLSCREEN_MONITORS;
+    WmFullPlacement = _NET_WM_FULL_PLACEMENT;
+    WmWindowTypeDesktop = _NET_WM_WINDOW_TYPE_DESKTOP;
+    WmWindowTypeDock = _NET_WM_WINDOW_TYPE_DOCK;
+    WmWindowTypeToolbar = _NET_WM_WINDOW_TYPE_TOOLBAR;
+    WmWindowTypeMenu = _NET_WM_WINDOW_TYPE_MENU;
+    WmWindowTypeUtility = _NET_WM_WINDOW_TYPE_UTILITY;
+    WmWindowTypeSplash = _NET_WM_WINDOW_TYPE_SPLASH;
+    WmWindowTypeDialog = _NET_WM_WINDOW_TYPE_DIALOG;
+    WmWindowTypeDropdownMenu = _NET_WM_WINDOW_TYPE_DROPDOWN_MENU;
+    WmWindowTypePopupMenu = _NET_WM_WINDOW_TYPE_POPUP_MENU;
+    WmWindowTypeTooltip = _NET_WM_WINDOW_TYPE_TOOLTIP;
+    WmWindowTypeNotification = _NET_WM_WINDOW_TYPE_NOTIFICATION;
+    WmWindowTypeCombo = _NET_WM_WINDOW_TYPE_COMBO;
+    WmWindowTypeDnd = _NET_WM_WINDOW_TYPE_DND;
+    WmWindowTypeNormal = _NET_WM_WINDOW_TYPE_NORMAL;
+    WmStateModal = _NET_WM_STATE_MODAL;
+    WmStateSticky = _NET_WM_STATE_STICKY;
+    WmStateMaximizedVert = _NET_WM_STATE_MAXIMIZED_VERT;
+    WmStateMaximizedHorz = _NET_WM_STATE_MAXIMIZED_HORZ;
+    WmStateShaded = _NET_WM_STATE_SHADED;
+    WmStateSkipTaskbar = _NET_WM_STATE_SKIP_TASKBAR;
+    WmStateSkipPager = _NET_WM_STATE_SKIP_PAGER;
+    WmStateHidden = _NET_WM_STATE_HIDDEN;
+    WmStateFullscreen = _NET_WM_STATE_FULLSCREEN;
+    WmStateAbove = _NET_WM_STATE_ABOVE;
+    WmStateBelow = _NET_WM_STATE_BELOW;
+    WmStateDemandsAttention = _NET_WM_STATE_DEMANDS_ATTENTION;
+    WmActionMove = _NET_WM_ACTION_MOVE;
+    WmActionResize = _NET_WM_ACTION_RESIZE;
+    WmActionMinimize = _NET_WM_ACTION_MINIMIZE;
+    WmActionShade = _NET_WM_ACTION_SHADE;
+    WmActionStick = _NET_WM_ACTION_STICK;
+    WmActionMaximizeHorz = _NET_WM_ACTION_MAXIMIZE_HORZ;
+    WmActionMaximizeVert = _NET_WM_ACTION_MAXIMIZE_VERT;
+    WmActionFullscreen = _NET_WM_ACTION_FULLSCREEN;
+    WmActionChangeDesktop = _NET_WM_ACTION_CHANGE_DESKTOP;
+    WmActionClose = _NET_WM_ACTION_CLOSE;
+    WmActionAbove = _NET_WM_ACTION_ABOVE;
+    WmActionBelow = _NET_WM_ACTION_BELOW;
+}
+
+/// The hints a window manager supports, as listed in `_NET_SUPPORTED`
+///
+/// Atoms that are not a [`Hint`] (e.g. extensions of a specific window manager) are kept apart
+/// in [`SupportedHints::unknown`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SupportedHints {
+    hints: HashSet<Hint>,
+    unknown: Vec<xcb::x::Atom>,
+}
+
+impl SupportedHints {
+    /// Map the atoms of a [`GetSupportedReply`](crate::ewmh::proto::GetSupportedReply) to hints
+    pub fn new(atoms: &Atoms, supported: &[xcb::x::Atom]) -> SupportedHints {
+        // Hints whose atom could not be interned must not match `ATOM_NONE` entries
+        let by_atom: HashMap<xcb::x::Atom, Hint> = Hint::ALL
+            .iter()
+            .map(|hint| (hint.atom(atoms), *hint))
+            .filter(|(atom, _)| *atom != xcb::x::ATOM_NONE)
+            .collect();
+
+        let mut hints = SupportedHints::default();
+        for atom in supported {
+            match by_atom.get(atom) {
+                Some(hint) => {
+                    hints.hints.insert(*hint);
+                }
+                None => hints.unknown.push(*atom),
+            }
+        }
+
+        hints
+    }
+
+    /// Fetch `_NET_SUPPORTED`
+    ///
+    /// Interns the [`Atoms`] of `con` if that did not happen yet. Without a window manager (or
+    /// with one that does not set `_NET_SUPPORTED`) no hint is supported.
+    pub fn fetch(con: &Connection) -> Result<SupportedHints> {
+        let cookie = con.send_request(&GetSupported);
+        let atoms = con.atoms()?;
+        let supported = con
+            .wait_for_optional_reply(cookie)?
+            .map(|reply| reply.atoms)
+            .unwrap_or_default();
+
+        Ok(SupportedHints::new(atoms, &supported))
+    }
+
+    pub fn supports(&self, hint: Hint) -> bool {
+        self.hints.contains(&hint)
+    }
+
+    /// Whether every hint of `hints` is supported
+    pub fn supports_all(&self, hints: &[Hint]) -> bool {
+        hints.iter().all(|hint| self.supports(*hint))
+    }
+
+    /// The supported hints in the order of [`Hint::ALL`]
+    pub fn hints(&self) -> impl Iterator<Item = Hint> + '_ {
+        Hint::ALL
+            .iter()
+            .copied()
+            .filter(move |hint| self.supports(*hint))
+    }
+
+    /// Atoms listed in `_NET_SUPPORTED` that are not a [`Hint`], in the order they were listed
+    pub fn unknown(&self) -> &[xcb::x::Atom] {
+        &self.unknown
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use xcb::Xid;
+
+    use super::*;
+    use crate::mock::PropertyStore;
+    use crate::Property;
+
+    #[test]
+    fn hint_names() {
+        assert_eq!(Hint::ALL.len(), 79);
+        for hint in Hint::ALL {
+            assert_eq!(Hint::from_name(hint.name()), Some(*hint));
+        }
+        assert_eq!(Hint::WmStateFullscreen.name(), "_NET_WM_STATE_FULLSCREEN");
+        assert_eq!(Hint::from_name("UTF8_STRING"), None);
+    }
+
+    #[test]
+    fn supported_and_unknown() {
+        let store = PropertyStore::new();
+        let ewmh_con = Connection::connect(&store);
+
+        let none = SupportedHints::fetch(&ewmh_con).unwrap();
+        assert!(!none.supports(Hint::Supported));
+        assert_eq!(none.hints().count(), 0);
+
+        let supported = [
+            "_NET_WM_STATE_FULLSCREEN",
+            "_KDE_NET_WM_FRAME_STRUT",
+            "_NET_SUPPORTED",
+            "_NET_WM_STATE",
+        ];
+        store.set_property(
+            store.root(),
+            "_NET_SUPPORTED",
+            Property::format32(
+                xcb::x::ATOM_ATOM,
+                supported
+                    .iter()
+                    .map(|name| store.atom(name).resource_id())
+                    .collect(),
+            ),
+        );
+
+        let hints = SupportedHints::fetch(&ewmh_con).unwrap();
+        assert!(hints.supports(Hint::WmStateFullscreen));
+        assert!(hints.supports_all(&[Hint::WmState, Hint::WmStateFullscreen]));
+        assert!(!hints.supports_all(&[Hint::WmState, Hint::WmStateAbove]));
+        assert_eq!(
+            hints.hints().collect::<Vec<_>>(),
+            [Hint::Supported, Hint::WmState, Hint::WmStateFullscreen]
+        );
+        assert_eq!(hints.unknown(), [store.atom("_KDE_NET_WM_FRAME_STRUT")]);
+    }
+}
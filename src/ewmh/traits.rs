@@ -1,4 +1,11 @@
+use std::convert::TryFrom;
+
+use crate::backend::{
+    BackendRequest, BackendVoidRequest, PropertyCookie, PropertyCookieUnchecked, VoidCookie,
+    VoidCookieChecked,
+};
 use crate::ewmh::connection::Connection;
+use crate::property::Property;
 
 /// Default for a request sent by [`Connection::send_request`]
 ///
@@ -10,28 +17,28 @@ use crate::ewmh::connection::Connection;
 /// This is a bit mind-bending and makes these traits hard to grasp. This was however done to align
 /// with how the parent [`xcb`] crate works.
 pub trait EwmhRequest<'a> {
-    /// The underlying [`xcb::Request`] used to do the actual heavy lifting
-    type XcbRequest: xcb::Request;
+    /// The underlying request description used to do the actual heavy lifting
+    type XcbRequest: BackendRequest;
 
-    /// The ewmh wrapper for the [`xcb::Request::Cookie`]
-    type EwmhCookie: EwmhCookie<XcbCookie = <Self::XcbRequest as xcb::Request>::Cookie>;
+    /// The ewmh wrapper for the [`BackendRequest::Cookie`]
+    type EwmhCookie: EwmhCookie<XcbCookie = <Self::XcbRequest as BackendRequest>::Cookie>;
 
-    /// Construct the [`xcb::Request`]. This is implementation specific.
+    /// Construct the request description. This is implementation specific.
     fn xcb_request(&'a self, con: &Connection) -> Self::XcbRequest;
 
-    /// Convert the [`xcb::Request::Cookie`] to the ewmh cookie wrapper
+    /// Convert the [`crate::backend`] cookie to the ewmh cookie wrapper
     fn convert_cookie(
         &'a self,
-        xcb_cookie: <Self::XcbRequest as xcb::Request>::Cookie,
+        xcb_cookie: <Self::XcbRequest as BackendRequest>::Cookie,
     ) -> Self::EwmhCookie;
 
-    /// Default implementation. Delegate the request to the [`xcb::Connection`] and wrap the
+    /// Default implementation. Delegate the request to the [`crate::backend::Backend`] and wrap the
     /// response in the ewmh cookie wrapper.
     ///
     /// There is usually no need to override the provided default implementation.
     fn send(&'a self, con: &Connection) -> Self::EwmhCookie {
         let xcb_request = self.xcb_request(con);
-        let xcb_cookie = con.con.send_request(&xcb_request);
+        let xcb_cookie = xcb_request.send(con.con);
         self.convert_cookie(xcb_cookie)
     }
 }
@@ -44,20 +51,20 @@ pub trait EwmhRequest<'a> {
 ///
 /// For [`xcb-wm`] this means either `SetProperty` or `ClientMessage` requests.
 pub trait EwmhVoidRequestChecked<'a> {
-    type XcbRequest: xcb::RequestWithoutReply;
+    type XcbRequest: BackendVoidRequest;
 
-    /// Construct the [`xcb::Request`]. This is implementation specific.
+    /// Construct the request description. This is implementation specific.
     fn xcb_request(&'a self, con: &Connection) -> Self::XcbRequest;
 
-    /// Default implementation. Delegate the request to the [`xcb::Connection`].
+    /// Default implementation. Delegate the request to the [`crate::backend::Backend`].
     ///
-    /// Since we don't need to convert the reply we just re-use the [`xcb::VoidCookieChecked`]
+    /// Since we don't need to convert the reply we just re-use the [`VoidCookieChecked`]
     /// without wrapping it.
     ///
     /// There is usually no need to override the provided default implementation.
-    fn send(&'a self, con: &Connection) -> xcb::VoidCookieChecked {
+    fn send(&'a self, con: &Connection) -> VoidCookieChecked {
         let xcb_request = self.xcb_request(con);
-        con.con.send_request_checked(&xcb_request)
+        xcb_request.send_checked(con.con)
     }
 }
 
@@ -72,13 +79,13 @@ pub trait EwmhVoidRequestChecked<'a> {
 pub trait EwmhPropertyRequestUnchecked {
     type EwmhCookie: EwmhPropertyCookieUnchecked;
 
-    /// Construct the [`xcb::Request`]. This is implementation specific.
+    /// Construct the [`xcb::x::GetProperty`]. This is implementation specific.
     fn xcb_request(&self, con: &Connection) -> xcb::x::GetProperty;
 
-    /// Convert the [`xcb::Request::Cookie`] to the ewmh cookie wrapper
-    fn convert_cookie(&self, xcb_cookie: xcb::x::GetPropertyCookieUnchecked) -> Self::EwmhCookie;
+    /// Convert the [`crate::backend`] cookie to the ewmh cookie wrapper
+    fn convert_cookie(&self, xcb_cookie: PropertyCookieUnchecked) -> Self::EwmhCookie;
 
-    /// Default implementation. Delegate the request to the [`xcb::Connection`].
+    /// Default implementation. Delegate the request to the [`crate::backend::Backend`].
     ///
     /// Returns a wrapped cookie that tracks the EwmhReply type which is needed
     /// to convert the reply after it is retrieved via [`Connection::wait_for_reply_unchecked`].
@@ -86,12 +93,12 @@ pub trait EwmhPropertyRequestUnchecked {
     /// There is usually no need to override the provided default implementation.
     fn send(&self, con: &Connection) -> Self::EwmhCookie {
         let xcb_request = self.xcb_request(con);
-        let xcb_cookie = con.con.send_request_unchecked(&xcb_request);
+        let xcb_cookie = con.con.get_property_unchecked(&xcb_request);
         self.convert_cookie(xcb_cookie)
     }
 }
 
-/// Most generic ewmh wrapper for an [`xcb::Cookie`]
+/// Most generic ewmh wrapper for a [`crate::backend`] cookie
 ///
 /// This is needed for [`EwmhRequest`] which basically cuts through
 /// all traits. I.e. it is an _unchecked_ request for void requests
@@ -100,18 +107,18 @@ pub trait EwmhPropertyRequestUnchecked {
 /// At the same time it may have a _response_ for reply requests or it
 /// may have no _response_ for void requests.
 pub trait EwmhCookie {
-    /// The wrapped [`xcb::Cookie`]
-    type XcbCookie: xcb::Cookie;
+    /// The wrapped [`crate::backend`] cookie
+    type XcbCookie;
 }
 
-/// Blanket impl for [`xcb::VoidCookie`] (basically unchecked void cookies)
+/// Blanket impl for [`VoidCookie`] (basically unchecked void cookies)
 ///
 /// This is implemented here because there are no special ewmh cookie wrapper
-/// for SetProperty and ClientMessage requests needed. [`xcb::VoidCookie`] satisfies
+/// for SetProperty and ClientMessage requests needed. [`VoidCookie`] satisfies
 /// all that is needed for [`ewmh`]. In order to use it with [`EwmhRequest`] we need
 /// this impl.
-impl EwmhCookie for xcb::VoidCookie {
-    type XcbCookie = xcb::VoidCookie;
+impl EwmhCookie for VoidCookie {
+    type XcbCookie = VoidCookie;
 }
 
 /// ewmh wrapper for checked GetProperty requests
@@ -122,11 +129,11 @@ impl EwmhCookie for xcb::VoidCookie {
 pub trait EwmhPropertyCookieChecked {
     type Reply: EwmhPropertyReply;
 
-    /// Retrieve the inner [`xcb::Cookie`]
+    /// Retrieve the inner [`PropertyCookie`]
     ///
-    /// This is needed for sending a [`xcb::Connection::wait_reply`] request which
-    /// expects the xcb cookie
-    fn inner(self) -> xcb::x::GetPropertyCookie;
+    /// This is needed for [`crate::backend::Backend::wait_for_property`] which expects the
+    /// backend cookie
+    fn inner(self) -> PropertyCookie;
 }
 
 /// ewmh wrapper for unchecked GetProperty requests
@@ -137,20 +144,22 @@ pub trait EwmhPropertyCookieChecked {
 pub trait EwmhPropertyCookieUnchecked {
     type Reply: EwmhPropertyReply;
 
-    /// Retrieve the inner [`xcb::Cookie`]
+    /// Retrieve the inner [`PropertyCookieUnchecked`]
     ///
-    /// This is needed for sending a [`xcb::Connection::wait_reply_unchecked`] request which
-    /// expects the xcb cookie
-    fn inner(self) -> xcb::x::GetPropertyCookieUnchecked;
+    /// This is needed for [`crate::backend::Backend::wait_for_property_unchecked`] which
+    /// expects the backend cookie
+    fn inner(self) -> PropertyCookieUnchecked;
 }
 
 /// Marker trait with blanket implementation for everything that implements
-/// [`From<xcb::x::GetPropertyReply`].
+/// [`TryFrom<Property>`] with a [`crate::Error`].
 ///
 /// The ewmh property reply trait is used to convert a generic reply to a [`xcb::x::GetProperty`]
 /// request to a specific ewmh reply struct.
 ///
 /// The connection between a ewmh request and the reply struct is made via ewmh property cookies
 /// ([`EwmhPropertyCookieChecked`] and [`EwmhPropertyCookieUnchecked`]
-pub trait EwmhPropertyReply: From<xcb::x::GetPropertyReply> {}
-impl<T> EwmhPropertyReply for T where T: From<xcb::x::GetPropertyReply> {}
+///
+/// Decoding fails with a [`crate::Error`] if the property is missing or malformed.
+pub trait EwmhPropertyReply: TryFrom<Property, Error = crate::Error> {}
+impl<T> EwmhPropertyReply for T where T: TryFrom<Property, Error = crate::Error> {}
@@ -0,0 +1,76 @@
+//! Typed `PropertyNotify` handling for `ewmh` properties
+//!
+//! See [`crate::watch`]. [`Watcher`] maps the events to an [`EwmhProperty`] and decodes the new
+//! value as an [`EwmhEvent`].
+
+use crate::ewmh::proto::*;
+use crate::ewmh::Connection;
+use crate::Result;
+
+/// Watches windows for changes of `ewmh` properties
+pub type Watcher<'a> = crate::watch::Watcher<'a, Connection<'a>>;
+
+watched_properties! {
+    protocol="ewmh", property=EwmhProperty, event=EwmhEvent;
+    Supported = "_NET_SUPPORTED", SupportedChanged(Vec<xcb::x::Atom>),
+        |_window| GetSupported, |r| r.atoms;
+    ClientList = "_NET_CLIENT_LIST", ClientListChanged(Vec<xcb::x::Window>),
+        |_window| GetClientList, |r| r.clients;
+    ClientListStacking = "_NET_CLIENT_LIST_STACKING", ClientListStackingChanged(Vec<xcb::x::Window>),
+        |_window| GetClientListStacking, |r| r.clients;
+    NumberOfDesktops = "_NET_NUMBER_OF_DESKTOPS", NumberOfDesktopsChanged(u32),
+        |_window| GetNumberOfDesktops, |r| r.desktops;
+    DesktopGeometry = "_NET_DESKTOP_GEOMETRY", DesktopGeometryChanged(GetDesktopGeometryReply),
+        |_window| GetDesktopGeometry, |r| r;
+    DesktopViewport = "_NET_DESKTOP_VIEWPORT", DesktopViewportChanged(GetDesktopViewportReply),
+        |_window| GetDesktopViewport, |r| r;
+    CurrentDesktop = "_NET_CURRENT_DESKTOP", CurrentDesktopChanged(u32),
+        |_window| GetCurrentDesktop, |r| r.desktop;
+    DesktopNames = "_NET_DESKTOP_NAMES", DesktopNamesChanged(Vec<String>),
+        |_window| GetDesktopNames, |r| r.names;
+    ActiveWindow = "_NET_ACTIVE_WINDOW", ActiveWindowChanged(xcb::x::Window),
+        |_window| GetActiveWindow, |r| r.window;
+    Workarea = "_NET_WORKAREA", WorkareaChanged(GetWorkareaReply),
+        |_window| GetWorkarea, |r| r;
+    SupportingWmCheck = "_NET_SUPPORTING_WM_CHECK", SupportingWmCheckChanged(xcb::x::Window),
+        |_window| GetSupportingWmCheck, |r| r.window;
+    VirtualRoots = "_NET_VIRTUAL_ROOTS", VirtualRootsChanged(xcb::x::Window),
+        |_window| GetVirtualRoots, |r| r.window;
+    ShowingDesktop = "_NET_SHOWING_DESKTOP", ShowingDesktopChanged(bool),
+        |_window| GetShowingDesktop, |r| r.is_showing_desktop;
+    WmName = "_NET_WM_NAME", WmNameChanged(String),
+        |window| GetWmName(window), |r| r.name;
+    WmVisibleName = "_NET_WM_VISIBLE_NAME", WmVisibleNameChanged(String),
+        |window| GetWmVisibleName(window), |r| r.name;
+    WmIconName = "_NET_WM_ICON_NAME", WmIconNameChanged(String),
+        |window| GetWmIconName(window), |r| r.name;
+    WmVisibleIconName = "_NET_WM_VISIBLE_ICON_NAME", WmVisibleIconNameChanged(String),
+        |window| GetWmVisibleIconName(window), |r| r.name;
+    WmDesktop = "_NET_WM_DESKTOP", WmDesktopChanged(u32),
+        |window| GetWmDesktop(window), |r| r.desktop;
+    WmWindowType = "_NET_WM_WINDOW_TYPE", WmWindowTypeChanged(Vec<xcb::x::Atom>),
+        |window| GetWmWindowType(window), |r| r.window_types;
+    WmState = "_NET_WM_STATE", WmStateChanged(Vec<xcb::x::Atom>),
+        |window| GetWmState(window), |r| r.states;
+    WmIcon = "_NET_WM_ICON", WmIconChanged(Vec<WmIcon>),
+        |window| GetWmIcon(window), |r| r.icons;
+    WmPid = "_NET_WM_PID", WmPidChanged(u32),
+        |window| GetWmPid(window), |r| r.pid;
+    FrameExtents = "_NET_FRAME_EXTENTS", FrameExtentsChanged(GetFrameExtentsReply),
+        |window| GetFrameExtents(window), |r| r;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EwmhProperty;
+
+    #[test]
+    fn property_names() {
+        assert_eq!(
+            EwmhProperty::from_name("_NET_ACTIVE_WINDOW"),
+            Some(EwmhProperty::ActiveWindow)
+        );
+        assert_eq!(EwmhProperty::DesktopNames.name(), "_NET_DESKTOP_NAMES");
+        assert_eq!(EwmhProperty::from_name("WM_NAME"), None);
+    }
+}
@@ -0,0 +1,207 @@
+//! High-level actions on a client window
+//!
+//! [`WindowHandle`] binds a [`Connection`] and a window and sends the `Send*` client messages of
+//! [`crate::ewmh::proto`] for common actions. Every action is checked, an error means the X server
+//! rejected the message. Whether the window manager honours it shows in the window properties
+//! afterwards.
+
+use crate::ewmh::proto::{
+    SendActiveWindow, SendCloseWindow, SendRestackWindow, SendWmChangeState, SendWmDesktop,
+    SendWmState, WmStateAction,
+};
+use crate::ewmh::Connection;
+use crate::Result;
+
+/// Source indication of requests from normal applications
+pub const SOURCE_APPLICATION: u32 = 1;
+
+/// Source indication of requests from pagers, taskbars and other tools acting for the user
+pub const SOURCE_PAGER: u32 = 2;
+
+/// A window and the connection to send actions for it
+///
+/// Actions are sent with [`SOURCE_APPLICATION`] and `CurrentTime` by default. Pagers should use
+/// [`WindowHandle::as_pager`], which window managers trust more, e.g. for focus stealing
+/// prevention. If the action was triggered by user input, pass the time of that event with
+/// [`WindowHandle::timestamp`].
+#[derive(Clone, Copy)]
+pub struct WindowHandle<'a> {
+    con: &'a Connection<'a>,
+    window: xcb::x::Window,
+    source_indication: u32,
+    timestamp: u32,
+}
+
+impl<'a> WindowHandle<'a> {
+    pub fn new(con: &'a Connection<'a>, window: xcb::x::Window) -> WindowHandle<'a> {
+        WindowHandle {
+            con,
+            window,
+            source_indication: SOURCE_APPLICATION,
+            timestamp: xcb::x::CURRENT_TIME,
+        }
+    }
+
+    /// Send actions with [`SOURCE_PAGER`]
+    pub fn as_pager(mut self) -> WindowHandle<'a> {
+        self.source_indication = SOURCE_PAGER;
+        self
+    }
+
+    /// X server time of the user action that triggered the actions
+    pub fn timestamp(mut self, timestamp: xcb::x::Timestamp) -> WindowHandle<'a> {
+        self.timestamp = timestamp;
+        self
+    }
+
+    pub fn window(&self) -> xcb::x::Window {
+        self.window
+    }
+
+    /// Switch to the desktop of the window, raise and focus it
+    pub fn activate(&self) -> Result<()> {
+        self.con.send_and_check_request(&SendActiveWindow::new(
+            self.con,
+            self.window,
+            self.source_indication,
+            self.timestamp,
+            None,
+        )?)
+    }
+
+    /// Ask the window manager to close the window
+    ///
+    /// The window manager usually sends `WM_DELETE_WINDOW` to the client, which may refuse.
+    pub fn close(&self) -> Result<()> {
+        self.con.send_and_check_request(&SendCloseWindow::new(
+            self.con,
+            self.window,
+            self.source_indication,
+            self.timestamp,
+        )?)
+    }
+
+    pub fn set_fullscreen(&self, fullscreen: bool) -> Result<()> {
+        let action = if fullscreen {
+            WmStateAction::Add
+        } else {
+            WmStateAction::Remove
+        };
+        self.change_state(action, "_NET_WM_STATE_FULLSCREEN", None)
+    }
+
+    /// Maximize the window in both directions, or restore it if it is maximized
+    pub fn toggle_maximized(&self) -> Result<()> {
+        self.change_state(
+            WmStateAction::Toggle,
+            "_NET_WM_STATE_MAXIMIZED_VERT",
+            Some("_NET_WM_STATE_MAXIMIZED_HORZ"),
+        )
+    }
+
+    /// Move the window to `desktop`, `0xFFFFFFFF` shows it on all desktops
+    pub fn move_to_desktop(&self, desktop: u32) -> Result<()> {
+        self.con.send_and_check_request(&SendWmDesktop::new(
+            self.con,
+            self.window,
+            desktop,
+            self.source_indication,
+        )?)
+    }
+
+    /// Iconify the window, see [`SendWmChangeState`]
+    pub fn minimize(&self) -> Result<()> {
+        self.con
+            .send_and_check_request(&SendWmChangeState::new(self.con, self.window)?)
+    }
+
+    /// Raise the window above all its siblings
+    ///
+    /// `_NET_RESTACK_WINDOW` is only meant for pagers, so it is always sent with
+    /// [`SOURCE_PAGER`]. Window managers may ignore it otherwise.
+    pub fn raise(&self) -> Result<()> {
+        self.con.send_and_check_request(&SendRestackWindow::new(
+            self.con,
+            self.window,
+            SOURCE_PAGER,
+            None,
+            xcb::x::StackMode::Above,
+        )?)
+    }
+
+    fn change_state(&self, action: WmStateAction, first: &str, second: Option<&str>) -> Result<()> {
+        let second = match second {
+            Some(name) => self.con.atom(name)?,
+            None => xcb::x::ATOM_NONE,
+        };
+
+        self.con.send_and_check_request(&SendWmState::new(
+            self.con,
+            self.window,
+            action,
+            [self.con.atom(first)?, second],
+            self.source_indication,
+        )?)
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use xcb::Xid;
+
+    use super::*;
+    use crate::backend::Backend;
+    use crate::mock::PropertyStore;
+
+    #[test]
+    fn actions() {
+        let store = PropertyStore::new();
+        let window = store.create_window();
+        let ewmh_con = Connection::connect(&store);
+        let atom = |name| store.atom(name).resource_id();
+
+        let handle = WindowHandle::new(&ewmh_con, window);
+        handle.activate().unwrap();
+        handle.set_fullscreen(true).unwrap();
+        handle.toggle_maximized().unwrap();
+        handle.as_pager().timestamp(42).close().unwrap();
+        handle.as_pager().move_to_desktop(3).unwrap();
+        handle.minimize().unwrap();
+        handle.raise().unwrap();
+
+        let messages = store.client_messages();
+        let sent: Vec<_> = messages
+            .iter()
+            .map(|m| {
+                assert_eq!((m.destination, m.window), (store.root(), window));
+                (store.atom_name(m.r#type).unwrap(), m.data32().unwrap())
+            })
+            .collect();
+        let message = |name: &str, data| (name.to_owned(), data);
+
+        assert_eq!(
+            sent,
+            [
+                message("_NET_ACTIVE_WINDOW", [1, 0, 0, 0, 0]),
+                message(
+                    "_NET_WM_STATE",
+                    [1, atom("_NET_WM_STATE_FULLSCREEN"), 0, 1, 0]
+                ),
+                message(
+                    "_NET_WM_STATE",
+                    [
+                        2,
+                        atom("_NET_WM_STATE_MAXIMIZED_VERT"),
+                        atom("_NET_WM_STATE_MAXIMIZED_HORZ"),
+                        1,
+                        0
+                    ]
+                ),
+                message("_NET_CLOSE_WINDOW", [42, 2, 0, 0, 0]),
+                message("_NET_WM_DESKTOP", [3, 2, 0, 0, 0]),
+                message("WM_CHANGE_STATE", [3, 0, 0, 0, 0]),
+                message("_NET_RESTACK_WINDOW", [2, 0, 0, 0, 0]),
+            ]
+        );
+    }
+}
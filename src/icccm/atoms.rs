@@ -1,7 +1,17 @@
 use std::collections::HashMap;
 
+use crate::atoms::AtomCache;
+
 // TODO use xcb::atom_struct!{} for this?
-const ATOM_NAMES: [&str; 1] = ["WM_COLORMAP_WINDOWS"];
+pub(crate) const ATOM_NAMES: [&str; 7] = [
+    "WM_COLORMAP_WINDOWS",
+    "WM_CLIENT_LEADER",
+    "WM_WINDOW_ROLE",
+    "SM_CLIENT_ID",
+    "WM_LOCALE_NAME",
+    "UTF8_STRING",
+    "COMPOUND_TEXT",
+];
 
 /// Interned [`xcb::x::Atom`]s for the `icccm` protocol
 ///
@@ -12,33 +22,35 @@ const ATOM_NAMES: [&str; 1] = ["WM_COLORMAP_WINDOWS"];
 #[allow(non_snake_case)]
 pub struct Atoms {
     pub WM_COLORMAP_WINDOWS: xcb::x::Atom,
+    pub WM_CLIENT_LEADER: xcb::x::Atom,
+    pub WM_WINDOW_ROLE: xcb::x::Atom,
+    pub SM_CLIENT_ID: xcb::x::Atom,
+    pub WM_LOCALE_NAME: xcb::x::Atom,
+    pub UTF8_STRING: xcb::x::Atom,
+    pub COMPOUND_TEXT: xcb::x::Atom,
 }
 
 impl Atoms {
-    pub(crate) fn intern(con: &xcb::Connection) -> Atoms {
-        let mut cookies: HashMap<&'static str, xcb::x::InternAtomCookie> = HashMap::new();
-
-        for atom in ATOM_NAMES {
-            let intern_atom = xcb::x::InternAtom {
-                only_if_exists: false,
-                name: atom.as_bytes(),
-            };
-
-            cookies.insert(atom, con.send_request(&intern_atom));
-        }
-
-        let interned_atoms: HashMap<&'static str, xcb::x::Atom> = cookies
-            .into_iter()
-            .map(|(atom_name, cookie)| (atom_name, con.wait_for_reply(cookie).unwrap()))
-            .map(|(atom_name, reply)| (atom_name, reply.atom()))
-            .collect();
-
-        Atoms::from_interned_atoms(interned_atoms)
+    pub(crate) fn intern(
+        con: &dyn crate::backend::Backend,
+        cache: &AtomCache,
+    ) -> crate::Result<Atoms> {
+        let atoms = cache.intern_all(con, &ATOM_NAMES)?;
+
+        Ok(Atoms::from_interned_atoms(
+            ATOM_NAMES.iter().copied().zip(atoms).collect(),
+        ))
     }
 
     fn from_interned_atoms(mut atoms: HashMap<&'static str, xcb::x::Atom>) -> Atoms {
         Atoms {
             WM_COLORMAP_WINDOWS: atoms.remove("WM_COLORMAP_WINDOWS").unwrap(),
+            WM_CLIENT_LEADER: atoms.remove("WM_CLIENT_LEADER").unwrap(),
+            WM_WINDOW_ROLE: atoms.remove("WM_WINDOW_ROLE").unwrap(),
+            SM_CLIENT_ID: atoms.remove("SM_CLIENT_ID").unwrap(),
+            WM_LOCALE_NAME: atoms.remove("WM_LOCALE_NAME").unwrap(),
+            UTF8_STRING: atoms.remove("UTF8_STRING").unwrap(),
+            COMPOUND_TEXT: atoms.remove("COMPOUND_TEXT").unwrap(),
         }
     }
 }
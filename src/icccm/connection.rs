@@ -1,7 +1,14 @@
+use std::sync::{Arc, OnceLock};
+
+use crate::atoms::AtomCache;
+#[cfg(feature = "async")]
+use crate::backend::PollBackend;
+use crate::backend::{Backend, VoidCookieChecked};
+
 use crate::icccm::atoms::Atoms;
 use crate::icccm::traits::{
-    IcccmPropertyCookieChecked, IcccmPropertyCookieUnchecked, IcccmPropertyRequestUnchecked,
-    IcccmRequest, IcccmVoidRequestChecked,
+    IcccmPropertyCookieChecked, IcccmPropertyCookieUnchecked, IcccmPropertyReply,
+    IcccmPropertyRequestUnchecked, IcccmRequest, IcccmVoidRequestChecked,
 };
 
 /// The main `icccm` entry point
@@ -21,21 +28,99 @@ use crate::icccm::traits::{
 /// [1] <https://www.x.org/releases/current/doc/xproto/x11protocol.html#Predefined_Atoms>
 ///
 pub struct Connection<'a> {
-    pub(crate) con: &'a xcb::Connection,
+    pub(crate) con: &'a dyn Backend,
+
+    /// Root window of the screen the connection was created for
+    root: xcb::x::Window,
+
+    /// Interned atoms, possibly shared with other connections
+    cache: Arc<AtomCache>,
 
-    /// Interned [`Atoms`] for the `icccm` protocol
-    pub atoms: Atoms,
+    /// Atoms of the `icccm` protocol, interned on first access
+    atoms: OnceLock<Atoms>,
 }
 
 #[allow(dead_code)]
 impl<'a> Connection<'a> {
-    pub fn connect(xcb_con: &'a xcb::Connection) -> Connection<'a> {
+    /// Wrap `backend` (usually an [`xcb::Connection`]) for the first screen without any
+    /// round-trip
+    ///
+    /// Atoms are interned on first use.
+    ///
+    /// # Panics
+    ///
+    /// If the X server has no screen. Use [`Connection::try_connect`] to handle this.
+    pub fn connect(backend: &'a dyn Backend) -> Connection<'a> {
+        Connection::try_connect(backend, 0).unwrap()
+    }
+
+    /// Wrap `backend` for `screen` without any round-trip
+    ///
+    /// `screen` is usually the preferred screen returned by [`xcb::Connection::connect`].
+    /// Fails with [`crate::Error::NoSuchScreen`] if the X server has no such screen.
+    pub fn try_connect(backend: &'a dyn Backend, screen: i32) -> crate::Result<Connection<'a>> {
+        Ok(Connection::with_cache(
+            backend,
+            backend.screen_root(screen)?,
+            Arc::new(AtomCache::new()),
+        ))
+    }
+
+    /// Wrap `backend` for the first screen and intern all atoms up front in one round-trip
+    pub fn connect_eager(backend: &'a dyn Backend) -> crate::Result<Connection<'a>> {
+        let con = Connection::try_connect(backend, 0)?;
+        con.atoms()?;
+        Ok(con)
+    }
+
+    pub(crate) fn with_cache(
+        backend: &'a dyn Backend,
+        root: xcb::x::Window,
+        cache: Arc<AtomCache>,
+    ) -> Connection<'a> {
         Connection {
-            con: xcb_con,
-            atoms: Atoms::intern(xcb_con),
+            con: backend,
+            root,
+            cache,
+            atoms: OnceLock::new(),
         }
     }
 
+    /// Root window of the screen this connection was created for
+    pub fn root(&self) -> xcb::x::Window {
+        self.root
+    }
+
+    /// All atoms of the `icccm` protocol
+    ///
+    /// The first call interns all of them in one round-trip.
+    pub fn atoms(&self) -> crate::Result<&Atoms> {
+        if let Some(atoms) = self.atoms.get() {
+            return Ok(atoms);
+        }
+
+        let atoms = Atoms::intern(self.con, &self.cache)?;
+        Ok(self.atoms.get_or_init(|| atoms))
+    }
+
+    /// The atom for `name`, interned on first use
+    pub fn atom(&self, name: &str) -> crate::Result<xcb::x::Atom> {
+        self.cache.intern(self.con, name)
+    }
+
+    /// The name of `atom`, looked up on first use
+    pub fn atom_name(&self, atom: xcb::x::Atom) -> crate::Result<String> {
+        self.cache.name(self.con, atom)
+    }
+
+    /// The atom for `name` to put into a request
+    ///
+    /// If interning fails the request is built with `ATOM_NONE`. The server rejects it and the
+    /// error surfaces when the request is checked or its reply is retrieved.
+    pub(crate) fn request_atom(&self, name: &str) -> xcb::x::Atom {
+        self.atom(name).unwrap_or(xcb::x::ATOM_NONE)
+    }
+
     pub fn send_request<'b, R>(&self, request: &'b R) -> R::IcccmCookie
     where
         R: IcccmRequest<'b>,
@@ -43,7 +128,7 @@ impl<'a> Connection<'a> {
         request.send(self)
     }
 
-    pub fn send_request_checked<'b, R>(&self, request: &'b R) -> xcb::VoidCookieChecked
+    pub fn send_request_checked<'b, R>(&self, request: &'b R) -> VoidCookieChecked
     where
         R: IcccmVoidRequestChecked<'b>,
     {
@@ -57,34 +142,119 @@ impl<'a> Connection<'a> {
         request.send(self)
     }
 
-    pub fn send_and_check_request<'b, R>(&self, request: &'b R) -> xcb::ProtocolResult<()>
+    pub fn send_and_check_request<'b, R>(&self, request: &'b R) -> crate::Result<()>
     where
         R: IcccmVoidRequestChecked<'b>,
     {
         self.con.check_request(request.send(self))
     }
 
-    pub fn wait_for_reply<C>(&self, cookie: C) -> xcb::Result<C::Reply>
+    pub fn wait_for_reply<C>(&self, cookie: C) -> crate::Result<C::Reply>
+    where
+        C: IcccmPropertyCookieChecked,
+    {
+        let property = self.con.wait_for_property(cookie.inner())?;
+        C::Reply::from_property(self, property)
+    }
+
+    /// Like [`Connection::wait_for_reply`] but a property that is not set on the window is
+    /// `None` instead of [`crate::Error::PropertyMissing`].
+    ///
+    /// A property that is set to an empty list is still `Some`.
+    pub fn wait_for_optional_reply<C>(&self, cookie: C) -> crate::Result<Option<C::Reply>>
     where
         C: IcccmPropertyCookieChecked,
     {
-        let xcb_reply = self.con.wait_for_reply(cookie.inner());
-        xcb_reply.map(|reply| reply.into())
+        match self.wait_for_reply(cookie) {
+            Ok(reply) => Ok(Some(reply)),
+            Err(crate::Error::PropertyMissing) => Ok(None),
+            Err(e) => Err(e),
+        }
     }
 
-    pub fn wait_for_reply_unchecked<C>(&self, cookie: C) -> xcb::ConnResult<Option<C::Reply>>
+    pub fn wait_for_reply_unchecked<C>(&self, cookie: C) -> crate::Result<Option<C::Reply>>
     where
         C: IcccmPropertyCookieUnchecked,
     {
-        let xcb_reply = self.con.wait_for_reply_unchecked(cookie.inner());
-        xcb_reply.map(|reply| reply.map(|reply| reply.into()))
+        let property = self.con.wait_for_property_unchecked(cookie.inner())?;
+        property
+            .map(|property| C::Reply::from_property(self, property))
+            .transpose()
     }
 
-    pub fn check_request(&self, cookie: xcb::VoidCookieChecked) -> xcb::ProtocolResult<()> {
+    pub fn check_request(&self, cookie: VoidCookieChecked) -> crate::Result<()> {
         self.con.check_request(cookie)
     }
 }
 
+/// A [`Connection`] over a [`PollBackend`] that can wait for replies without blocking
+///
+/// It derefs to [`Connection`] for everything else. Backends that cannot poll for a reply, like
+/// `X11rbBackend`, are rejected at compile time.
+#[cfg(feature = "async")]
+pub struct AsyncConnection<'a> {
+    con: Connection<'a>,
+    backend: &'a dyn PollBackend,
+}
+
+#[cfg(feature = "async")]
+impl<'a> AsyncConnection<'a> {
+    /// Like [`Connection::connect`]
+    pub fn connect<B: PollBackend>(backend: &'a B) -> AsyncConnection<'a> {
+        AsyncConnection::try_connect(backend, 0).unwrap()
+    }
+
+    /// Like [`Connection::try_connect`]
+    pub fn try_connect<B: PollBackend>(
+        backend: &'a B,
+        screen: i32,
+    ) -> crate::Result<AsyncConnection<'a>> {
+        Ok(AsyncConnection {
+            con: Connection::try_connect(backend, screen)?,
+            backend,
+        })
+    }
+
+    /// Like [`Connection::send_request`] but also flushes the request to the server
+    pub async fn send_request_async<'b, R>(&self, request: &'b R) -> crate::Result<R::IcccmCookie>
+    where
+        R: IcccmRequest<'b>,
+    {
+        let cookie = request.send(&self.con);
+        self.backend.flush()?;
+        Ok(cookie)
+    }
+
+    /// Like [`Connection::wait_for_reply`] but does not block
+    ///
+    /// `readable` is called whenever the reply has not arrived yet. It must return a future that
+    /// resolves once the file descriptor of the [`xcb::Connection`] (see
+    /// [`std::os::unix::io::AsRawFd`]) is readable. With tokio, for example, this is
+    /// `AsyncFd::readable` plus clearing the readiness.
+    pub async fn wait_for_reply_async<C, R, F>(
+        &self,
+        cookie: C,
+        readable: R,
+    ) -> crate::Result<C::Reply>
+    where
+        C: IcccmPropertyCookieChecked,
+        R: FnMut() -> F,
+        F: std::future::Future<Output = std::io::Result<()>>,
+    {
+        let property = crate::util::poll_for_reply(self.backend, &cookie.inner(), readable).await?;
+        C::Reply::from_property(&self.con, property)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'a> std::ops::Deref for AsyncConnection<'a> {
+    type Target = Connection<'a>;
+
+    fn deref(&self) -> &Connection<'a> {
+        &self.con
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::icccm::proto::{
@@ -119,7 +289,7 @@ mod tests {
 
         let request = SetWmName::new(window, xcb::x::ATOM_STRING, name);
         let cookie = icccm_con.send_request_checked(&request);
-        let reply = xcb_con.check_request(cookie);
+        let reply = icccm_con.check_request(cookie);
         println!("{:?}", reply);
     }
 
@@ -187,7 +357,7 @@ mod tests {
 
         let urgent = SetWmHints::new(window, &mut wm_hints);
         let cookie = icccm_con.send_request_checked(&urgent);
-        let reply = xcb_con.check_request(cookie);
+        let reply = icccm_con.check_request(cookie);
         println!("{:?}", reply);
     }
 }
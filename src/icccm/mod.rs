@@ -2,12 +2,24 @@
 //!
 //! see: <https://www.x.org/releases/X11R7.7/doc/xorg-docs/icccm/icccm.html>
 
-mod traits;
+pub(crate) mod traits;
+pub use traits::{
+    IcccmCookie, IcccmPropertyCookieChecked, IcccmPropertyCookieUnchecked, IcccmPropertyReply,
+    IcccmPropertyRequestUnchecked, IcccmRequest, IcccmVoidRequestChecked,
+};
 
-mod atoms;
+pub(crate) mod atoms;
 
 mod connection;
+#[cfg(feature = "async")]
+pub use connection::AsyncConnection;
 pub use connection::Connection;
 
+mod session;
+pub use session::SessionIdentity;
+
+mod watch;
+pub use watch::{IcccmEvent, IcccmProperty, Watcher};
+
 pub mod proto;
 pub use proto::*;
@@ -5,30 +5,33 @@
 #![allow(dead_code)]
 
 use bitflags::bitflags;
-use std::convert::TryInto;
-use std::io::BufRead;
-use std::mem;
+use std::convert::TryFrom;
 use xcb::{Xid, XidNew};
 
+use crate::icccm::proto::text::{
+    decode_text, decode_text_list_reply, decode_text_reply, encode_text, reply_encoding,
+    TextEncoding,
+};
 use crate::icccm::traits::*;
 use crate::icccm::Connection;
+use crate::util::property_value;
+use crate::{Error, Property, Result};
 
 use paste::paste; // Needed for macros
 
 // WM_NAME, TEXT
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetWmNameReply {
     pub name: String,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetWmNameReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        let x = reply.value::<u8>().into();
-        GetWmNameReply {
-            // TODO Compound string
-            name: String::from_utf8(x).unwrap(),
-        }
+impl IcccmPropertyReply for GetWmNameReply {
+    fn from_property(con: &Connection, reply: Property) -> Result<Self> {
+        Ok(GetWmNameReply {
+            name: decode_text_reply(con, &reply)?,
+        })
     }
 }
 
@@ -57,6 +60,18 @@ impl<T: xcb::x::PropEl> SetWmName<T> {
     }
 }
 
+impl SetWmName<u8> {
+    /// Encode `name` as `STRING` or, if that is not possible, as `COMPOUND_TEXT`
+    pub fn with_text(connection: &Connection, window: xcb::x::Window, name: &str) -> SetWmName<u8> {
+        let encoding = TextEncoding::for_text(name);
+        SetWmName::new(
+            window,
+            encoding.atom(connection),
+            encode_text(encoding, name),
+        )
+    }
+}
+
 icccm_set_text_property! {
     request=SetWmName{
         property: ATOM_WM_NAME
@@ -67,17 +82,16 @@ icccm_set_text_property! {
 // WM_ICON_NAME, TEXT
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetWmIconNameReply {
     pub name: String,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetWmIconNameReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        let x = reply.value::<u8>().into();
-        GetWmIconNameReply {
-            // TODO Compound string
-            name: String::from_utf8(x).unwrap(),
-        }
+impl IcccmPropertyReply for GetWmIconNameReply {
+    fn from_property(con: &Connection, reply: Property) -> Result<Self> {
+        Ok(GetWmIconNameReply {
+            name: decode_text_reply(con, &reply)?,
+        })
     }
 }
 
@@ -106,6 +120,22 @@ impl<T: xcb::x::PropEl> SetWmIconName<T> {
     }
 }
 
+impl SetWmIconName<u8> {
+    /// Encode `name` as `STRING` or, if that is not possible, as `COMPOUND_TEXT`
+    pub fn with_text(
+        connection: &Connection,
+        window: xcb::x::Window,
+        name: &str,
+    ) -> SetWmIconName<u8> {
+        let encoding = TextEncoding::for_text(name);
+        SetWmIconName::new(
+            window,
+            encoding.atom(connection),
+            encode_text(encoding, name),
+        )
+    }
+}
+
 icccm_set_text_property! {
     request=SetWmIconName{
         property: ATOM_WM_ICON_NAME
@@ -116,25 +146,29 @@ icccm_set_text_property! {
 // WM_COLORMAP_WINDOWS, WINDOW[]/32
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetWmColorMapWindowsReply {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::xids"))]
     pub windows: Vec<xcb::x::Window>,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetWmColorMapWindowsReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetWmColorMapWindowsReply {
-            windows: reply.value().into(),
-        }
+impl TryFrom<Property> for GetWmColorMapWindowsReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        Ok(GetWmColorMapWindowsReply {
+            windows: property_value(&reply, 0)?.into(),
+        })
     }
 }
 
 icccm_get_property! {
     request=GetWmColorMapWindows {
         window: client,
-        property: WM_COLORMAP_WINDOWS,
+        property: con.WM_COLORMAP_WINDOWS,
         xtype: ATOM_WINDOW
     },
-    reply=GetWmIconNameReply
+    reply=GetWmColorMapWindowsReply
 }
 
 pub struct SetWmColorMapWindows {
@@ -165,17 +199,16 @@ icccm_set_property! {
 // WM_CLIENT_MACHINE, TEXT
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetWmClientMachineReply {
     pub name: String,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetWmClientMachineReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        let x = reply.value::<u8>().into();
-        GetWmClientMachineReply {
-            // TODO Compound string
-            name: String::from_utf8(x).unwrap(),
-        }
+impl IcccmPropertyReply for GetWmClientMachineReply {
+    fn from_property(con: &Connection, reply: Property) -> Result<Self> {
+        Ok(GetWmClientMachineReply {
+            name: decode_text_reply(con, &reply)?,
+        })
     }
 }
 
@@ -208,6 +241,22 @@ impl<T: xcb::x::PropEl> SetWmClientMachine<T> {
     }
 }
 
+impl SetWmClientMachine<u8> {
+    /// Encode `name` as `STRING` or, if that is not possible, as `COMPOUND_TEXT`
+    pub fn with_text(
+        connection: &Connection,
+        window: xcb::x::Window,
+        name: &str,
+    ) -> SetWmClientMachine<u8> {
+        let encoding = TextEncoding::for_text(name);
+        SetWmClientMachine::new(
+            window,
+            encoding.atom(connection),
+            encode_text(encoding, name),
+        )
+    }
+}
+
 icccm_set_text_property! {
     request=SetWmClientMachine{
         property: ATOM_WM_CLIENT_MACHINE
@@ -218,19 +267,29 @@ icccm_set_text_property! {
 // WM_TRANSIENT_FOR, WINDOW/32
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetWmClassReply {
     pub instance: String,
     pub class: String,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetWmClassReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        let values: Vec<&[u8]> = reply.value::<u8>().split(|v| *v == 0x00 as u8).collect();
-
-        GetWmClassReply {
-            instance: String::from_utf8_lossy(values[0]).to_string(),
-            class: String::from_utf8_lossy(values[1]).to_string(),
+impl IcccmPropertyReply for GetWmClassReply {
+    fn from_property(con: &Connection, reply: Property) -> Result<Self> {
+        let data = property_value::<u8>(&reply, 0)?;
+        let encoding = reply_encoding(con, &reply, data);
+        let values: Vec<&[u8]> = data.split(|v| *v == 0x00 as u8).collect();
+
+        if values.len() < 2 {
+            return Err(Error::Truncated {
+                expected: 2,
+                actual: values.len(),
+            });
         }
+
+        Ok(GetWmClassReply {
+            instance: decode_text(encoding, values[0]),
+            class: decode_text(encoding, values[1]),
+        })
     }
 }
 
@@ -238,7 +297,7 @@ icccm_get_property! {
     request=GetWmClass{
         window: client,
         property: ATOM_WM_CLASS,
-        xtype: ATOM_STRING
+        xtype: ATOM_ANY
     },
     reply=GetWmClassReply
 }
@@ -251,15 +310,9 @@ pub struct SetWmClass {
 impl SetWmClass {
     // TODO better name for second window
     pub fn new(window: xcb::x::Window, instance: &str, class: &str) -> SetWmClass {
-        let mut data = vec![];
-        data.append(&mut instance.as_bytes().to_vec());
-        data.push(0x00 as u8);
-        data.append(&mut class.as_bytes().to_vec());
-        data.push(0x00 as u8);
-
         SetWmClass {
             window: window,
-            data: data,
+            data: encode_string_list(&[instance, class]),
         }
     }
 }
@@ -275,15 +328,21 @@ icccm_set_string_property! {
 // WM_TRANSIENT_FOR, WINDOW/32
 // {{{
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetWmTransientForReply {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::xid"))]
     pub window: xcb::x::Window,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetWmTransientForReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetWmTransientForReply {
-            window: unsafe { xcb::x::Window::new(reply.value::<u32>()[0]) },
-        }
+impl TryFrom<Property> for GetWmTransientForReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        let value = property_value::<u32>(&reply, 1)?;
+
+        Ok(GetWmTransientForReply {
+            window: unsafe { xcb::x::Window::new(value[0]) },
+        })
     }
 }
 
@@ -338,7 +397,8 @@ bitflags! {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WmSizeHints {
     flags: WmSizeHintsFlags,
     x: u32,
@@ -355,6 +415,7 @@ pub struct WmSizeHints {
     max_aspect: (u32, u32),
     base_width: u32,
     base_height: u32,
+    #[cfg_attr(feature = "serde", serde(with = "gravity"))]
     win_gravity: xcb::x::Gravity,
 }
 
@@ -382,7 +443,7 @@ impl Default for WmSizeHints {
 }
 
 impl WmSizeHints {
-    pub fn as_data(&mut self) -> Vec<u32> {
+    pub fn as_data(&self) -> Vec<u32> {
         vec![
             self.flags.bits,
             self.x,
@@ -405,9 +466,11 @@ impl WmSizeHints {
         ]
     }
 
-    pub fn from_reply(reply: xcb::x::GetPropertyReply) -> WmSizeHints {
-        let packed_vals = reply.value::<u32>();
-        WmSizeHints {
+    pub fn from_reply(reply: Property) -> Result<WmSizeHints> {
+        // Pre-ICCCM clients do not set base size and window gravity
+        let packed_vals = property_value::<u32>(&reply, 15)?;
+
+        Ok(WmSizeHints {
             flags: WmSizeHintsFlags::from_bits_truncate(packed_vals[0]),
             x: packed_vals[1],
             y: packed_vals[2],
@@ -421,10 +484,13 @@ impl WmSizeHints {
             height_inc: packed_vals[10],
             min_aspect: (packed_vals[11], packed_vals[12]),
             max_aspect: (packed_vals[13], packed_vals[14]),
-            base_width: packed_vals[15],
-            base_height: packed_vals[16],
-            win_gravity: unsafe { std::mem::transmute(packed_vals[17]) },
-        }
+            base_width: packed_vals.get(15).copied().unwrap_or(0),
+            base_height: packed_vals.get(16).copied().unwrap_or(0),
+            win_gravity: packed_vals
+                .get(17)
+                .and_then(|g| gravity_from_u32(*g))
+                .unwrap_or(xcb::x::Gravity::NorthWest),
+        })
     }
 
     pub fn position(&mut self, user_specified: bool, x: u32, y: u32) {
@@ -497,16 +563,83 @@ impl WmSizeHints {
     }
 }
 
+/// Window gravity from its wire value, `None` if out of range
+fn gravity_from_u32(gravity: u32) -> Option<xcb::x::Gravity> {
+    use xcb::x::Gravity::*;
+
+    [
+        BitForget, NorthWest, North, NorthEast, West, Center, East, SouthWest, South, SouthEast,
+        Static,
+    ]
+    .iter()
+    .copied()
+    .find(|g| *g as u32 == gravity)
+}
+
+/// `#[serde(with)]` for window gravity, as its wire value
+#[cfg(feature = "serde")]
+mod gravity {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        gravity: &xcb::x::Gravity,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_u32(*gravity as u32)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<xcb::x::Gravity, D::Error> {
+        let gravity = u32::deserialize(deserializer)?;
+        super::gravity_from_u32(gravity).ok_or_else(|| {
+            de::Error::invalid_value(
+                de::Unexpected::Unsigned(gravity.into()),
+                &"a window gravity",
+            )
+        })
+    }
+}
+
+/// Flags (de)serialize as their bits, unknown bits are dropped
+#[cfg(feature = "serde")]
+macro_rules! serde_flags {
+    ($flags:ident) => {
+        impl serde::Serialize for $flags {
+            fn serialize<S: serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                serializer.serialize_u32(self.bits())
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $flags {
+            fn deserialize<D: serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> std::result::Result<Self, D::Error> {
+                Ok($flags::from_bits_truncate(u32::deserialize(deserializer)?))
+            }
+        }
+    };
+}
+
+#[cfg(feature = "serde")]
+serde_flags!(WmSizeHintsFlags);
+
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetWmNormalHintsReply {
     pub size_hints: WmSizeHints,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetWmNormalHintsReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetWmNormalHintsReply {
-            size_hints: WmSizeHints::from_reply(reply),
-        }
+impl TryFrom<Property> for GetWmNormalHintsReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        Ok(GetWmNormalHintsReply {
+            size_hints: WmSizeHints::from_reply(reply)?,
+        })
     }
 }
 
@@ -559,7 +692,11 @@ bitflags! {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[cfg(feature = "serde")]
+serde_flags!(WmHintsFlags);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum WmInitialState {
     None = 4,
@@ -568,16 +705,21 @@ pub enum WmInitialState {
     Iconic = 3,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WmHints {
     flags: WmHintsFlags,
     input: bool,
     initial_state: WmInitialState,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::xid"))]
     icon_pixmap: xcb::x::Pixmap,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::xid"))]
     icon_window: xcb::x::Window,
     icon_x: u32,
     icon_y: u32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::xid"))]
     icon_mask: xcb::x::Pixmap,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::xid"))]
     window_group: xcb::x::Window,
 }
 
@@ -598,7 +740,7 @@ impl Default for WmHints {
 }
 
 impl WmHints {
-    pub fn as_data(&mut self) -> Vec<u32> {
+    pub fn as_data(&self) -> Vec<u32> {
         let initial_state = if self.flags.contains(WmHintsFlags::StateHint) {
             self.initial_state as u32
         } else {
@@ -618,17 +760,24 @@ impl WmHints {
         ]
     }
 
-    pub fn from_reply(reply: xcb::x::GetPropertyReply) -> WmHints {
-        let packed_vals = reply.value::<u32>();
-        let flags: WmHintsFlags = WmHintsFlags::from_bits_truncate(packed_vals[0]);
+    pub fn from_reply(reply: Property) -> Result<WmHints> {
+        // Pre-ICCCM clients do not set the window group
+        let packed_vals = property_value::<u32>(&reply, 8)?;
+        let mut flags: WmHintsFlags = WmHintsFlags::from_bits_truncate(packed_vals[0]);
 
-        let initial_state = if flags.contains(WmHintsFlags::StateHint) {
-            unsafe { mem::transmute(packed_vals[2]) }
-        } else {
-            WmInitialState::None
+        let initial_state = match packed_vals[2] {
+            _ if !flags.contains(WmHintsFlags::StateHint) => WmInitialState::None,
+            0 => WmInitialState::Withdrawn,
+            1 => WmInitialState::Normal,
+            3 => WmInitialState::Iconic,
+            _ => {
+                // Obsolete states (e.g. ZoomState) are treated as unset
+                flags &= !WmHintsFlags::StateHint;
+                WmInitialState::None
+            }
         };
 
-        WmHints {
+        Ok(WmHints {
             flags: flags,
             input: packed_vals[1] != 0,
             initial_state: initial_state,
@@ -637,8 +786,12 @@ impl WmHints {
             icon_x: packed_vals[5],
             icon_y: packed_vals[6],
             icon_mask: unsafe { xcb::x::Pixmap::new(packed_vals[7]) },
-            window_group: unsafe { xcb::x::Window::new(packed_vals[8]) },
-        }
+            window_group: packed_vals
+                .get(8)
+                .map_or(xcb::x::Window::none(), |w| unsafe {
+                    xcb::x::Window::new(*w)
+                }),
+        })
     }
 
     pub fn input(&mut self, input: bool) {
@@ -688,15 +841,18 @@ impl WmHints {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetWmHintsReply {
     pub size_hints: WmHints,
 }
 
-impl From<xcb::x::GetPropertyReply> for GetWmHintsReply {
-    fn from(reply: xcb::x::GetPropertyReply) -> Self {
-        GetWmHintsReply {
-            size_hints: WmHints::from_reply(reply),
-        }
+impl TryFrom<Property> for GetWmHintsReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        Ok(GetWmHintsReply {
+            size_hints: WmHints::from_reply(reply)?,
+        })
     }
 }
 
@@ -730,3 +886,525 @@ icccm_set_hint_property! {
     }
 }
 // }}}
+
+// WM_CLIENT_LEADER, WINDOW/32
+// {{{
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GetWmClientLeaderReply {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::xid"))]
+    pub window: xcb::x::Window,
+}
+
+impl TryFrom<Property> for GetWmClientLeaderReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        let value = property_value::<u32>(&reply, 1)?;
+
+        Ok(GetWmClientLeaderReply {
+            window: unsafe { xcb::x::Window::new(value[0]) },
+        })
+    }
+}
+
+icccm_get_property! {
+    request=GetWmClientLeader{
+        window: client,
+        property: con.WM_CLIENT_LEADER,
+        xtype: ATOM_WINDOW
+    },
+    reply=GetWmClientLeaderReply
+}
+
+pub struct SetWmClientLeader {
+    window: xcb::x::Window,
+    data: Vec<xcb::x::Window>,
+}
+
+impl SetWmClientLeader {
+    pub fn new(window: xcb::x::Window, leader: xcb::x::Window) -> SetWmClientLeader {
+        SetWmClientLeader {
+            window,
+            data: vec![leader],
+        }
+    }
+}
+
+icccm_set_property! {
+    request=SetWmClientLeader{
+        property: con.WM_CLIENT_LEADER,
+        xtype: ATOM_WINDOW
+    }
+}
+// }}}
+
+// WM_WINDOW_ROLE, STRING
+// {{{
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GetWmWindowRoleReply {
+    pub role: String,
+}
+
+impl IcccmPropertyReply for GetWmWindowRoleReply {
+    fn from_property(con: &Connection, reply: Property) -> Result<Self> {
+        Ok(GetWmWindowRoleReply {
+            role: decode_text_reply(con, &reply)?,
+        })
+    }
+}
+
+icccm_get_property! {
+    request=GetWmWindowRole{
+        window: client,
+        property: con.WM_WINDOW_ROLE,
+        xtype: ATOM_ANY
+    },
+    reply=GetWmWindowRoleReply
+}
+
+pub struct SetWmWindowRole {
+    window: xcb::x::Window,
+    data: Vec<u8>,
+}
+
+impl SetWmWindowRole {
+    pub fn new(window: xcb::x::Window, role: &str) -> SetWmWindowRole {
+        SetWmWindowRole {
+            window,
+            data: encode_text(TextEncoding::String, role),
+        }
+    }
+}
+
+icccm_set_string_property! {
+    request=SetWmWindowRole{
+        property: con.WM_WINDOW_ROLE,
+        xtype: ATOM_STRING
+    }
+}
+// }}}
+
+// SM_CLIENT_ID, STRING
+// {{{
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GetSmClientIdReply {
+    pub client_id: String,
+}
+
+impl IcccmPropertyReply for GetSmClientIdReply {
+    fn from_property(con: &Connection, reply: Property) -> Result<Self> {
+        Ok(GetSmClientIdReply {
+            client_id: decode_text_reply(con, &reply)?,
+        })
+    }
+}
+
+icccm_get_property! {
+    request=GetSmClientId{
+        window: client,
+        property: con.SM_CLIENT_ID,
+        xtype: ATOM_ANY
+    },
+    reply=GetSmClientIdReply
+}
+
+pub struct SetSmClientId {
+    window: xcb::x::Window,
+    data: Vec<u8>,
+}
+
+impl SetSmClientId {
+    pub fn new(leader: xcb::x::Window, client_id: &str) -> SetSmClientId {
+        SetSmClientId {
+            window: leader,
+            data: encode_text(TextEncoding::String, client_id),
+        }
+    }
+}
+
+icccm_set_string_property! {
+    request=SetSmClientId{
+        property: con.SM_CLIENT_ID,
+        xtype: ATOM_STRING
+    }
+}
+// }}}
+
+// WM_COMMAND, STRING[]
+// {{{
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GetWmCommandReply {
+    pub argv: Vec<String>,
+}
+
+impl IcccmPropertyReply for GetWmCommandReply {
+    fn from_property(con: &Connection, reply: Property) -> Result<Self> {
+        Ok(GetWmCommandReply {
+            argv: decode_text_list_reply(con, &reply)?,
+        })
+    }
+}
+
+icccm_get_property! {
+    request=GetWmCommand{
+        window: client,
+        property: ATOM_WM_COMMAND,
+        xtype: ATOM_ANY
+    },
+    reply=GetWmCommandReply
+}
+
+pub struct SetWmCommand {
+    window: xcb::x::Window,
+    data: Vec<u8>,
+}
+
+impl SetWmCommand {
+    pub fn new(window: xcb::x::Window, argv: Vec<&str>) -> SetWmCommand {
+        SetWmCommand {
+            window,
+            data: encode_string_list(&argv),
+        }
+    }
+}
+
+icccm_set_string_property! {
+    request=SetWmCommand{
+        property: ATOM_WM_COMMAND,
+        xtype: ATOM_STRING
+    }
+}
+// }}}
+
+// WM_LOCALE_NAME, STRING
+// {{{
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GetWmLocaleNameReply {
+    pub locale: String,
+}
+
+impl IcccmPropertyReply for GetWmLocaleNameReply {
+    fn from_property(con: &Connection, reply: Property) -> Result<Self> {
+        Ok(GetWmLocaleNameReply {
+            locale: decode_text_reply(con, &reply)?,
+        })
+    }
+}
+
+icccm_get_property! {
+    request=GetWmLocaleName{
+        window: client,
+        property: con.WM_LOCALE_NAME,
+        xtype: ATOM_ANY
+    },
+    reply=GetWmLocaleNameReply
+}
+
+pub struct SetWmLocaleName {
+    window: xcb::x::Window,
+    data: Vec<u8>,
+}
+
+impl SetWmLocaleName {
+    pub fn new(window: xcb::x::Window, locale: &str) -> SetWmLocaleName {
+        SetWmLocaleName {
+            window,
+            data: encode_text(TextEncoding::String, locale),
+        }
+    }
+}
+
+icccm_set_string_property! {
+    request=SetWmLocaleName{
+        property: con.WM_LOCALE_NAME,
+        xtype: ATOM_STRING
+    }
+}
+// }}}
+
+/// Encode `strings` as a NULL terminated list of `STRING`s
+fn encode_string_list(strings: &[&str]) -> Vec<u8> {
+    let mut data = vec![];
+    for s in strings {
+        data.extend(encode_text(TextEncoding::String, s));
+        data.push(0x00);
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(id: u32) -> xcb::x::Window {
+        xcb::x::Window::new(id)
+    }
+
+    fn size_hints(data: Vec<u32>) -> Result<WmSizeHints> {
+        WmSizeHints::from_reply(Property::format32(xcb::x::ATOM_WM_SIZE_HINTS, data))
+    }
+
+    fn hints(data: Vec<u32>) -> Result<WmHints> {
+        WmHints::from_reply(Property::format32(xcb::x::ATOM_WM_HINTS, data))
+    }
+
+    /// Decode a `TEXT` property of the type named `r#type` like a connection to a server does
+    #[cfg(feature = "mock")]
+    fn decode<R: IcccmPropertyReply>(r#type: &str, data: &[u8]) -> Result<R> {
+        let store = crate::mock::PropertyStore::new();
+        let con = Connection::connect(&store);
+        R::from_property(&con, Property::format8(store.atom(r#type), data.to_vec()))
+    }
+
+    #[test]
+    fn size_hints_round_trip() {
+        let mut all = WmSizeHints::default();
+        all.position(true, 10, 20);
+        all.size(false, 640, 480);
+        all.min_size(100, 50);
+        all.max_size(1920, 1080);
+        all.resize_inc(8, 16);
+        all.aspect((4, 3), (16, 9));
+        all.base_size(2, 4);
+        all.win_gravity(xcb::x::Gravity::Static);
+
+        for hints in [WmSizeHints::default(), all] {
+            assert_eq!(hints.as_data().len(), 18);
+            assert_eq!(size_hints(hints.as_data()).unwrap(), hints);
+        }
+    }
+
+    #[test]
+    fn size_hints_pre_icccm() {
+        let mut hints = WmSizeHints::default();
+        hints.min_size(100, 50);
+
+        let decoded = size_hints(hints.as_data()[..15].to_vec()).unwrap();
+        assert_eq!(decoded, hints);
+    }
+
+    #[test]
+    fn size_hints_malformed() {
+        assert!(matches!(
+            size_hints(vec![0; 14]),
+            Err(Error::Truncated {
+                expected: 15,
+                actual: 14
+            })
+        ));
+        assert!(matches!(
+            WmSizeHints::from_reply(Property::format8(xcb::x::ATOM_WM_SIZE_HINTS, vec![0; 72])),
+            Err(Error::WrongFormat { .. })
+        ));
+
+        // Unknown flag bits and an out of range gravity are dropped
+        let mut data = WmSizeHints::default().as_data();
+        data[0] = 0xffff_f000;
+        data[17] = 42;
+        assert_eq!(size_hints(data).unwrap(), WmSizeHints::default());
+    }
+
+    #[test]
+    fn hints_round_trip() {
+        let mut all = WmHints::default();
+        all.input(false);
+        all.initial_state(WmInitialState::Iconic);
+        all.icon_pixmap(xcb::x::Pixmap::new(1));
+        all.icon_mask(xcb::x::Pixmap::new(2));
+        all.icon_window(window(3));
+        all.window_group(window(4));
+        all.toggle_urgent();
+
+        for hints in [WmHints::default(), all] {
+            assert_eq!(hints.as_data().len(), 9);
+            assert_eq!(self::hints(hints.as_data()).unwrap(), hints);
+        }
+    }
+
+    #[test]
+    fn hints_pre_icccm() {
+        let mut hints = WmHints::default();
+        hints.initial_state(WmInitialState::Normal);
+
+        assert_eq!(self::hints(hints.as_data()[..8].to_vec()).unwrap(), hints);
+    }
+
+    #[test]
+    fn hints_malformed() {
+        assert!(matches!(
+            hints(vec![0; 7]),
+            Err(Error::Truncated {
+                expected: 8,
+                actual: 7
+            })
+        ));
+        assert!(matches!(
+            WmHints::from_reply(Property::missing()),
+            Err(Error::PropertyMissing)
+        ));
+
+        // The obsolete ZoomState counts as no initial state
+        let mut data = WmHints::default().as_data();
+        data[0] = WmHintsFlags::StateHint.bits;
+        data[2] = 2;
+        assert_eq!(hints(data).unwrap(), WmHints::default());
+    }
+
+    #[test]
+    #[cfg(feature = "mock")]
+    fn class_round_trip() {
+        let request = SetWmClass::new(window(1), "xterm", "XTerm");
+        let reply: GetWmClassReply = decode("STRING", &request.data).unwrap();
+
+        assert_eq!(
+            (reply.instance.as_str(), reply.class.as_str()),
+            ("xterm", "XTerm")
+        );
+
+        let request = SetWmClass::new(window(1), "café", "Café");
+        let reply: GetWmClassReply = decode("STRING", &request.data).unwrap();
+        assert_eq!(
+            (reply.instance.as_str(), reply.class.as_str()),
+            ("café", "Café")
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "mock")]
+    fn class_malformed() {
+        let reply = decode::<GetWmClassReply>("STRING", b"xterm");
+        assert!(matches!(
+            reply,
+            Err(Error::Truncated {
+                expected: 2,
+                actual: 1
+            })
+        ));
+
+        // Latin-1 is decoded as such
+        let reply: GetWmClassReply = decode("STRING", b"caf\xe9\0Caf\xe9\0").unwrap();
+        assert_eq!(reply.class, "Café");
+    }
+
+    #[test]
+    #[cfg(feature = "mock")]
+    fn name_encodings() {
+        for (name, encoding) in [
+            ("plain", TextEncoding::String),
+            ("café", TextEncoding::String),
+            ("→ ü", TextEncoding::CompoundText),
+        ] {
+            assert_eq!(TextEncoding::for_text(name), encoding);
+
+            let r#type = match encoding {
+                TextEncoding::String => "STRING",
+                _ => "COMPOUND_TEXT",
+            };
+            let reply: GetWmNameReply = decode(r#type, &encode_text(encoding, name)).unwrap();
+
+            assert_eq!(reply.name, name);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "mock")]
+    fn text_by_type() {
+        // ASCII only, so it looks like UTF-8, but the type says `COMPOUND_TEXT`
+        let store = crate::mock::PropertyStore::new();
+        let con = Connection::connect(&store);
+        let ascii = Property::format8(store.atom("COMPOUND_TEXT"), b"xterm".to_vec());
+        assert_eq!(
+            reply_encoding(&con, &ascii, b"xterm"),
+            TextEncoding::CompoundText
+        );
+        let reply = GetWmNameReply::from_property(&con, ascii).unwrap();
+        assert_eq!(reply.name, "xterm");
+
+        // Latin-1 and UTF-8 of the same bytes
+        let reply: GetWmNameReply = decode("COMPOUND_TEXT", b"caf\xc3\xa9").unwrap();
+        assert_eq!(reply.name, "caf\u{c3}\u{a9}");
+        let reply: GetWmNameReply = decode("UTF8_STRING", b"caf\xc3\xa9").unwrap();
+        assert_eq!(reply.name, "café");
+
+        // Unknown types are guessed from the data
+        let reply: GetWmIconNameReply = decode("TEXT", b"caf\xe9").unwrap();
+        assert_eq!(reply.name, "café");
+        let reply: GetWmClassReply = decode("COMPOUND_TEXT", b"xterm\0XTerm\0").unwrap();
+        assert_eq!(reply.class, "XTerm");
+    }
+
+    #[test]
+    #[cfg(feature = "mock")]
+    fn command_round_trip() {
+        let request = SetWmCommand::new(window(1), vec!["xterm", "-e", "top"]);
+        let reply: GetWmCommandReply = decode("STRING", &request.data).unwrap();
+
+        assert_eq!(reply.argv, vec!["xterm", "-e", "top"]);
+
+        // An empty argument keeps its position
+        let request = SetWmCommand::new(window(1), vec!["sh", "-c", "", "x"]);
+        let reply: GetWmCommandReply = decode("STRING", &request.data).unwrap();
+        assert_eq!(reply.argv, vec!["sh", "-c", "", "x"]);
+
+        let request = SetWmCommand::new(window(1), vec!["vim", "résumé.txt"]);
+        let reply: GetWmCommandReply = decode("STRING", &request.data).unwrap();
+        assert_eq!(reply.argv, vec!["vim", "résumé.txt"]);
+    }
+
+    #[test]
+    #[cfg(feature = "mock")]
+    fn text_of_any_type() {
+        let store = crate::mock::PropertyStore::new();
+        let client = store.create_window();
+        let utf8 = |text: &[u8]| Property::format8(store.atom("UTF8_STRING"), text.to_vec());
+        store.set_property(client, "WM_COMMAND", utf8(b"vim\0\xc3\xa9t\xc3\xa9.txt\0"));
+        store.set_property(client, "WM_WINDOW_ROLE", utf8(b"\xc3\xa9diteur"));
+        store.set_property(client, "SM_CLIENT_ID", utf8(b"\xe2\x98\x83"));
+        store.set_property(client, "WM_LOCALE_NAME", utf8(b"fr_FR.UTF-8"));
+        let con = Connection::connect(&store);
+
+        let command = con.wait_for_reply(con.send_request(&GetWmCommand::new(client)));
+        assert_eq!(command.unwrap().argv, vec!["vim", "été.txt"]);
+        let role = con.wait_for_reply(con.send_request(&GetWmWindowRole::new(client)));
+        assert_eq!(role.unwrap().role, "éditeur");
+        let client_id = con.wait_for_reply(con.send_request(&GetSmClientId::new(client)));
+        assert_eq!(client_id.unwrap().client_id, "☃");
+        let locale = con.wait_for_reply(con.send_request(&GetWmLocaleName::new(client)));
+        assert_eq!(locale.unwrap().locale, "fr_FR.UTF-8");
+    }
+
+    #[test]
+    #[cfg(feature = "mock")]
+    fn latin1_strings_round_trip() {
+        let request = SetWmWindowRole::new(window(1), "Öffnen-Dialog");
+        let reply: GetWmWindowRoleReply = decode("STRING", &request.data).unwrap();
+        assert_eq!(reply.role, "Öffnen-Dialog");
+
+        let request = SetSmClientId::new(window(1), "sitzung-ü1");
+        let reply: GetSmClientIdReply = decode("STRING", &request.data).unwrap();
+        assert_eq!(reply.client_id, "sitzung-ü1");
+
+        let request = SetWmLocaleName::new(window(1), "français");
+        let reply: GetWmLocaleNameReply = decode("STRING", &request.data).unwrap();
+        assert_eq!(reply.locale, "français");
+    }
+
+    #[test]
+    fn transient_for_round_trip() {
+        let request = SetWmTransientFor::new(window(1), window(2));
+        let data = request.data.iter().map(|w| w.resource_id()).collect();
+        let reply = GetWmTransientForReply::try_from(Property::format32(xcb::x::ATOM_WINDOW, data))
+            .unwrap();
+
+        assert_eq!(reply.window, window(2));
+        assert!(matches!(
+            GetWmTransientForReply::try_from(Property::format32(xcb::x::ATOM_WINDOW, vec![])),
+            Err(Error::Truncated { .. })
+        ));
+    }
+}
@@ -2,13 +2,13 @@ macro_rules! _get_property_cookies {
     (@icccm_priv $request:ident, $reply:ident) => {
         paste! {
             impl IcccmCookie for [<$request Cookie>] {
-                type XcbCookie = xcb::x::GetPropertyCookie;
+                type XcbCookie = crate::backend::PropertyCookie;
             }
 
             impl IcccmPropertyCookieChecked for [<$request Cookie>] {
                 type Reply = $reply;
 
-                fn inner(self) -> xcb::x::GetPropertyCookie {
+                fn inner(self) -> crate::backend::PropertyCookie {
                     self.0
                 }
             }
@@ -16,7 +16,7 @@ macro_rules! _get_property_cookies {
             impl IcccmPropertyCookieUnchecked for [<$request CookieUnchecked>] {
                 type Reply = $reply;
 
-                fn inner(self) -> xcb::x::GetPropertyCookieUnchecked {
+                fn inner(self) -> crate::backend::PropertyCookieUnchecked {
                     self.0
                 }
             }
@@ -25,13 +25,12 @@ macro_rules! _get_property_cookies {
 }
 
 macro_rules! _get_property_request {
-    //TODO do we need the `client` here? Probs just c&p
-    (@icccm_priv client, WM_COLORMAP_WINDOWS, $xtype:ident) => {
+    (@icccm_priv client, con.$property:ident, $xtype:ident) => {
         fn xcb_request(&self, con: &Connection) -> xcb::x::GetProperty {
             xcb::x::GetProperty {
                 delete: false,
                 window: self.0,
-                property: con.atoms.WM_COLORMAP_WINDOWS,
+                property: con.request_atom(stringify!($property)),
                 r#type: xcb::x::$xtype,
                 long_offset: 0,
                 long_length: u32::MAX,
@@ -39,8 +38,21 @@ macro_rules! _get_property_request {
         }
     };
 
-    (@icccm_priv client, $property:ident, $xtype:ident) => {
+    (@icccm_priv root, $property:ident, $xtype:ident) => {
         fn xcb_request(&self, con: &Connection) -> xcb::x::GetProperty {
+            xcb::x::GetProperty {
+                delete: false,
+                window: con.root(),
+                property: xcb::x::$property,
+                r#type: xcb::x::$xtype,
+                long_offset: 0,
+                long_length: u32::MAX,
+            }
+        }
+    };
+
+    (@icccm_priv client, $property:ident, $xtype:ident) => {
+        fn xcb_request(&self, _con: &Connection) -> xcb::x::GetProperty {
             xcb::x::GetProperty {
                 delete: false,
                 window: self.0,
@@ -53,31 +65,81 @@ macro_rules! _get_property_request {
     };
 }
 
+macro_rules! _get_property_structs {
+    (@icccm_priv $request:ident, root) => {
+        paste! {
+            pub struct $request;
+            pub struct [<$request Cookie>](crate::backend::PropertyCookie);
+            pub struct [<$request CookieUnchecked>](crate::backend::PropertyCookieUnchecked);
+        }
+    };
+    (@icccm_priv $request:ident, client) => {
+        paste! {
+            pub struct $request(xcb::x::Window);
+            pub struct [<$request Cookie>](crate::backend::PropertyCookie);
+            pub struct [<$request CookieUnchecked>](crate::backend::PropertyCookieUnchecked);
+
+            impl $request {
+                pub fn new(window: xcb::x::Window) -> $request {
+                    $request(window)
+                }
+            }
+        }
+    };
+}
+
 macro_rules! icccm_get_property {
     (request=$request:ident{
         window: $window:ident,
-        property: $property:ident,
+        property: con.$property:ident,
         xtype: $xtype: ident
      },
      reply=$reply:ident) => {
        paste!{
-            pub struct $request(xcb::x::Window);
-            pub struct [<$request Cookie>](xcb::x::GetPropertyCookie);
-            pub struct [<$request CookieUnchecked>](xcb::x::GetPropertyCookieUnchecked);
+           _get_property_structs! {@icccm_priv $request, $window}
 
-            impl $request {
-                pub fn new(window: xcb::x::Window) -> $request {
-                    $request(window)
+           impl<'a> IcccmRequest<'a> for $request {
+                type XcbRequest = xcb::x::GetProperty;
+                type IcccmCookie = [<$request Cookie>];
+
+                _get_property_request! {@icccm_priv $window, con.$property, $xtype}
+
+                fn convert_cookie(&'a self, xcb_cookie: crate::backend::PropertyCookie) -> Self::IcccmCookie {
+                    [<$request Cookie>](xcb_cookie)
                 }
             }
 
+            impl IcccmPropertyRequestUnchecked for $request {
+                paste!{type IcccmCookie = [<$request CookieUnchecked>];}
+
+                #[rustfmt::skip]
+                fn convert_cookie(&self, xcb_cookie: crate::backend::PropertyCookieUnchecked) -> Self::IcccmCookie {
+                    [<$request CookieUnchecked>](xcb_cookie)
+                }
+
+                _get_property_request! {@icccm_priv $window, con.$property, $xtype}
+            }
+
+            _get_property_cookies! {@icccm_priv $request, $reply}
+        }
+    };
+
+    (request=$request:ident{
+        window: $window:ident,
+        property: $property:ident,
+        xtype: $xtype: ident
+     },
+     reply=$reply:ident) => {
+       paste!{
+           _get_property_structs! {@icccm_priv $request, $window}
+
            impl<'a> IcccmRequest<'a> for $request {
                 type XcbRequest = xcb::x::GetProperty;
                 type IcccmCookie = [<$request Cookie>];
 
                 _get_property_request! {@icccm_priv $window, $property, $xtype}
 
-                fn convert_cookie(&'a self, xcb_cookie: xcb::x::GetPropertyCookie) -> Self::IcccmCookie {
+                fn convert_cookie(&'a self, xcb_cookie: crate::backend::PropertyCookie) -> Self::IcccmCookie {
                     [<$request Cookie>](xcb_cookie)
                 }
             }
@@ -86,7 +148,7 @@ macro_rules! icccm_get_property {
                 paste!{type IcccmCookie = [<$request CookieUnchecked>];}
 
                 #[rustfmt::skip]
-                fn convert_cookie(&self, xcb_cookie: xcb::x::GetPropertyCookieUnchecked) -> Self::IcccmCookie {
+                fn convert_cookie(&self, xcb_cookie: crate::backend::PropertyCookieUnchecked) -> Self::IcccmCookie {
                     [<$request CookieUnchecked>](xcb_cookie)
                 }
 
@@ -4,9 +4,9 @@ macro_rules! icccm_set_text_property {
     }) => {
         impl<'a, T: 'a + xcb::x::PropEl> IcccmRequest<'a> for $request<T> {
             type XcbRequest = xcb::x::ChangeProperty<'a, T>;
-            type IcccmCookie = xcb::VoidCookie;
+            type IcccmCookie = crate::backend::VoidCookie;
 
-            fn xcb_request(&'a self, con: &Connection) -> xcb::x::ChangeProperty<'a, T> {
+            fn xcb_request(&'a self, _con: &Connection) -> xcb::x::ChangeProperty<'a, T> {
                 xcb::x::ChangeProperty {
                     mode: xcb::x::PropMode::Replace,
                     window: self.window,
@@ -16,7 +16,10 @@ macro_rules! icccm_set_text_property {
                 }
             }
 
-            fn convert_cookie(&'a self, xcb_cookie: xcb::VoidCookie) -> Self::IcccmCookie {
+            fn convert_cookie(
+                &'a self,
+                xcb_cookie: crate::backend::VoidCookie,
+            ) -> Self::IcccmCookie {
                 xcb_cookie
             }
         }
@@ -24,7 +27,7 @@ macro_rules! icccm_set_text_property {
         impl<'a, T: 'a + xcb::x::PropEl> IcccmVoidRequestChecked<'a> for $request<T> {
             type XcbRequest = xcb::x::ChangeProperty<'a, T>;
 
-            fn xcb_request(&'a self, con: &Connection) -> xcb::x::ChangeProperty<'a, T> {
+            fn xcb_request(&'a self, _con: &Connection) -> xcb::x::ChangeProperty<'a, T> {
                 xcb::x::ChangeProperty {
                     mode: xcb::x::PropMode::Replace,
                     window: self.window,
@@ -44,7 +47,7 @@ macro_rules! icccm_set_property {
      }) => {
         impl<'a> IcccmRequest<'a> for $request {
             type XcbRequest = xcb::x::ChangeProperty<'a, xcb::x::Window>;
-            type IcccmCookie = xcb::VoidCookie;
+            type IcccmCookie = crate::backend::VoidCookie;
 
             fn xcb_request(
                 &'a self,
@@ -53,13 +56,16 @@ macro_rules! icccm_set_property {
                 xcb::x::ChangeProperty {
                     mode: xcb::x::PropMode::Replace,
                     window: self.window,
-                    property: con.atoms.$property,
+                    property: con.request_atom(stringify!($property)),
                     r#type: xcb::x::$type,
                     data: &self.data,
                 }
             }
 
-            fn convert_cookie(&'a self, xcb_cookie: xcb::VoidCookie) -> Self::IcccmCookie {
+            fn convert_cookie(
+                &'a self,
+                xcb_cookie: crate::backend::VoidCookie,
+            ) -> Self::IcccmCookie {
                 xcb_cookie
             }
         }
@@ -74,7 +80,7 @@ macro_rules! icccm_set_property {
                 xcb::x::ChangeProperty {
                     mode: xcb::x::PropMode::Replace,
                     window: self.window,
-                    property: con.atoms.$property,
+                    property: con.request_atom(stringify!($property)),
                     r#type: xcb::x::$type,
                     data: &self.data,
                 }
@@ -88,11 +94,11 @@ macro_rules! icccm_set_property {
      }) => {
         impl<'a> IcccmRequest<'a> for $request {
             type XcbRequest = xcb::x::ChangeProperty<'a, xcb::x::Window>;
-            type IcccmCookie = xcb::VoidCookie;
+            type IcccmCookie = crate::backend::VoidCookie;
 
             fn xcb_request(
                 &'a self,
-                con: &Connection,
+                _con: &Connection,
             ) -> xcb::x::ChangeProperty<'a, xcb::x::Window> {
                 xcb::x::ChangeProperty {
                     mode: xcb::x::PropMode::Replace,
@@ -103,7 +109,10 @@ macro_rules! icccm_set_property {
                 }
             }
 
-            fn convert_cookie(&'a self, xcb_cookie: xcb::VoidCookie) -> Self::IcccmCookie {
+            fn convert_cookie(
+                &'a self,
+                xcb_cookie: crate::backend::VoidCookie,
+            ) -> Self::IcccmCookie {
                 xcb_cookie
             }
         }
@@ -113,7 +122,7 @@ macro_rules! icccm_set_property {
 
             fn xcb_request(
                 &'a self,
-                con: &Connection,
+                _con: &Connection,
             ) -> xcb::x::ChangeProperty<'a, xcb::x::Window> {
                 xcb::x::ChangeProperty {
                     mode: xcb::x::PropMode::Replace,
@@ -129,24 +138,27 @@ macro_rules! icccm_set_property {
 
 macro_rules! icccm_set_string_property {
     (request=$request:ident{
-        property: $property:ident,
+        property: con.$property:ident,
         xtype: $type:ident
      }) => {
         impl<'a> IcccmRequest<'a> for $request {
             type XcbRequest = xcb::x::ChangeProperty<'a, u8>;
-            type IcccmCookie = xcb::VoidCookie;
+            type IcccmCookie = crate::backend::VoidCookie;
 
             fn xcb_request(&'a self, con: &Connection) -> xcb::x::ChangeProperty<'a, u8> {
                 xcb::x::ChangeProperty {
                     mode: xcb::x::PropMode::Replace,
                     window: self.window,
-                    property: xcb::x::$property,
+                    property: con.request_atom(stringify!($property)),
                     r#type: xcb::x::$type,
                     data: &self.data,
                 }
             }
 
-            fn convert_cookie(&'a self, xcb_cookie: xcb::VoidCookie) -> Self::IcccmCookie {
+            fn convert_cookie(
+                &'a self,
+                xcb_cookie: crate::backend::VoidCookie,
+            ) -> Self::IcccmCookie {
                 xcb_cookie
             }
         }
@@ -155,6 +167,47 @@ macro_rules! icccm_set_string_property {
             type XcbRequest = xcb::x::ChangeProperty<'a, u8>;
 
             fn xcb_request(&'a self, con: &Connection) -> xcb::x::ChangeProperty<'a, u8> {
+                xcb::x::ChangeProperty {
+                    mode: xcb::x::PropMode::Replace,
+                    window: self.window,
+                    property: con.request_atom(stringify!($property)),
+                    r#type: xcb::x::$type,
+                    data: &self.data,
+                }
+            }
+        }
+    };
+
+    (request=$request:ident{
+        property: $property:ident,
+        xtype: $type:ident
+     }) => {
+        impl<'a> IcccmRequest<'a> for $request {
+            type XcbRequest = xcb::x::ChangeProperty<'a, u8>;
+            type IcccmCookie = crate::backend::VoidCookie;
+
+            fn xcb_request(&'a self, _con: &Connection) -> xcb::x::ChangeProperty<'a, u8> {
+                xcb::x::ChangeProperty {
+                    mode: xcb::x::PropMode::Replace,
+                    window: self.window,
+                    property: xcb::x::$property,
+                    r#type: xcb::x::$type,
+                    data: &self.data,
+                }
+            }
+
+            fn convert_cookie(
+                &'a self,
+                xcb_cookie: crate::backend::VoidCookie,
+            ) -> Self::IcccmCookie {
+                xcb_cookie
+            }
+        }
+
+        impl<'a> IcccmVoidRequestChecked<'a> for $request {
+            type XcbRequest = xcb::x::ChangeProperty<'a, u8>;
+
+            fn xcb_request(&'a self, _con: &Connection) -> xcb::x::ChangeProperty<'a, u8> {
                 xcb::x::ChangeProperty {
                     mode: xcb::x::PropMode::Replace,
                     window: self.window,
@@ -169,24 +222,28 @@ macro_rules! icccm_set_string_property {
 
 macro_rules! icccm_set_hint_property {
     (request=$request:ident{
+        window: root,
         property: $property:ident,
         xtype: $type:ident
      }) => {
         impl<'a> IcccmRequest<'a> for $request {
             type XcbRequest = xcb::x::ChangeProperty<'a, u32>;
-            type IcccmCookie = xcb::VoidCookie;
+            type IcccmCookie = crate::backend::VoidCookie;
 
             fn xcb_request(&'a self, con: &Connection) -> xcb::x::ChangeProperty<'a, u32> {
                 xcb::x::ChangeProperty {
                     mode: xcb::x::PropMode::Replace,
-                    window: self.window,
+                    window: con.root(),
                     property: xcb::x::$property,
                     r#type: xcb::x::$type,
                     data: &self.data,
                 }
             }
 
-            fn convert_cookie(&'a self, xcb_cookie: xcb::VoidCookie) -> Self::IcccmCookie {
+            fn convert_cookie(
+                &'a self,
+                xcb_cookie: crate::backend::VoidCookie,
+            ) -> Self::IcccmCookie {
                 xcb_cookie
             }
         }
@@ -195,6 +252,47 @@ macro_rules! icccm_set_hint_property {
             type XcbRequest = xcb::x::ChangeProperty<'a, u32>;
 
             fn xcb_request(&'a self, con: &Connection) -> xcb::x::ChangeProperty<'a, u32> {
+                xcb::x::ChangeProperty {
+                    mode: xcb::x::PropMode::Replace,
+                    window: con.root(),
+                    property: xcb::x::$property,
+                    r#type: xcb::x::$type,
+                    data: &self.data,
+                }
+            }
+        }
+    };
+
+    (request=$request:ident{
+        property: $property:ident,
+        xtype: $type:ident
+     }) => {
+        impl<'a> IcccmRequest<'a> for $request {
+            type XcbRequest = xcb::x::ChangeProperty<'a, u32>;
+            type IcccmCookie = crate::backend::VoidCookie;
+
+            fn xcb_request(&'a self, _con: &Connection) -> xcb::x::ChangeProperty<'a, u32> {
+                xcb::x::ChangeProperty {
+                    mode: xcb::x::PropMode::Replace,
+                    window: self.window,
+                    property: xcb::x::$property,
+                    r#type: xcb::x::$type,
+                    data: &self.data,
+                }
+            }
+
+            fn convert_cookie(
+                &'a self,
+                xcb_cookie: crate::backend::VoidCookie,
+            ) -> Self::IcccmCookie {
+                xcb_cookie
+            }
+        }
+
+        impl<'a> IcccmVoidRequestChecked<'a> for $request {
+            type XcbRequest = xcb::x::ChangeProperty<'a, u32>;
+
+            fn xcb_request(&'a self, _con: &Connection) -> xcb::x::ChangeProperty<'a, u32> {
                 xcb::x::ChangeProperty {
                     mode: xcb::x::PropMode::Replace,
                     window: self.window,
@@ -17,5 +17,11 @@
 #[macro_use]
 mod macros;
 
+mod text;
+pub use text::*;
+
 mod client_props;
 pub use client_props::*;
+
+mod wm_props;
+pub use wm_props::*;
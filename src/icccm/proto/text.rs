@@ -0,0 +1,380 @@
+//! Decoding and encoding of ICCCM `TEXT` properties
+//!
+//! A `TEXT` property can be of type `STRING` (ISO-8859-1), `COMPOUND_TEXT` (ISO-2022) or the
+//! de-facto standard `UTF8_STRING`.
+//!
+//! see: <https://www.x.org/releases/X11R7.7/doc/xorg-docs/icccm/icccm.html#TEXT_Properties>
+//! see: <https://www.x.org/releases/X11R7.7/doc/xorg-docs/ctext/ctext.html>
+
+use crate::icccm::Connection;
+use crate::util::{property_value, x_buffer_split_list};
+use crate::{Property, Result};
+
+const ESC: u8 = 0x1b;
+const CSI: u8 = 0x9b;
+
+/// The encoding of a `TEXT` property
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TextEncoding {
+    /// `STRING`, ISO-8859-1
+    String,
+    /// `UTF8_STRING`
+    Utf8String,
+    /// `COMPOUND_TEXT`, ISO-2022
+    CompoundText,
+}
+
+impl TextEncoding {
+    /// Map the type atom of a property to its encoding
+    pub fn from_atom(con: &Connection, atom: xcb::x::Atom) -> Option<TextEncoding> {
+        if atom == xcb::x::ATOM_NONE {
+            None
+        } else if atom == xcb::x::ATOM_STRING {
+            Some(TextEncoding::String)
+        } else if atom == con.request_atom("UTF8_STRING") {
+            Some(TextEncoding::Utf8String)
+        } else if atom == con.request_atom("COMPOUND_TEXT") {
+            Some(TextEncoding::CompoundText)
+        } else {
+            None
+        }
+    }
+
+    /// The type atom for this encoding
+    pub fn atom(&self, con: &Connection) -> xcb::x::Atom {
+        match self {
+            TextEncoding::String => xcb::x::ATOM_STRING,
+            TextEncoding::Utf8String => con.request_atom("UTF8_STRING"),
+            TextEncoding::CompoundText => con.request_atom("COMPOUND_TEXT"),
+        }
+    }
+
+    /// Determine the encoding of a property without a [`Connection`]
+    ///
+    /// Only `STRING` is a predefined atom. `UTF8_STRING` and `COMPOUND_TEXT` are interned
+    /// per server, so they cannot be told apart by their type alone. For any other type, data
+    /// that contains escape sequences or is not valid UTF-8 is taken as `COMPOUND_TEXT`.
+    ///
+    /// Prefer [`TextEncoding::from_atom`], the replies only guess for types it does not know.
+    pub fn guess(r#type: xcb::x::Atom, data: &[u8]) -> TextEncoding {
+        if r#type == xcb::x::ATOM_STRING {
+            TextEncoding::String
+        } else if data.contains(&ESC) || std::str::from_utf8(data).is_err() {
+            TextEncoding::CompoundText
+        } else {
+            TextEncoding::Utf8String
+        }
+    }
+
+    /// Pick the encoding ICCCM recommends for `text`
+    ///
+    /// That is `STRING` if `text` is representable in ISO-8859-1, `COMPOUND_TEXT` otherwise.
+    pub fn for_text(text: &str) -> TextEncoding {
+        if text.chars().all(|c| (c as u32) <= 0xff) {
+            TextEncoding::String
+        } else {
+            TextEncoding::CompoundText
+        }
+    }
+}
+
+/// Decode the value of a `TEXT` property
+pub fn decode_text(encoding: TextEncoding, data: &[u8]) -> String {
+    match encoding {
+        TextEncoding::String => data.iter().map(|b| *b as char).collect(),
+        TextEncoding::Utf8String => String::from_utf8_lossy(data).to_string(),
+        TextEncoding::CompoundText => decode_compound_text(data),
+    }
+}
+
+/// Encode `text` for a `TEXT` property
+///
+/// Characters that are not representable in `STRING` are replaced by `?`.
+pub fn encode_text(encoding: TextEncoding, text: &str) -> Vec<u8> {
+    match encoding {
+        TextEncoding::String => text
+            .chars()
+            .map(|c| if (c as u32) <= 0xff { c as u8 } else { b'?' })
+            .collect(),
+        TextEncoding::Utf8String => text.as_bytes().to_vec(),
+        TextEncoding::CompoundText => encode_compound_text(text),
+    }
+}
+
+/// The encoding of a `TEXT` property by its type, guessed from `data` for unknown types
+pub(crate) fn reply_encoding(con: &Connection, reply: &Property, data: &[u8]) -> TextEncoding {
+    TextEncoding::from_atom(con, reply.r#type())
+        .unwrap_or_else(|| TextEncoding::guess(reply.r#type(), data))
+}
+
+/// Decode a [`Property`] of a `TEXT` property
+pub(crate) fn decode_text_reply(con: &Connection, reply: &Property) -> Result<String> {
+    let data = property_value::<u8>(reply, 0)?;
+    Ok(decode_text(reply_encoding(con, reply, data), data))
+}
+
+/// Decode a [`Property`] of a NULL separated list of `TEXT`
+pub(crate) fn decode_text_list_reply(con: &Connection, reply: &Property) -> Result<Vec<String>> {
+    let data = property_value::<u8>(reply, 0)?;
+    let encoding = reply_encoding(con, reply, data);
+
+    Ok(x_buffer_split_list(data)
+        .into_iter()
+        .map(|v| decode_text(encoding, v))
+        .collect())
+}
+
+/// Character set designated to the left (GL) half
+#[derive(Copy, Clone)]
+enum Gl {
+    Ascii,
+    JisRoman,
+    Unsupported(usize),
+}
+
+/// Character set designated to the right (GR) half
+#[derive(Copy, Clone)]
+enum Gr {
+    Latin1,
+    Latin9,
+    Greek,
+    Cyrillic,
+    Unsupported(usize),
+}
+
+fn decode_compound_text(data: &[u8]) -> String {
+    let mut text = String::new();
+    let mut gl = Gl::Ascii;
+    let mut gr = Gr::Latin1;
+    let mut i = 0;
+
+    while i < data.len() {
+        let b = data[i];
+
+        match b {
+            ESC => {
+                i = decode_escape(data, i + 1, &mut gl, &mut gr, &mut text);
+                continue;
+            }
+            CSI => {
+                // direction control, skip up to and including the final byte
+                i += 1;
+                while i < data.len() && !(0x40..=0x7e).contains(&data[i]) {
+                    i += 1;
+                }
+            }
+            b'\t' | b'\n' => text.push(b as char),
+            0x20..=0x7f => match gl {
+                Gl::Ascii => text.push(b as char),
+                Gl::JisRoman => text.push(match b {
+                    0x5c => '\u{a5}',
+                    0x7e => '\u{203e}',
+                    _ => b as char,
+                }),
+                Gl::Unsupported(width) => {
+                    text.push(char::REPLACEMENT_CHARACTER);
+                    i += width - 1;
+                }
+            },
+            0xa0..=0xff => match gr {
+                Gr::Latin1 => text.push(b as char),
+                Gr::Latin9 => text.push(latin9(b)),
+                Gr::Greek => text.push(greek(b)),
+                Gr::Cyrillic => text.push(cyrillic(b)),
+                Gr::Unsupported(width) => {
+                    text.push(char::REPLACEMENT_CHARACTER);
+                    i += width - 1;
+                }
+            },
+            // other C0 and C1 controls are not allowed in compound text
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    text
+}
+
+/// Handle the escape sequence starting at `data[i]` (after `ESC`). Returns the index after it.
+fn decode_escape(data: &[u8], i: usize, gl: &mut Gl, gr: &mut Gr, text: &mut String) -> usize {
+    match (data.get(i), data.get(i + 1), data.get(i + 2)) {
+        // 94 character sets
+        (Some(b'('), Some(f), _) => {
+            *gl = match f {
+                b'B' => Gl::Ascii,
+                b'J' => Gl::JisRoman,
+                _ => Gl::Unsupported(1),
+            };
+            i + 2
+        }
+        (Some(b')'), Some(_), _) => {
+            *gr = Gr::Unsupported(1);
+            i + 2
+        }
+        // 96 character sets
+        (Some(b'-'), Some(f), _) => {
+            *gr = match f {
+                b'A' => Gr::Latin1,
+                b'b' => Gr::Latin9,
+                b'F' => Gr::Greek,
+                b'L' => Gr::Cyrillic,
+                _ => Gr::Unsupported(1),
+            };
+            i + 2
+        }
+        // 94^2 character sets
+        (Some(b'$'), Some(b'('), Some(_)) => {
+            *gl = Gl::Unsupported(2);
+            i + 3
+        }
+        (Some(b'$'), Some(b')'), Some(_)) => {
+            *gr = Gr::Unsupported(2);
+            i + 3
+        }
+        // UTF-8 segment, terminated by ESC % @
+        (Some(b'%'), Some(b'G'), _) => {
+            let start = i + 2;
+            let end = data[start..]
+                .windows(3)
+                .position(|w| w == [ESC, b'%', b'@'])
+                .map_or(data.len(), |p| start + p);
+
+            text.push_str(&String::from_utf8_lossy(&data[start..end]));
+            (end + 3).min(data.len())
+        }
+        // extended segment with explicit length, skipped
+        (Some(b'%'), Some(b'/'), Some(_)) => {
+            let (m, l) = (data.get(i + 3), data.get(i + 4));
+            let len = match (m, l) {
+                (Some(m), Some(l)) => ((*m as usize & 0x7f) << 7) | (*l as usize & 0x7f),
+                _ => 0,
+            };
+
+            text.push(char::REPLACEMENT_CHARACTER);
+            (i + 5 + len).min(data.len())
+        }
+        // unknown sequence, skip intermediate bytes up to and including the final byte
+        _ => {
+            let mut i = i;
+            while i < data.len() && (0x20..=0x2f).contains(&data[i]) {
+                i += 1;
+            }
+            (i + 1).min(data.len())
+        }
+    }
+}
+
+fn latin9(b: u8) -> char {
+    match b {
+        0xa4 => '\u{20ac}',
+        0xa6 => '\u{160}',
+        0xa8 => '\u{161}',
+        0xb4 => '\u{17d}',
+        0xb8 => '\u{17e}',
+        0xbc => '\u{152}',
+        0xbd => '\u{153}',
+        0xbe => '\u{178}',
+        _ => b as char,
+    }
+}
+
+fn greek(b: u8) -> char {
+    let c = match b {
+        0xa1 => 0x2018,
+        0xa2 => 0x2019,
+        0xa4 => 0x20ac,
+        0xa5 => 0x20af,
+        0xaa => 0x37a,
+        0xaf => 0x2015,
+        0xb4..=0xb6 => 0x384 + (b as u32 - 0xb4),
+        0xae | 0xd2 | 0xff => 0xfffd,
+        0xa0 | 0xa3 | 0xa6..=0xa9 | 0xab..=0xad | 0xb0..=0xb3 | 0xb7 | 0xbb | 0xbd => b as u32,
+        _ => 0x2d0 + b as u32,
+    };
+
+    char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+fn cyrillic(b: u8) -> char {
+    let c = match b {
+        0xa0 | 0xad => b as u32,
+        0xf0 => 0x2116,
+        0xfd => 0xa7,
+        _ => 0x360 + b as u32,
+    };
+
+    char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+fn encode_compound_text(text: &str) -> Vec<u8> {
+    let mut data = vec![];
+    let mut in_utf8 = false;
+
+    for c in text.chars() {
+        let latin1 = matches!(c as u32, 0x09 | 0x0a | 0x20..=0x7e | 0xa0..=0xff);
+
+        if latin1 && in_utf8 {
+            data.extend([ESC, b'%', b'@']);
+            in_utf8 = false;
+        } else if !latin1 && !in_utf8 {
+            data.extend([ESC, b'%', b'G']);
+            in_utf8 = true;
+        }
+
+        if in_utf8 {
+            let mut buf = [0; 4];
+            data.extend(c.encode_utf8(&mut buf).as_bytes());
+        } else {
+            data.push(c as u8);
+        }
+    }
+
+    if in_utf8 {
+        data.extend([ESC, b'%', b'@']);
+    }
+
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latin1_string() {
+        let data = b"caf\xe9";
+        assert_eq!(decode_text(TextEncoding::String, data), "café");
+        assert_eq!(encode_text(TextEncoding::String, "café"), data);
+    }
+
+    #[test]
+    fn compound_text_charsets() {
+        let data = b"abc \x1b-L\xbf\xde\xde \x1b-A\xe9";
+        assert_eq!(decode_text(TextEncoding::CompoundText, data), "abc Поо é");
+    }
+
+    #[test]
+    fn compound_text_utf8_segment() {
+        let encoded = encode_text(TextEncoding::CompoundText, "é → ü");
+        assert_eq!(encoded, b"\xe9 \x1b%G\xe2\x86\x92\x1b%@ \xfc");
+        assert_eq!(decode_text(TextEncoding::CompoundText, &encoded), "é → ü");
+    }
+
+    #[test]
+    fn guess_encoding() {
+        assert_eq!(
+            TextEncoding::guess(xcb::x::ATOM_STRING, b"\xe9"),
+            TextEncoding::String
+        );
+        assert_eq!(
+            TextEncoding::guess(xcb::x::ATOM_NONE, "é".as_bytes()),
+            TextEncoding::Utf8String
+        );
+        assert_eq!(
+            TextEncoding::guess(xcb::x::ATOM_NONE, b"\xe9"),
+            TextEncoding::CompoundText
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! Window Manager Properties
+//!
+//! see: <https://www.x.org/releases/X11R7.7/doc/xorg-docs/icccm/icccm.html#Window_Manager_Properties>
+
+#![allow(dead_code)]
+
+use std::convert::TryFrom;
+
+use crate::icccm::traits::*;
+use crate::icccm::Connection;
+use crate::util::property_value;
+use crate::{Error, Property, Result};
+
+use paste::paste; // Needed for macros
+
+// WM_ICON_SIZE, WM_ICON_SIZE/32
+// {{{
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IconSize {
+    pub min_width: u32,
+    pub min_height: u32,
+    pub max_width: u32,
+    pub max_height: u32,
+    pub width_inc: u32,
+    pub height_inc: u32,
+}
+
+impl IconSize {
+    pub fn new(min_size: (u32, u32), max_size: (u32, u32), size_inc: (u32, u32)) -> IconSize {
+        IconSize {
+            min_width: min_size.0,
+            min_height: min_size.1,
+            max_width: max_size.0,
+            max_height: max_size.1,
+            width_inc: size_inc.0,
+            height_inc: size_inc.1,
+        }
+    }
+
+    /// The allowed size of this range that is nearest to `width`x`height`
+    pub fn closest(&self, width: u32, height: u32) -> (u32, u32) {
+        (
+            IconSize::snap(width, self.min_width, self.max_width, self.width_inc),
+            IconSize::snap(height, self.min_height, self.max_height, self.height_inc),
+        )
+    }
+
+    fn snap(value: u32, min: u32, max: u32, inc: u32) -> u32 {
+        let max = max.max(min);
+        let value = value.clamp(min, max);
+
+        if inc == 0 {
+            return value;
+        }
+
+        // round to the nearest step, but never step beyond `max`
+        let (value, min, max, inc) = (value as u64, min as u64, max as u64, inc as u64);
+        let steps = (value - min + inc / 2) / inc;
+        let snapped = min + steps * inc;
+
+        if snapped > max {
+            (snapped - inc) as u32
+        } else {
+            snapped as u32
+        }
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GetWmIconSizeReply {
+    pub sizes: Vec<IconSize>,
+}
+
+impl GetWmIconSizeReply {
+    /// Pick the allowed icon size closest to an icon of `width`x`height`
+    ///
+    /// `width` and `height` are usually the geometry of the [`crate::icccm::proto::WmHints`]
+    /// icon pixmap. Returns `None` if the window manager did not advertise any sizes.
+    pub fn closest_size(&self, width: u32, height: u32) -> Option<(u32, u32)> {
+        self.sizes
+            .iter()
+            .map(|size| size.closest(width, height))
+            .min_by_key(|(w, h)| {
+                let dw = (*w as i64 - width as i64).pow(2);
+                let dh = (*h as i64 - height as i64).pow(2);
+                dw + dh
+            })
+    }
+}
+
+impl TryFrom<Property> for GetWmIconSizeReply {
+    type Error = Error;
+
+    fn try_from(reply: Property) -> Result<Self> {
+        Ok(GetWmIconSizeReply {
+            sizes: property_value::<u32>(&reply, 6)?
+                .chunks_exact(6)
+                .map(|v| IconSize::new((v[0], v[1]), (v[2], v[3]), (v[4], v[5])))
+                .collect(),
+        })
+    }
+}
+
+icccm_get_property! {
+    request=GetWmIconSize{
+        window: root,
+        property: ATOM_WM_ICON_SIZE,
+        xtype: ATOM_WM_ICON_SIZE
+    },
+    reply=GetWmIconSizeReply
+}
+
+pub struct SetWmIconSize {
+    data: Vec<u32>,
+}
+
+impl SetWmIconSize {
+    pub fn new(sizes: &[IconSize]) -> SetWmIconSize {
+        SetWmIconSize {
+            data: sizes
+                .iter()
+                .flat_map(|s| {
+                    [
+                        s.min_width,
+                        s.min_height,
+                        s.max_width,
+                        s.max_height,
+                        s.width_inc,
+                        s.height_inc,
+                    ]
+                })
+                .collect(),
+        }
+    }
+}
+
+icccm_set_hint_property! {
+    request=SetWmIconSize{
+        window: root,
+        property: ATOM_WM_ICON_SIZE,
+        xtype: ATOM_WM_ICON_SIZE
+    }
+}
+// }}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icon_size_round_trip() {
+        let sizes = vec![
+            IconSize::new((16, 16), (48, 48), (16, 16)),
+            IconSize::new((64, 64), (64, 64), (0, 0)),
+        ];
+        let request = SetWmIconSize::new(&sizes);
+        let property = Property::format32(xcb::x::ATOM_WM_ICON_SIZE, request.data);
+        let reply = GetWmIconSizeReply::try_from(property).unwrap();
+
+        assert_eq!(reply.sizes, sizes);
+        assert_eq!(reply.closest_size(40, 20), Some((48, 16)));
+    }
+
+    #[test]
+    fn icon_size_malformed() {
+        let property = Property::format32(xcb::x::ATOM_WM_ICON_SIZE, vec![1; 5]);
+        assert!(matches!(
+            GetWmIconSizeReply::try_from(property),
+            Err(Error::Truncated {
+                expected: 6,
+                actual: 5
+            })
+        ));
+
+        // A trailing partial range is ignored
+        let property = Property::format32(xcb::x::ATOM_WM_ICON_SIZE, vec![1; 8]);
+        assert_eq!(
+            GetWmIconSizeReply::try_from(property).unwrap().sizes.len(),
+            1
+        );
+
+        // Huge increments must not overflow
+        let size = IconSize::new((0, 0), (u32::MAX, u32::MAX), (u32::MAX, u32::MAX));
+        assert_eq!(size.closest(u32::MAX, 1), (u32::MAX, 0));
+    }
+}
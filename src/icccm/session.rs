@@ -0,0 +1,46 @@
+//! Session management identity of client windows
+//!
+//! see: <https://www.x.org/releases/X11R7.7/doc/libSM/xsmp.html#Window_Manager_Properties>
+
+use crate::icccm::proto::{
+    GetSmClientId, GetWmClass, GetWmClassReply, GetWmClientLeader, GetWmWindowRole,
+};
+use crate::icccm::Connection;
+use crate::Result;
+
+/// The properties session-matching rules key on
+///
+/// `sm_client_id` is read from the client leader ([`GetWmClientLeader`]). If the window does
+/// not name a leader, the window itself is treated as the leader.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SessionIdentity {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::xid"))]
+    pub leader: xcb::x::Window,
+    pub sm_client_id: Option<String>,
+    pub role: Option<String>,
+    pub class: Option<GetWmClassReply>,
+}
+
+impl SessionIdentity {
+    pub fn resolve(con: &Connection, window: xcb::x::Window) -> Result<SessionIdentity> {
+        let leader_cookie = con.send_request(&GetWmClientLeader::new(window));
+        let role_cookie = con.send_request(&GetWmWindowRole::new(window));
+        let class_cookie = con.send_request(&GetWmClass::new(window));
+
+        let leader = con
+            .wait_for_optional_reply(leader_cookie)?
+            .map_or(window, |reply| reply.window);
+
+        let sm_client_id_cookie = con.send_request(&GetSmClientId::new(leader));
+
+        Ok(SessionIdentity {
+            leader,
+            role: con.wait_for_optional_reply(role_cookie)?.map(|r| r.role),
+            class: con.wait_for_optional_reply(class_cookie)?,
+            sm_client_id: con
+                .wait_for_optional_reply(sm_client_id_cookie)?
+                .map(|r| r.client_id),
+        })
+    }
+}
@@ -0,0 +1,58 @@
+//! Typed `PropertyNotify` handling for `icccm` properties
+//!
+//! See [`crate::watch`]. [`Watcher`] maps the events to an [`IcccmProperty`] and decodes the new
+//! value as an [`IcccmEvent`].
+
+use crate::icccm::proto::*;
+use crate::icccm::Connection;
+use crate::Result;
+
+/// Watches windows for changes of `icccm` properties
+pub type Watcher<'a> = crate::watch::Watcher<'a, Connection<'a>>;
+
+watched_properties! {
+    protocol="icccm", property=IcccmProperty, event=IcccmEvent;
+    WmName = "WM_NAME", WmNameChanged(String),
+        |window| GetWmName::new(window), |r| r.name;
+    WmIconName = "WM_ICON_NAME", WmIconNameChanged(String),
+        |window| GetWmIconName::new(window), |r| r.name;
+    WmColorMapWindows = "WM_COLORMAP_WINDOWS", WmColorMapWindowsChanged(Vec<xcb::x::Window>),
+        |window| GetWmColorMapWindows::new(window), |r| r.windows;
+    WmClientMachine = "WM_CLIENT_MACHINE", WmClientMachineChanged(String),
+        |window| GetWmClientMachine::new(window), |r| r.name;
+    WmClass = "WM_CLASS", WmClassChanged(GetWmClassReply),
+        |window| GetWmClass::new(window), |r| r;
+    WmTransientFor = "WM_TRANSIENT_FOR", WmTransientForChanged(xcb::x::Window),
+        |window| GetWmTransientFor::new(window), |r| r.window;
+    WmNormalHints = "WM_NORMAL_HINTS", WmNormalHintsChanged(WmSizeHints),
+        |window| GetWmNormalHints::new(window), |r| r.size_hints;
+    WmHints = "WM_HINTS", WmHintsChanged(WmHints),
+        |window| GetWmHints::new(window), |r| r.size_hints;
+    WmClientLeader = "WM_CLIENT_LEADER", WmClientLeaderChanged(xcb::x::Window),
+        |window| GetWmClientLeader::new(window), |r| r.window;
+    WmWindowRole = "WM_WINDOW_ROLE", WmWindowRoleChanged(String),
+        |window| GetWmWindowRole::new(window), |r| r.role;
+    SmClientId = "SM_CLIENT_ID", SmClientIdChanged(String),
+        |window| GetSmClientId::new(window), |r| r.client_id;
+    WmCommand = "WM_COMMAND", WmCommandChanged(Vec<String>),
+        |window| GetWmCommand::new(window), |r| r.argv;
+    WmLocaleName = "WM_LOCALE_NAME", WmLocaleNameChanged(String),
+        |window| GetWmLocaleName::new(window), |r| r.locale;
+    WmIconSize = "WM_ICON_SIZE", WmIconSizeChanged(Vec<IconSize>),
+        |_window| GetWmIconSize, |r| r.sizes;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IcccmProperty;
+
+    #[test]
+    fn property_names() {
+        assert_eq!(
+            IcccmProperty::from_name("WM_CLASS"),
+            Some(IcccmProperty::WmClass)
+        );
+        assert_eq!(IcccmProperty::WmIconSize.name(), "WM_ICON_SIZE");
+        assert_eq!(IcccmProperty::from_name("_NET_WM_NAME"), None);
+    }
+}
@@ -1,12 +1,66 @@
 #![warn(rustdoc::broken_intra_doc_links)]
 #![warn(rustdoc::private_intra_doc_links)]
 
+#[cfg(any(feature = "ewmh", feature = "icccm"))]
+mod error;
+#[cfg(any(feature = "ewmh", feature = "icccm"))]
+pub use error::{Error, Result};
+
+#[cfg(any(feature = "ewmh", feature = "icccm"))]
+mod atoms;
+#[cfg(any(feature = "ewmh", feature = "icccm"))]
+pub use atoms::AtomCache;
+
+#[cfg(any(feature = "ewmh", feature = "icccm"))]
+pub mod backend;
+
+#[cfg(any(feature = "ewmh", feature = "icccm"))]
+mod property;
+#[cfg(any(feature = "ewmh", feature = "icccm"))]
+pub use property::Property;
+
+#[cfg(any(feature = "ewmh", feature = "icccm"))]
+pub(crate) mod util;
+
+#[cfg(any(feature = "ewmh", feature = "icccm"))]
+#[macro_use]
+pub mod watch;
+
+#[cfg(all(feature = "mock", any(feature = "ewmh", feature = "icccm")))]
+pub mod mock;
+
+#[cfg(all(feature = "serde", any(feature = "ewmh", feature = "icccm")))]
+pub mod serialize;
+
 #[cfg(feature = "ewmh")]
 pub mod ewmh;
 
 #[cfg(feature = "icccm")]
 pub mod icccm;
 
+#[cfg(all(feature = "ewmh", feature = "icccm"))]
+mod traits;
+#[cfg(all(feature = "ewmh", feature = "icccm"))]
+pub use traits::{
+    WmPropertyCookieChecked, WmPropertyCookieUnchecked, WmPropertyRequestUnchecked, WmRequest,
+    WmVoidRequestChecked,
+};
+
+#[cfg(all(feature = "ewmh", feature = "icccm"))]
+mod batch;
+#[cfg(all(feature = "ewmh", feature = "icccm"))]
+pub use batch::{WmBatch, WmBatchCookies};
+
+#[cfg(all(feature = "ewmh", feature = "icccm"))]
+mod connection;
+#[cfg(all(feature = "ewmh", feature = "icccm"))]
+pub use connection::Connection;
+
+#[cfg(all(feature = "ewmh", feature = "icccm"))]
+mod window_info;
+#[cfg(all(feature = "ewmh", feature = "icccm"))]
+pub use window_info::WindowInfo;
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -0,0 +1,929 @@
+//! In-memory [`Backend`] for unit tests without an X server
+//!
+//! A [`PropertyStore`] holds the properties of a fake root window and of the windows a test
+//! creates. `ewmh` and `icccm` connections are built on it like on an [`xcb::Connection`]:
+//!
+//! ```
+//! use xcb_wm::ewmh;
+//! use xcb_wm::mock::PropertyStore;
+//! use xcb_wm::Property;
+//! use xcb::Xid;
+//!
+//! let store = PropertyStore::new();
+//! let window = store.create_window();
+//! store.set_property(
+//!     store.root(),
+//!     "_NET_ACTIVE_WINDOW",
+//!     Property::format32(xcb::x::ATOM_WINDOW, vec![window.resource_id()]),
+//! );
+//!
+//! let ewmh_con = ewmh::Connection::connect(&store);
+//! let reply = ewmh_con
+//!     .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetActiveWindow))
+//!     .unwrap();
+//! assert_eq!(reply.window, window);
+//! ```
+//!
+//! `ChangeProperty` requests are applied to the store, client messages are not interpreted.
+//! Both are recorded in the order they were sent, see [`PropertyStore::requests`]. X errors are
+//! injected with [`PropertyStore::fail_next`] and reported as [`crate::Error::Backend`] with an
+//! [`XError`].
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+
+use xcb::{Xid, XidNew};
+
+use crate::backend::{
+    Backend, PollBackend, PropertyChange, PropertyCookie, PropertyCookieUnchecked, VoidCookie,
+    VoidCookieChecked,
+};
+use crate::{Error, Property, Result};
+
+/// Names of the predefined atoms `1..=68`
+const PREDEFINED_ATOMS: [&str; 68] = [
+    "PRIMARY",
+    "SECONDARY",
+    "ARC",
+    "ATOM",
+    "BITMAP",
+    "CARDINAL",
+    "COLORMAP",
+    "CURSOR",
+    "CUT_BUFFER0",
+    "CUT_BUFFER1",
+    "CUT_BUFFER2",
+    "CUT_BUFFER3",
+    "CUT_BUFFER4",
+    "CUT_BUFFER5",
+    "CUT_BUFFER6",
+    "CUT_BUFFER7",
+    "DRAWABLE",
+    "FONT",
+    "INTEGER",
+    "PIXMAP",
+    "POINT",
+    "RECTANGLE",
+    "RESOURCE_MANAGER",
+    "RGB_COLOR_MAP",
+    "RGB_BEST_MAP",
+    "RGB_BLUE_MAP",
+    "RGB_DEFAULT_MAP",
+    "RGB_GRAY_MAP",
+    "RGB_GREEN_MAP",
+    "RGB_RED_MAP",
+    "STRING",
+    "VISUALID",
+    "WINDOW",
+    "WM_COMMAND",
+    "WM_HINTS",
+    "WM_CLIENT_MACHINE",
+    "WM_ICON_NAME",
+    "WM_ICON_SIZE",
+    "WM_NAME",
+    "WM_NORMAL_HINTS",
+    "WM_SIZE_HINTS",
+    "WM_ZOOM_HINTS",
+    "MIN_SPACE",
+    "NORM_SPACE",
+    "MAX_SPACE",
+    "END_SPACE",
+    "SUPERSCRIPT_X",
+    "SUPERSCRIPT_Y",
+    "SUBSCRIPT_X",
+    "SUBSCRIPT_Y",
+    "UNDERLINE_POSITION",
+    "UNDERLINE_THICKNESS",
+    "STRIKEOUT_ASCENT",
+    "STRIKEOUT_DESCENT",
+    "ITALIC_ANGLE",
+    "X_HEIGHT",
+    "QUAD_WIDTH",
+    "WEIGHT",
+    "POINT_SIZE",
+    "RESOLUTION",
+    "COPYRIGHT",
+    "NOTICE",
+    "FONT_NAME",
+    "FAMILY_NAME",
+    "FULL_NAME",
+    "CAP_HEIGHT",
+    "WM_CLASS",
+    "WM_TRANSIENT_FOR",
+];
+
+const ROOT: u32 = 0x0000_0100;
+const FIRST_WINDOW: u32 = 0x0020_0001;
+
+/// An X error reported by a [`PropertyStore`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XError {
+    /// The window does not exist
+    BadWindow(xcb::x::Window),
+
+    /// The atom does not exist
+    BadAtom(xcb::x::Atom),
+
+    /// A numeric argument is out of range
+    BadValue(u32),
+
+    /// Appending or prepending with a different type or format
+    BadMatch,
+
+    BadAccess,
+
+    BadAlloc,
+}
+
+impl std::fmt::Display for XError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            XError::BadWindow(window) => write!(f, "BadWindow ({:#x})", window.resource_id()),
+            XError::BadAtom(atom) => write!(f, "BadAtom ({})", atom.resource_id()),
+            XError::BadValue(value) => write!(f, "BadValue ({})", value),
+            XError::BadMatch => write!(f, "BadMatch"),
+            XError::BadAccess => write!(f, "BadAccess"),
+            XError::BadAlloc => write!(f, "BadAlloc"),
+        }
+    }
+}
+
+impl std::error::Error for XError {}
+
+impl From<XError> for Error {
+    fn from(e: XError) -> Self {
+        Error::Backend(Box::new(e))
+    }
+}
+
+/// A `ChangeProperty` request sent to a [`PropertyStore`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedProperty {
+    pub mode: xcb::x::PropMode,
+    pub window: xcb::x::Window,
+    pub property: xcb::x::Atom,
+
+    /// Type, format and data of the request
+    pub value: Property,
+}
+
+/// A client message sent to a [`PropertyStore`]
+#[derive(Debug, Clone)]
+pub struct SentClientMessage {
+    pub propagate: bool,
+    pub destination: xcb::x::Window,
+    pub event_mask: xcb::x::EventMask,
+
+    /// The window the message is about
+    pub window: xcb::x::Window,
+    pub r#type: xcb::x::Atom,
+    pub data: xcb::x::ClientMessageData,
+}
+
+impl SentClientMessage {
+    /// The data of a message of format 32
+    pub fn data32(&self) -> Option<[u32; 5]> {
+        match self.data {
+            xcb::x::ClientMessageData::Data32(data) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+/// A request recorded by a [`PropertyStore`]
+#[derive(Debug, Clone)]
+pub enum Request {
+    ChangeProperty(ChangedProperty),
+    ClientMessage(SentClientMessage),
+}
+
+#[derive(Default)]
+struct Store {
+    atoms: Vec<String>,
+    windows: HashSet<xcb::x::Window>,
+    next_window: u32,
+    properties: HashMap<(xcb::x::Window, xcb::x::Atom), Property>,
+    selected_events: HashMap<xcb::x::Window, xcb::x::EventMask>,
+
+    requests: Vec<Request>,
+    injected: VecDeque<XError>,
+    unchecked_errors: Vec<XError>,
+
+    sequence: u64,
+    replies: HashMap<u64, std::result::Result<Property, XError>>,
+    results: HashMap<u64, std::result::Result<(), XError>>,
+}
+
+/// In-memory X server state that implements [`Backend`]
+///
+/// It has one screen with the root window [`PropertyStore::root`]. Properties can be preloaded
+/// on the root window and on windows from [`PropertyStore::create_window`], requests for any
+/// other window fail with [`XError::BadWindow`].
+pub struct PropertyStore {
+    store: Mutex<Store>,
+}
+
+impl Default for PropertyStore {
+    fn default() -> Self {
+        PropertyStore::new()
+    }
+}
+
+impl PropertyStore {
+    pub fn new() -> PropertyStore {
+        let root = xcb::x::Window::new(ROOT);
+
+        PropertyStore {
+            store: Mutex::new(Store {
+                atoms: PREDEFINED_ATOMS
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect(),
+                windows: std::iter::once(root).collect(),
+                next_window: FIRST_WINDOW,
+                ..Store::default()
+            }),
+        }
+    }
+
+    pub fn root(&self) -> xcb::x::Window {
+        xcb::x::Window::new(ROOT)
+    }
+
+    /// A new window without properties
+    pub fn create_window(&self) -> xcb::x::Window {
+        let mut store = self.store.lock().unwrap();
+        let window = xcb::x::Window::new(store.next_window);
+
+        store.next_window += 1;
+        store.windows.insert(window);
+        window
+    }
+
+    /// Remove `window` and its properties, later requests for it fail with `BadWindow`
+    pub fn destroy_window(&self, window: xcb::x::Window) {
+        let mut store = self.store.lock().unwrap();
+
+        store.windows.remove(&window);
+        store.properties.retain(|(w, _), _| *w != window);
+    }
+
+    /// The atom for `name`, interned if needed
+    pub fn atom(&self, name: &str) -> xcb::x::Atom {
+        self.store.lock().unwrap().intern(name)
+    }
+
+    /// Set `property` of `window` without recording a request
+    ///
+    /// # Panics
+    ///
+    /// If `window` does not exist.
+    pub fn set_property(&self, window: xcb::x::Window, property: &str, value: Property) {
+        let mut store = self.store.lock().unwrap();
+        assert!(store.windows.contains(&window), "no window {:?}", window);
+
+        let property = store.intern(property);
+        store.properties.insert((window, property), value);
+    }
+
+    /// The current value of `property` of `window`
+    pub fn property(&self, window: xcb::x::Window, property: &str) -> Option<Property> {
+        let mut store = self.store.lock().unwrap();
+        let property = store.intern(property);
+
+        store.properties.get(&(window, property)).cloned()
+    }
+
+    pub fn delete_property(&self, window: xcb::x::Window, property: &str) {
+        let mut store = self.store.lock().unwrap();
+        let property = store.intern(property);
+
+        store.properties.remove(&(window, property));
+    }
+
+    /// Fail the next request with `error`
+    ///
+    /// Errors are queued, each one fails one request. This includes `GetProperty`,
+    /// `ChangeProperty` and client messages, but not interning atoms.
+    pub fn fail_next(&self, error: XError) {
+        self.store.lock().unwrap().injected.push_back(error);
+    }
+
+    /// All `ChangeProperty` requests and client messages in the order they were sent
+    ///
+    /// Requests that failed are recorded, too.
+    pub fn requests(&self) -> Vec<Request> {
+        self.store.lock().unwrap().requests.clone()
+    }
+
+    /// All `ChangeProperty` requests in the order they were sent
+    pub fn changed_properties(&self) -> Vec<ChangedProperty> {
+        self.requests()
+            .into_iter()
+            .filter_map(|request| match request {
+                Request::ChangeProperty(change) => Some(change),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// All client messages in the order they were sent
+    pub fn client_messages(&self) -> Vec<SentClientMessage> {
+        self.requests()
+            .into_iter()
+            .filter_map(|request| match request {
+                Request::ClientMessage(message) => Some(message),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Forget the recorded requests
+    pub fn clear_requests(&self) {
+        self.store.lock().unwrap().requests.clear();
+    }
+
+    /// Errors of unchecked requests, which an X server sends to the event loop
+    pub fn take_unchecked_errors(&self) -> Vec<XError> {
+        std::mem::take(&mut self.store.lock().unwrap().unchecked_errors)
+    }
+
+    /// The events selected on `window` with [`Backend::select_events`]
+    pub fn selected_events(&self, window: xcb::x::Window) -> xcb::x::EventMask {
+        let store = self.store.lock().unwrap();
+
+        store
+            .selected_events
+            .get(&window)
+            .copied()
+            .unwrap_or(xcb::x::EventMask::NO_EVENT)
+    }
+}
+
+impl Store {
+    fn intern(&mut self, name: &str) -> xcb::x::Atom {
+        let index = match self.atoms.iter().position(|atom| atom == name) {
+            Some(index) => index,
+            None => {
+                self.atoms.push(name.to_owned());
+                self.atoms.len() - 1
+            }
+        };
+
+        xcb::x::Atom::new(index as u32 + 1)
+    }
+
+    fn atom_name(&self, atom: xcb::x::Atom) -> std::result::Result<String, XError> {
+        (atom.resource_id() as usize)
+            .checked_sub(1)
+            .and_then(|index| self.atoms.get(index))
+            .cloned()
+            .ok_or(XError::BadAtom(atom))
+    }
+
+    fn check_window(&self, window: xcb::x::Window) -> std::result::Result<(), XError> {
+        if self.windows.contains(&window) {
+            Ok(())
+        } else {
+            Err(XError::BadWindow(window))
+        }
+    }
+
+    fn check_atom(&self, atom: xcb::x::Atom) -> std::result::Result<(), XError> {
+        self.atom_name(atom).map(|_| ())
+    }
+
+    fn next_sequence(&mut self) -> u64 {
+        self.sequence += 1;
+        self.sequence
+    }
+
+    fn injected_error(&mut self) -> std::result::Result<(), XError> {
+        match self.injected.pop_front() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    fn get_property(
+        &mut self,
+        request: &xcb::x::GetProperty,
+    ) -> std::result::Result<Property, XError> {
+        self.injected_error()?;
+        self.check_window(request.window)?;
+        self.check_atom(request.property)?;
+
+        let key = (request.window, request.property);
+        let property = match self.properties.get(&key) {
+            Some(property) => property,
+            None => return Ok(Property::missing()),
+        };
+
+        let bytes = to_bytes(property);
+        if request.r#type != xcb::x::ATOM_ANY && request.r#type != property.r#type() {
+            return Ok(from_bytes(property.r#type(), property.format(), &[])
+                .with_bytes_after(bytes.len() as u32));
+        }
+
+        // Offset and length are in 4 byte units, like on the wire
+        let start = 4 * request.long_offset as usize;
+        if start > bytes.len() {
+            return Err(XError::BadValue(request.long_offset));
+        }
+        let end = bytes
+            .len()
+            .min(start.saturating_add(4 * request.long_length as usize));
+        let reply = from_bytes(property.r#type(), property.format(), &bytes[start..end])
+            .with_bytes_after((bytes.len() - end) as u32);
+
+        if request.delete && end == bytes.len() {
+            self.properties.remove(&key);
+        }
+
+        Ok(reply)
+    }
+
+    fn change_property(&mut self, request: &PropertyChange) -> std::result::Result<(), XError> {
+        let value = from_bytes(request.r#type, request.format, request.data);
+        self.requests.push(Request::ChangeProperty(ChangedProperty {
+            mode: request.mode,
+            window: request.window,
+            property: request.property,
+            value: value.clone(),
+        }));
+
+        self.injected_error()?;
+        self.check_window(request.window)?;
+        self.check_atom(request.property)?;
+        self.check_atom(request.r#type)?;
+        if ![8, 16, 32].contains(&request.format) {
+            return Err(XError::BadValue(request.format as u32));
+        }
+
+        let key = (request.window, request.property);
+        let value = match (request.mode, self.properties.get(&key)) {
+            (xcb::x::PropMode::Replace, _) | (_, None) => value,
+            (mode, Some(old)) => {
+                if old.r#type() != value.r#type() || old.format() != value.format() {
+                    return Err(XError::BadMatch);
+                }
+
+                let (old, new) = (to_bytes(old), request.data);
+                let bytes = match mode {
+                    xcb::x::PropMode::Prepend => [new, &old].concat(),
+                    _ => [&old, new].concat(),
+                };
+                from_bytes(request.r#type, request.format, &bytes)
+            }
+        };
+
+        self.properties.insert(key, value);
+        Ok(())
+    }
+
+    fn send_client_message(
+        &mut self,
+        request: &xcb::x::SendEvent<xcb::x::ClientMessageEvent>,
+    ) -> std::result::Result<(), XError> {
+        let destination = match request.destination {
+            xcb::x::SendEventDest::Window(window) => window,
+            // There is no pointer or focus in the store
+            _ => xcb::x::Window::none(),
+        };
+
+        self.requests
+            .push(Request::ClientMessage(SentClientMessage {
+                propagate: request.propagate,
+                destination,
+                event_mask: request.event_mask,
+                window: request.event.window(),
+                r#type: request.event.r#type(),
+                data: request.event.data(),
+            }));
+
+        self.injected_error()?;
+        self.check_window(destination)
+    }
+
+    /// Store the outcome of a request that has a reply
+    fn reply(&mut self, reply: std::result::Result<Property, XError>) -> u64 {
+        let sequence = self.next_sequence();
+        self.replies.insert(sequence, reply);
+        sequence
+    }
+
+    /// Store the outcome of a checked request without reply
+    fn result(&mut self, result: std::result::Result<(), XError>) -> u64 {
+        let sequence = self.next_sequence();
+        self.results.insert(sequence, result);
+        sequence
+    }
+
+    /// Keep the error of an unchecked request for the event loop
+    fn unchecked(&mut self, result: std::result::Result<(), XError>) -> u64 {
+        if let Err(error) = result {
+            self.unchecked_errors.push(error);
+        }
+        self.next_sequence()
+    }
+}
+
+/// The value of `property` in native byte order
+fn to_bytes(property: &Property) -> Vec<u8> {
+    match property.format() {
+        8 => property.value::<u8>().to_vec(),
+        16 => property
+            .value::<u16>()
+            .iter()
+            .flat_map(|v| v.to_ne_bytes())
+            .collect(),
+        32 => property
+            .value::<u32>()
+            .iter()
+            .flat_map(|v| v.to_ne_bytes())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// A property of `format` from elements in native byte order
+fn from_bytes(r#type: xcb::x::Atom, format: u8, bytes: &[u8]) -> Property {
+    match format {
+        16 => Property::format16(
+            r#type,
+            bytes
+                .chunks_exact(2)
+                .map(|b| u16::from_ne_bytes([b[0], b[1]]))
+                .collect(),
+        ),
+        32 => Property::format32(
+            r#type,
+            bytes
+                .chunks_exact(4)
+                .map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+        ),
+        _ => Property::format8(r#type, bytes.to_vec()),
+    }
+}
+
+impl Backend for PropertyStore {
+    fn screen_root(&self, screen: i32) -> Result<xcb::x::Window> {
+        if screen == 0 {
+            Ok(self.root())
+        } else {
+            Err(Error::NoSuchScreen(screen))
+        }
+    }
+
+    fn intern_atoms(&self, names: &[&str]) -> Result<Vec<xcb::x::Atom>> {
+        let mut store = self.store.lock().unwrap();
+        Ok(names.iter().map(|name| store.intern(name)).collect())
+    }
+
+    fn atom_name(&self, atom: xcb::x::Atom) -> Result<String> {
+        Ok(self.store.lock().unwrap().atom_name(atom)?)
+    }
+
+    fn get_property(&self, request: &xcb::x::GetProperty) -> PropertyCookie {
+        let mut store = self.store.lock().unwrap();
+        let reply = store.get_property(request);
+        PropertyCookie::new(store.reply(reply))
+    }
+
+    fn get_property_unchecked(&self, request: &xcb::x::GetProperty) -> PropertyCookieUnchecked {
+        let mut store = self.store.lock().unwrap();
+        let reply = store.get_property(request);
+        PropertyCookieUnchecked::new(store.reply(reply))
+    }
+
+    fn wait_for_property(&self, cookie: PropertyCookie) -> Result<Property> {
+        let mut store = self.store.lock().unwrap();
+        match store.replies.remove(&cookie.sequence()) {
+            Some(reply) => Ok(reply?),
+            None => panic!("no reply for request {}", cookie.sequence()),
+        }
+    }
+
+    fn wait_for_property_unchecked(
+        &self,
+        cookie: PropertyCookieUnchecked,
+    ) -> Result<Option<Property>> {
+        let mut store = self.store.lock().unwrap();
+        match store.replies.remove(&cookie.sequence()) {
+            Some(Ok(reply)) => Ok(Some(reply)),
+            Some(Err(error)) => {
+                store.unchecked_errors.push(error);
+                Ok(None)
+            }
+            None => panic!("no reply for request {}", cookie.sequence()),
+        }
+    }
+
+    fn change_property(&self, request: &PropertyChange) -> VoidCookie {
+        let mut store = self.store.lock().unwrap();
+        let result = store.change_property(request);
+        VoidCookie::new(store.unchecked(result))
+    }
+
+    fn change_property_checked(&self, request: &PropertyChange) -> VoidCookieChecked {
+        let mut store = self.store.lock().unwrap();
+        let result = store.change_property(request);
+        VoidCookieChecked::new(store.result(result))
+    }
+
+    fn send_client_message(
+        &self,
+        request: &xcb::x::SendEvent<xcb::x::ClientMessageEvent>,
+    ) -> VoidCookie {
+        let mut store = self.store.lock().unwrap();
+        let result = store.send_client_message(request);
+        VoidCookie::new(store.unchecked(result))
+    }
+
+    fn send_client_message_checked(
+        &self,
+        request: &xcb::x::SendEvent<xcb::x::ClientMessageEvent>,
+    ) -> VoidCookieChecked {
+        let mut store = self.store.lock().unwrap();
+        let result = store.send_client_message(request);
+        VoidCookieChecked::new(store.result(result))
+    }
+
+    fn check_request(&self, cookie: VoidCookieChecked) -> Result<()> {
+        let mut store = self.store.lock().unwrap();
+        match store.results.remove(&cookie.sequence()) {
+            Some(result) => Ok(result?),
+            None => panic!("no result for request {}", cookie.sequence()),
+        }
+    }
+
+    fn event_mask(&self, window: xcb::x::Window) -> Result<xcb::x::EventMask> {
+        self.store.lock().unwrap().check_window(window)?;
+        Ok(self.selected_events(window))
+    }
+
+    fn select_events(&self, window: xcb::x::Window, mask: xcb::x::EventMask) -> Result<()> {
+        let mut store = self.store.lock().unwrap();
+        store.check_window(window)?;
+        store.selected_events.insert(window, mask);
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl PollBackend for PropertyStore {
+    fn poll_for_property(&self, cookie: &PropertyCookie) -> Option<Result<Property>> {
+        let mut store = self.store.lock().unwrap();
+        store
+            .replies
+            .remove(&cookie.sequence())
+            .map(|reply| Ok(reply?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn x_error(e: Error) -> XError {
+        match e {
+            Error::Backend(e) => *e.downcast::<XError>().unwrap(),
+            e => panic!("not an X error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn predefined_atoms() {
+        let store = PropertyStore::new();
+
+        assert_eq!(store.atom("STRING"), xcb::x::ATOM_STRING);
+        assert_eq!(
+            store.atom("WM_TRANSIENT_FOR"),
+            xcb::x::ATOM_WM_TRANSIENT_FOR
+        );
+        assert_eq!(store.atom("UTF8_STRING").resource_id(), 69);
+        assert_eq!(store.atom_name(xcb::x::ATOM_WM_CLASS).unwrap(), "WM_CLASS");
+
+        let unknown = xcb::x::Atom::new(1000);
+        assert_eq!(
+            x_error(store.atom_name(unknown).unwrap_err()),
+            XError::BadAtom(unknown)
+        );
+    }
+
+    #[test]
+    fn get_property_like_the_server() {
+        let store = PropertyStore::new();
+        let window = store.create_window();
+        let property = store.atom("_NET_WM_PID");
+        store.set_property(
+            window,
+            "_NET_WM_PID",
+            Property::format32(xcb::x::ATOM_CARDINAL, vec![1, 2, 3]),
+        );
+
+        let get = |r#type, long_offset, long_length| xcb::x::GetProperty {
+            delete: false,
+            window,
+            property,
+            r#type,
+            long_offset,
+            long_length,
+        };
+        let reply = |request| store.wait_for_property(store.get_property(&request));
+
+        assert_eq!(
+            reply(get(xcb::x::ATOM_CARDINAL, 1, 1)).unwrap(),
+            Property::format32(xcb::x::ATOM_CARDINAL, vec![2]).with_bytes_after(4)
+        );
+        assert_eq!(
+            reply(get(xcb::x::ATOM_ANY, 0, u32::MAX)).unwrap(),
+            Property::format32(xcb::x::ATOM_CARDINAL, vec![1, 2, 3])
+        );
+
+        // A different type only reports the actual type and length
+        assert_eq!(
+            reply(get(xcb::x::ATOM_WINDOW, 0, u32::MAX)).unwrap(),
+            Property::format32(xcb::x::ATOM_CARDINAL, vec![]).with_bytes_after(12)
+        );
+
+        let other = store.create_window();
+        assert_eq!(
+            store
+                .wait_for_property(store.get_property(&xcb::x::GetProperty {
+                    window: other,
+                    ..get(xcb::x::ATOM_ANY, 0, 1)
+                }))
+                .unwrap(),
+            Property::missing()
+        );
+
+        store.destroy_window(window);
+        assert_eq!(
+            x_error(reply(get(xcb::x::ATOM_ANY, 0, 1)).unwrap_err()),
+            XError::BadWindow(window)
+        );
+    }
+
+    #[test]
+    fn change_property_modes() {
+        let store = PropertyStore::new();
+        let window = store.create_window();
+        let change = |mode, r#type, data: &[u32]| {
+            let request = xcb::x::ChangeProperty {
+                mode,
+                window,
+                property: store.atom("_NET_WM_STATE"),
+                r#type,
+                data,
+            };
+            store.check_request(store.change_property_checked(&(&request).into()))
+        };
+
+        change(xcb::x::PropMode::Replace, xcb::x::ATOM_ATOM, &[2]).unwrap();
+        change(xcb::x::PropMode::Append, xcb::x::ATOM_ATOM, &[3]).unwrap();
+        change(xcb::x::PropMode::Prepend, xcb::x::ATOM_ATOM, &[1]).unwrap();
+        assert_eq!(
+            store.property(window, "_NET_WM_STATE"),
+            Some(Property::format32(xcb::x::ATOM_ATOM, vec![1, 2, 3]))
+        );
+
+        let error = change(xcb::x::PropMode::Append, xcb::x::ATOM_CARDINAL, &[4]).unwrap_err();
+        assert_eq!(x_error(error), XError::BadMatch);
+        assert_eq!(store.changed_properties().len(), 4);
+    }
+
+    #[test]
+    fn injected_errors() {
+        let store = PropertyStore::new();
+        let window = store.create_window();
+        let request = xcb::x::ChangeProperty {
+            mode: xcb::x::PropMode::Replace,
+            window,
+            property: xcb::x::ATOM_WM_NAME,
+            r#type: xcb::x::ATOM_STRING,
+            data: b"name",
+        };
+
+        store.fail_next(XError::BadAlloc);
+        store.change_property(&(&request).into());
+        assert_eq!(store.take_unchecked_errors(), vec![XError::BadAlloc]);
+        assert_eq!(store.property(window, "WM_NAME"), None);
+
+        store
+            .check_request(store.change_property_checked(&(&request).into()))
+            .unwrap();
+        assert_eq!(
+            store.property(window, "WM_NAME"),
+            Some(Property::format8(xcb::x::ATOM_STRING, b"name".to_vec()))
+        );
+    }
+
+    #[cfg(feature = "ewmh")]
+    #[test]
+    fn ewmh_requests() {
+        use crate::ewmh;
+
+        let store = PropertyStore::new();
+        let window = store.create_window();
+        let ewmh_con = ewmh::Connection::connect(&store);
+
+        store.set_property(
+            store.root(),
+            "_NET_ACTIVE_WINDOW",
+            Property::format32(xcb::x::ATOM_WINDOW, vec![window.resource_id()]),
+        );
+        let reply = ewmh_con
+            .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetActiveWindow))
+            .unwrap();
+        assert_eq!(reply.window, window);
+
+        ewmh_con
+            .send_and_check_request(&ewmh::proto::SetWmPid::new(window, 42))
+            .unwrap();
+        let reply = ewmh_con
+            .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetWmPid(window)))
+            .unwrap();
+        assert_eq!(reply.pid, 42);
+
+        ewmh_con
+            .send_and_check_request(
+                &ewmh::proto::SendCloseWindow::new(&ewmh_con, window, 2, 0).unwrap(),
+            )
+            .unwrap();
+        let messages = store.client_messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].destination, store.root());
+        assert_eq!(messages[0].window, window);
+        assert_eq!(
+            store.atom_name(messages[0].r#type).unwrap(),
+            "_NET_CLOSE_WINDOW"
+        );
+        assert_eq!(messages[0].data32(), Some([0, 2, 0, 0, 0]));
+
+        ewmh_con
+            .send_and_check_request(
+                &ewmh::proto::SendMoveresizeWindow::new(
+                    &ewmh_con,
+                    window,
+                    xcb::x::Gravity::NorthWest,
+                    2,
+                    Some(-10),
+                    None,
+                    Some(640),
+                    None,
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        let messages = store.client_messages();
+        assert_eq!(
+            messages[1].data32(),
+            Some([1 | 0b0101 << 8 | 2 << 12, -10i32 as u32, 0, 640, 0])
+        );
+
+        store.fail_next(XError::BadWindow(window));
+        let error = ewmh_con
+            .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetWmPid(window)))
+            .unwrap_err();
+        assert_eq!(x_error(error), XError::BadWindow(window));
+    }
+
+    #[cfg(feature = "icccm")]
+    #[test]
+    fn icccm_requests() {
+        use crate::icccm;
+
+        let store = PropertyStore::new();
+        let window = store.create_window();
+        let icccm_con = icccm::Connection::connect(&store);
+
+        icccm_con
+            .send_and_check_request(&icccm::proto::SetWmClass::new(window, "xterm", "XTerm"))
+            .unwrap();
+        assert_eq!(
+            store.property(window, "WM_CLASS"),
+            Some(Property::format8(
+                xcb::x::ATOM_STRING,
+                b"xterm\0XTerm\0".to_vec()
+            ))
+        );
+
+        let reply = icccm_con
+            .wait_for_reply(icccm_con.send_request(&icccm::proto::GetWmClass::new(window)))
+            .unwrap();
+        assert_eq!(
+            (reply.instance.as_str(), reply.class.as_str()),
+            ("xterm", "XTerm")
+        );
+
+        let missing = icccm_con
+            .wait_for_optional_reply(icccm_con.send_request(&icccm::proto::GetWmName::new(window)))
+            .unwrap();
+        assert!(missing.is_none());
+    }
+}
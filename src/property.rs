@@ -0,0 +1,209 @@
+//! Transport independent value of a window property
+//!
+//! Every [`crate::backend::Backend`] hands `GetProperty` replies to the decoders as a
+//! [`Property`]. The `ewmh` and `icccm` replies are decoded from it with `TryFrom<Property>`, so
+//! decoding works the same no matter how the property was fetched. `icccm` `TEXT` replies also
+//! need the connection, which knows the atoms of their encodings.
+
+/// Type, format and value of a window property
+///
+/// A property that is not set has type `ATOM_NONE` and format `0`. If a property was requested
+/// with a different type than it has, the server sends the actual type and format, but no value
+/// and the full length in `bytes_after`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Property {
+    r#type: xcb::x::Atom,
+    bytes_after: u32,
+    value: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    None,
+    Format8(Vec<u8>),
+    Format16(Vec<u16>),
+    Format32(Vec<u32>),
+}
+
+impl Property {
+    /// A property that is not set
+    pub fn missing() -> Property {
+        Property {
+            r#type: xcb::x::ATOM_NONE,
+            bytes_after: 0,
+            value: Value::None,
+        }
+    }
+
+    /// An 8 bit property, e.g. a string
+    pub fn format8(r#type: xcb::x::Atom, value: Vec<u8>) -> Property {
+        Property::with_value(r#type, Value::Format8(value))
+    }
+
+    /// A 16 bit property
+    pub fn format16(r#type: xcb::x::Atom, value: Vec<u16>) -> Property {
+        Property::with_value(r#type, Value::Format16(value))
+    }
+
+    /// A 32 bit property, e.g. a list of cardinals, atoms or windows
+    pub fn format32(r#type: xcb::x::Atom, value: Vec<u32>) -> Property {
+        Property::with_value(r#type, Value::Format32(value))
+    }
+
+    fn with_value(r#type: xcb::x::Atom, value: Value) -> Property {
+        Property {
+            r#type,
+            bytes_after: 0,
+            value,
+        }
+    }
+
+    /// Set the number of bytes the server did not send
+    pub fn with_bytes_after(mut self, bytes_after: u32) -> Property {
+        self.bytes_after = bytes_after;
+        self
+    }
+
+    pub fn r#type(&self) -> xcb::x::Atom {
+        self.r#type
+    }
+
+    /// 8, 16 or 32, `0` if the property is not set
+    pub fn format(&self) -> u8 {
+        match self.value {
+            Value::None => 0,
+            Value::Format8(_) => 8,
+            Value::Format16(_) => 16,
+            Value::Format32(_) => 32,
+        }
+    }
+
+    pub fn bytes_after(&self) -> u32 {
+        self.bytes_after
+    }
+
+    /// Number of elements of the value
+    pub fn len(&self) -> usize {
+        match &self.value {
+            Value::None => 0,
+            Value::Format8(v) => v.len(),
+            Value::Format16(v) => v.len(),
+            Value::Format32(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The value as elements of `P`
+    ///
+    /// # Panics
+    ///
+    /// If the format of `P` is not the format of the property, like
+    /// [`xcb::x::GetPropertyReply::value`].
+    pub fn value<P: xcb::x::PropEl>(&self) -> &[P] {
+        assert_eq!(
+            P::FORMAT,
+            self.format(),
+            "property of format {} accessed with format {}",
+            self.format(),
+            P::FORMAT
+        );
+        assert_eq!(std::mem::size_of::<P>() * 8, P::FORMAT as usize);
+
+        let (ptr, len) = match &self.value {
+            Value::None => return &[],
+            Value::Format8(v) => (v.as_ptr() as *const P, v.len()),
+            Value::Format16(v) => (v.as_ptr() as *const P, v.len()),
+            Value::Format32(v) => (v.as_ptr() as *const P, v.len()),
+        };
+
+        // SAFETY: `P` has the size of the elements of the matching buffer, so its alignment is
+        // at most theirs. Like the reply buffers of `xcb`, `PropEl` types are plain integers or
+        // resource ids.
+        unsafe { std::slice::from_raw_parts(ptr, len) }
+    }
+}
+
+impl From<xcb::x::GetPropertyReply> for Property {
+    fn from(reply: xcb::x::GetPropertyReply) -> Property {
+        let value = match reply.format() {
+            8 => Value::Format8(reply.value::<u8>().to_vec()),
+            16 => Value::Format16(reply.value::<u16>().to_vec()),
+            32 => Value::Format32(reply.value::<u32>().to_vec()),
+            _ => Value::None,
+        };
+
+        Property::with_value(reply.r#type(), value).with_bytes_after(reply.bytes_after())
+    }
+}
+
+#[cfg(feature = "x11rb")]
+impl From<x11rb::protocol::xproto::GetPropertyReply> for Property {
+    fn from(reply: x11rb::protocol::xproto::GetPropertyReply) -> Property {
+        let value = match reply.format {
+            8 => Value::Format8(reply.value),
+            16 => Value::Format16(reply.value16().map_or(vec![], Iterator::collect)),
+            32 => Value::Format32(reply.value32().map_or(vec![], Iterator::collect)),
+            _ => Value::None,
+        };
+
+        Property::with_value(xcb::XidNew::new(reply.type_), value)
+            .with_bytes_after(reply.bytes_after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xcb::Xid;
+
+    #[test]
+    fn value_of_each_format() {
+        let bytes = Property::format8(xcb::x::ATOM_STRING, b"abc".to_vec());
+        assert_eq!((bytes.format(), bytes.len()), (8, 3));
+        assert_eq!(bytes.value::<u8>(), b"abc");
+
+        let shorts = Property::format16(xcb::x::ATOM_INTEGER, vec![1, 2]);
+        assert_eq!(shorts.value::<u16>(), &[1, 2]);
+
+        let atoms = Property::format32(xcb::x::ATOM_ATOM, vec![xcb::x::ATOM_STRING.resource_id()]);
+        assert_eq!(atoms.value::<xcb::x::Atom>(), &[xcb::x::ATOM_STRING]);
+    }
+
+    #[test]
+    fn missing_property() {
+        let missing = Property::missing();
+        assert_eq!(missing.r#type(), xcb::x::ATOM_NONE);
+        assert_eq!(missing.format(), 0);
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn value_of_wrong_format() {
+        Property::format8(xcb::x::ATOM_STRING, vec![]).value::<u32>();
+    }
+
+    #[test]
+    #[cfg(feature = "x11rb")]
+    fn from_x11rb_reply() {
+        let value: Vec<u8> = [7u32, 8].iter().flat_map(|v| v.to_ne_bytes()).collect();
+        let reply = x11rb::protocol::xproto::GetPropertyReply {
+            format: 32,
+            sequence: 0,
+            length: 2,
+            type_: xcb::x::ATOM_CARDINAL.resource_id(),
+            bytes_after: 4,
+            value_len: 2,
+            value,
+        };
+
+        let property = Property::from(reply);
+        assert_eq!(
+            property,
+            Property::format32(xcb::x::ATOM_CARDINAL, vec![7, 8]).with_bytes_after(4)
+        );
+    }
+}
@@ -0,0 +1,710 @@
+//! `serde` support for replies and hints
+//!
+//! Enabled by the `serde` feature. Window and pixmap ids serialize as plain integers. Atoms do as
+//! well, unless they are serialized through [`AtomNamed`], which looks up their names first, or
+//! (de)serialization runs inside [`with_atom_names`]: then the atoms of its [`AtomTable`]
+//! serialize by name and deserialize from either a name or an integer.
+//!
+//! ```no_run
+//! # fn example(con: &xcb_wm::ewmh::Connection) -> xcb_wm::Result<()> {
+//! let reply = con.wait_for_reply(con.send_request(&xcb_wm::ewmh::proto::GetSupported))?;
+//!
+//! // `{"atoms":[301,302]}`
+//! let by_id = serde_json::to_string(&reply).unwrap();
+//! // `{"atoms":["_NET_SUPPORTED","_NET_CLIENT_LIST"]}`
+//! let by_name = serde_json::to_string(&xcb_wm::serialize::AtomNamed::new(con, &reply)).unwrap();
+//! # Ok(())
+//! # }
+//! ```
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::{SerializeSeq, Serializer};
+use serde::{Deserialize, Serialize};
+use xcb::{Xid, XidNew};
+
+/// Translation between atoms and their names
+///
+/// Implemented by the connections, which look names up and intern atoms on first use.
+pub trait AtomNames {
+    /// The name of `atom`, `None` if it cannot be looked up
+    fn atom_name(&self, atom: xcb::x::Atom) -> Option<String>;
+
+    /// The atom for `name`, `None` if it cannot be interned
+    fn atom(&self, name: &str) -> Option<xcb::x::Atom>;
+}
+
+#[cfg(feature = "ewmh")]
+impl<'a> AtomNames for crate::ewmh::Connection<'a> {
+    fn atom_name(&self, atom: xcb::x::Atom) -> Option<String> {
+        crate::ewmh::Connection::atom_name(self, atom).ok()
+    }
+
+    fn atom(&self, name: &str) -> Option<xcb::x::Atom> {
+        crate::ewmh::Connection::atom(self, name).ok()
+    }
+}
+
+#[cfg(feature = "icccm")]
+impl<'a> AtomNames for crate::icccm::Connection<'a> {
+    fn atom_name(&self, atom: xcb::x::Atom) -> Option<String> {
+        crate::icccm::Connection::atom_name(self, atom).ok()
+    }
+
+    fn atom(&self, name: &str) -> Option<xcb::x::Atom> {
+        crate::icccm::Connection::atom(self, name).ok()
+    }
+}
+
+#[cfg(all(feature = "ewmh", feature = "icccm"))]
+impl<'a> AtomNames for crate::Connection<'a> {
+    fn atom_name(&self, atom: xcb::x::Atom) -> Option<String> {
+        crate::Connection::atom_name(self, atom).ok()
+    }
+
+    fn atom(&self, name: &str) -> Option<xcb::x::Atom> {
+        crate::Connection::atom(self, name).ok()
+    }
+}
+
+/// Atoms and their names, looked up ahead of (de)serialization
+///
+/// [`with_atom_names`] (de)serializes the atoms of the table by name. [`AtomNamed`] fills one
+/// with the atoms of a value.
+#[derive(Debug, Clone, Default)]
+pub struct AtomTable {
+    names: HashMap<xcb::x::Atom, String>,
+    atoms: HashMap<String, xcb::x::Atom>,
+}
+
+impl AtomTable {
+    pub fn new() -> AtomTable {
+        AtomTable::default()
+    }
+
+    pub fn insert(&mut self, atom: xcb::x::Atom, name: &str) {
+        self.names.insert(atom, name.to_owned());
+        self.atoms.insert(name.to_owned(), atom);
+    }
+
+    /// Look up the names of `atoms` that are not in the table yet
+    ///
+    /// Atoms without a name (e.g. `ATOM_NONE`) are left out.
+    pub fn lookup(
+        &mut self,
+        names: &dyn AtomNames,
+        atoms: impl IntoIterator<Item = xcb::x::Atom>,
+    ) -> &mut AtomTable {
+        for atom in atoms {
+            if self.names.contains_key(&atom) {
+                continue;
+            }
+            if let Some(name) = names.atom_name(atom) {
+                self.insert(atom, &name);
+            }
+        }
+        self
+    }
+
+    /// Intern the atoms `atom_names` that are not in the table yet
+    ///
+    /// Names that cannot be interned are left out.
+    pub fn intern<'n>(
+        &mut self,
+        names: &dyn AtomNames,
+        atom_names: impl IntoIterator<Item = &'n str>,
+    ) -> &mut AtomTable {
+        for name in atom_names {
+            if self.atoms.contains_key(name) {
+                continue;
+            }
+            if let Some(atom) = names.atom(name) {
+                self.insert(atom, name);
+            }
+        }
+        self
+    }
+}
+
+/// What atoms (de)serialize as
+enum Mode {
+    /// [`atoms_in`]: note the atoms that are serialized
+    Collect(HashSet<xcb::x::Atom>),
+
+    /// [`with_atom_names`]: (de)serialize the atoms of the table by name
+    Names(AtomTable),
+}
+
+thread_local! {
+    /// The modes of the nested calls, innermost last
+    static MODES: RefCell<Vec<Mode>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Run `f` with `mode` installed, it is removed again even if `f` panics
+fn with_installed<R>(mode: Mode, f: impl FnOnce() -> R) -> (R, Mode) {
+    struct Installed;
+
+    impl Drop for Installed {
+        fn drop(&mut self) {
+            MODES.with(|modes| modes.borrow_mut().pop());
+        }
+    }
+
+    MODES.with(|modes| modes.borrow_mut().push(mode));
+    let installed = Installed;
+    let result = f();
+
+    let mode = MODES.with(|modes| modes.borrow_mut().pop().unwrap());
+    std::mem::forget(installed);
+    (result, mode)
+}
+
+/// Apply `f` to the innermost mode, `None` outside of [`with_atom_names`] and [`atoms_in`]
+fn with_mode<R>(f: impl FnOnce(Option<&mut Mode>) -> R) -> R {
+    MODES.with(|modes| f(modes.borrow_mut().last_mut()))
+}
+
+/// Run `f` with the atoms of `table` (de)serialized by name
+///
+/// Atoms that are not in the table still serialize as integers, names that are not in it fail to
+/// deserialize. Calls nest; the innermost `table` is used.
+///
+/// ```no_run
+/// # fn example(con: &xcb_wm::ewmh::Connection, json: &str) -> xcb_wm::Result<()> {
+/// use xcb_wm::serialize::{with_atom_names, AtomTable};
+///
+/// let mut table = AtomTable::new();
+/// table.intern(con, ["_NET_WM_STATE_ABOVE", "_NET_WM_STATE_HIDDEN"]);
+/// let reply: xcb_wm::ewmh::proto::GetWmStateReply =
+///     with_atom_names(&table, || serde_json::from_str(json)).unwrap();
+/// # Ok(())
+/// # }
+/// ```
+pub fn with_atom_names<R>(table: &AtomTable, f: impl FnOnce() -> R) -> R {
+    with_installed(Mode::Names(table.clone()), f).0
+}
+
+/// The atoms that serializing `value` writes
+pub fn atoms_in<T: Serialize + ?Sized>(value: &T) -> HashSet<xcb::x::Atom> {
+    let (_, mode) = with_installed(Mode::Collect(HashSet::new()), || {
+        value.serialize(collect::Collector)
+    });
+
+    match mode {
+        Mode::Collect(atoms) => atoms,
+        Mode::Names(_) => unreachable!("nested calls remove their own mode"),
+    }
+}
+
+/// `value` with its atoms serialized by name, looked up in `names`
+///
+/// The names of the atoms of `value` are looked up before it is serialized. Atoms without a name
+/// (e.g. `ATOM_NONE`) still serialize as integers.
+pub struct AtomNamed<'a, T: ?Sized> {
+    names: &'a dyn AtomNames,
+    value: &'a T,
+}
+
+impl<'a, T: Serialize + ?Sized> AtomNamed<'a, T> {
+    pub fn new(names: &'a dyn AtomNames, value: &'a T) -> AtomNamed<'a, T> {
+        AtomNamed { names, value }
+    }
+}
+
+impl<T: Serialize + ?Sized> Serialize for AtomNamed<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut table = AtomTable::new();
+        table.lookup(self.names, atoms_in(self.value));
+
+        with_atom_names(&table, || self.value.serialize(serializer))
+    }
+}
+
+/// A [`Serializer`] that discards everything, for [`atoms_in`]
+mod collect {
+    use std::fmt;
+
+    use serde::ser::{self, Serialize};
+
+    pub struct Collector;
+
+    #[derive(Debug)]
+    pub struct Error(String);
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str(&self.0)
+        }
+    }
+
+    impl std::error::Error for Error {}
+
+    impl ser::Error for Error {
+        fn custom<T: fmt::Display>(msg: T) -> Error {
+            Error(msg.to_string())
+        }
+    }
+
+    type Result = std::result::Result<(), Error>;
+
+    macro_rules! discard {
+        ($($method:ident($($ty:ty),*);)+) => {
+            $(fn $method(self, $(_: $ty),*) -> Result {
+                Ok(())
+            })+
+        };
+    }
+
+    impl ser::Serializer for Collector {
+        type Ok = ();
+        type Error = Error;
+        type SerializeSeq = Collector;
+        type SerializeTuple = Collector;
+        type SerializeTupleStruct = Collector;
+        type SerializeTupleVariant = Collector;
+        type SerializeMap = Collector;
+        type SerializeStruct = Collector;
+        type SerializeStructVariant = Collector;
+
+        discard! {
+            serialize_bool(bool);
+            serialize_i8(i8);
+            serialize_i16(i16);
+            serialize_i32(i32);
+            serialize_i64(i64);
+            serialize_u8(u8);
+            serialize_u16(u16);
+            serialize_u32(u32);
+            serialize_u64(u64);
+            serialize_f32(f32);
+            serialize_f64(f64);
+            serialize_char(char);
+            serialize_str(&str);
+            serialize_bytes(&[u8]);
+            serialize_none();
+            serialize_unit();
+            serialize_unit_struct(&'static str);
+            serialize_unit_variant(&'static str, u32, &'static str);
+        }
+
+        fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result {
+            value.serialize(Collector)
+        }
+
+        fn serialize_newtype_struct<T: Serialize + ?Sized>(
+            self,
+            _: &'static str,
+            value: &T,
+        ) -> Result {
+            value.serialize(Collector)
+        }
+
+        fn serialize_newtype_variant<T: Serialize + ?Sized>(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            value: &T,
+        ) -> Result {
+            value.serialize(Collector)
+        }
+
+        fn serialize_seq(self, _: Option<usize>) -> std::result::Result<Collector, Error> {
+            Ok(Collector)
+        }
+
+        fn serialize_tuple(self, _: usize) -> std::result::Result<Collector, Error> {
+            Ok(Collector)
+        }
+
+        fn serialize_tuple_struct(
+            self,
+            _: &'static str,
+            _: usize,
+        ) -> std::result::Result<Collector, Error> {
+            Ok(Collector)
+        }
+
+        fn serialize_tuple_variant(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: usize,
+        ) -> std::result::Result<Collector, Error> {
+            Ok(Collector)
+        }
+
+        fn serialize_map(self, _: Option<usize>) -> std::result::Result<Collector, Error> {
+            Ok(Collector)
+        }
+
+        fn serialize_struct(
+            self,
+            _: &'static str,
+            _: usize,
+        ) -> std::result::Result<Collector, Error> {
+            Ok(Collector)
+        }
+
+        fn serialize_struct_variant(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: usize,
+        ) -> std::result::Result<Collector, Error> {
+            Ok(Collector)
+        }
+    }
+
+    /// Implement compound serializer traits whose elements are serialized with `$method`
+    macro_rules! compound {
+        ($($trait:ident::$method:ident($($key:ident),*);)+) => {
+            $(impl ser::$trait for Collector {
+                type Ok = ();
+                type Error = Error;
+
+                fn $method<T: Serialize + ?Sized>(&mut self, $($key: &'static str,)* value: &T) -> Result {
+                    value.serialize(Collector)
+                }
+
+                fn end(self) -> Result {
+                    Ok(())
+                }
+            })+
+        };
+    }
+
+    compound! {
+        SerializeSeq::serialize_element();
+        SerializeTuple::serialize_element();
+        SerializeTupleStruct::serialize_field();
+        SerializeTupleVariant::serialize_field();
+        SerializeStruct::serialize_field(_key);
+        SerializeStructVariant::serialize_field(_key);
+    }
+
+    impl ser::SerializeMap for Collector {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result {
+            key.serialize(Collector)
+        }
+
+        fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result {
+            value.serialize(Collector)
+        }
+
+        fn end(self) -> Result {
+            Ok(())
+        }
+    }
+}
+
+/// `#[serde(with)]` for window and pixmap ids
+pub(crate) mod xid {
+    use super::*;
+
+    pub fn serialize<T: Xid, S: Serializer>(id: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(id.resource_id())
+    }
+
+    pub fn deserialize<'de, T: XidNew, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        Ok(T::new(u32::deserialize(deserializer)?))
+    }
+}
+
+/// `#[serde(with)]` for `Option`s of window ids
+pub(crate) mod optional_xid {
+    use super::*;
+
+    pub fn serialize<T: Xid, S: Serializer>(
+        id: &Option<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        id.as_ref().map(Xid::resource_id).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: XidNew, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<T>, D::Error> {
+        Ok(Option::<u32>::deserialize(deserializer)?.map(T::new))
+    }
+}
+
+/// `#[serde(with)]` for lists of window ids
+pub(crate) mod xids {
+    use super::*;
+
+    pub fn serialize<T: Xid, S: Serializer>(ids: &[T], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(ids.iter().map(Xid::resource_id))
+    }
+
+    pub fn deserialize<'de, T: XidNew, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<T>, D::Error> {
+        Ok(Vec::<u32>::deserialize(deserializer)?
+            .into_iter()
+            .map(T::new)
+            .collect())
+    }
+}
+
+/// An atom as (de)serialized by `atoms`
+struct AtomRepr(xcb::x::Atom);
+
+impl Serialize for AtomRepr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let name = with_mode(|mode| match mode {
+            Some(Mode::Collect(atoms)) => {
+                atoms.insert(self.0);
+                None
+            }
+            Some(Mode::Names(table)) => table.names.get(&self.0).cloned(),
+            None => None,
+        });
+
+        match name {
+            Some(name) => serializer.serialize_str(&name),
+            None => serializer.serialize_u32(self.0.resource_id()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for AtomRepr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AtomVisitor;
+
+        impl<'de> Visitor<'de> for AtomVisitor {
+            type Value = AtomRepr;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an atom id or name")
+            }
+
+            fn visit_u64<E: de::Error>(self, id: u64) -> Result<AtomRepr, E> {
+                u32::try_from(id)
+                    .map(|id| AtomRepr(xcb::x::Atom::new(id)))
+                    .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(id), &self))
+            }
+
+            fn visit_str<E: de::Error>(self, name: &str) -> Result<AtomRepr, E> {
+                with_mode(|mode| match mode {
+                    Some(Mode::Names(table)) => {
+                        table.atoms.get(name).copied().map(AtomRepr).ok_or_else(|| {
+                            E::custom(format_args!("atom {} is not in the atom table", name))
+                        })
+                    }
+                    _ => Err(E::custom(format_args!(
+                        "atom {} given by name outside of `with_atom_names`",
+                        name
+                    ))),
+                })
+            }
+        }
+
+        // Only self-describing formats can tell names from ids
+        if with_mode(|mode| matches!(mode, Some(Mode::Names(_)))) {
+            deserializer.deserialize_any(AtomVisitor)
+        } else {
+            deserializer.deserialize_u32(AtomVisitor)
+        }
+    }
+}
+
+/// `#[serde(with)]` for lists of atoms
+pub(crate) mod atoms {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        atoms: &[xcb::x::Atom],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(atoms.len()))?;
+        for atom in atoms {
+            seq.serialize_element(&AtomRepr(*atom))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<xcb::x::Atom>, D::Error> {
+        Ok(Vec::<AtomRepr>::deserialize(deserializer)?
+            .into_iter()
+            .map(|AtomRepr(atom)| atom)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    struct Names(HashMap<&'static str, u32>);
+
+    impl Names {
+        fn new() -> Names {
+            Names([("_NET_WM_STATE_ABOVE", 301), ("_NET_WM_STATE_HIDDEN", 302)].into())
+        }
+    }
+
+    impl AtomNames for Names {
+        fn atom_name(&self, atom: xcb::x::Atom) -> Option<String> {
+            self.0
+                .iter()
+                .find(|(_, id)| **id == atom.resource_id())
+                .map(|(name, _)| name.to_string())
+        }
+
+        fn atom(&self, name: &str) -> Option<xcb::x::Atom> {
+            self.0.get(name).map(|id| xcb::x::Atom::new(*id))
+        }
+    }
+
+    fn round_trip<T: Serialize + serde::de::DeserializeOwned>(value: &T) -> T {
+        serde_json::from_value(serde_json::to_value(value).unwrap()).unwrap()
+    }
+
+    #[cfg(feature = "ewmh")]
+    #[test]
+    fn ids_as_integers() {
+        use crate::ewmh::proto::{GetClientListReply, GetWmStateReply};
+
+        let clients = GetClientListReply {
+            clients: vec![xcb::x::Window::new(0x200001), xcb::x::Window::new(0x400002)],
+        };
+        assert_eq!(
+            serde_json::to_value(&clients).unwrap(),
+            json!({ "clients": [0x200001, 0x400002] })
+        );
+        assert_eq!(round_trip(&clients).clients, clients.clients);
+
+        let states = GetWmStateReply {
+            states: vec![xcb::x::Atom::new(301), xcb::x::Atom::new(302)],
+        };
+        assert_eq!(
+            serde_json::to_value(&states).unwrap(),
+            json!({ "states": [301, 302] })
+        );
+        assert_eq!(round_trip(&states).states, states.states);
+    }
+
+    #[cfg(feature = "ewmh")]
+    #[test]
+    fn atoms_by_name() {
+        use crate::ewmh::proto::GetWmStateReply;
+
+        let names = Names::new();
+        let states = GetWmStateReply {
+            states: vec![
+                xcb::x::Atom::new(301),
+                xcb::x::Atom::new(302),
+                xcb::x::ATOM_NONE,
+            ],
+        };
+        assert_eq!(
+            atoms_in(&states),
+            [301, 302, 0]
+                .iter()
+                .map(|id| xcb::x::Atom::new(*id))
+                .collect()
+        );
+
+        let value = serde_json::to_value(AtomNamed::new(&names, &states)).unwrap();
+        assert_eq!(
+            value,
+            json!({ "states": ["_NET_WM_STATE_ABOVE", "_NET_WM_STATE_HIDDEN", 0] })
+        );
+
+        let mut table = AtomTable::new();
+        table.intern(&names, ["_NET_WM_STATE_ABOVE", "_NET_WM_STATE_HIDDEN"]);
+        let mut runs = 0;
+        let parsed: GetWmStateReply = with_atom_names(&table, || {
+            runs += 1;
+            serde_json::from_value(value.clone()).unwrap()
+        });
+        assert_eq!(runs, 1);
+        assert_eq!(parsed.states, states.states);
+
+        // Names cannot be resolved outside of `with_atom_names`
+        assert!(serde_json::from_value::<GetWmStateReply>(value).is_err());
+        let unknown = json!({ "states": ["_NET_WM_STATE_STICKY"] });
+        assert!(
+            with_atom_names(&table, || serde_json::from_value::<GetWmStateReply>(
+                unknown
+            ))
+            .is_err()
+        );
+
+        // The table is uninstalled again
+        assert_eq!(
+            serde_json::to_value(&states).unwrap(),
+            json!({ "states": [301, 302, 0] })
+        );
+    }
+
+    #[cfg(feature = "ewmh")]
+    #[test]
+    fn ewmh_state() {
+        use crate::ewmh::proto::GetWorkareaReply;
+        use crate::ewmh::{EwmhState, EwmhStateChange};
+
+        let state = EwmhState {
+            client_list: vec![xcb::x::Window::new(0x200001)],
+            active_window: Some(xcb::x::Window::new(0x200001)),
+            workarea: Some(GetWorkareaReply {
+                x: 0,
+                y: 20,
+                width: 1280,
+                height: 780,
+            }),
+            ..EwmhState::default()
+        };
+        assert_eq!(round_trip(&state), state);
+
+        let change = EwmhStateChange::ActiveWindowChanged(None);
+        assert_eq!(
+            serde_json::to_value(&change).unwrap(),
+            json!({ "ActiveWindowChanged": null })
+        );
+        assert_eq!(round_trip(&change), change);
+    }
+
+    #[cfg(feature = "icccm")]
+    #[test]
+    fn hints() {
+        use crate::icccm::proto::{WmHints, WmInitialState, WmSizeHints};
+
+        let mut size_hints = WmSizeHints::default();
+        size_hints.size(true, 640, 480);
+        size_hints.win_gravity(xcb::x::Gravity::Center);
+        assert_eq!(round_trip(&size_hints), size_hints);
+
+        let value = serde_json::to_value(&size_hints).unwrap();
+        assert_eq!(value["win_gravity"], json!(xcb::x::Gravity::Center as u32));
+
+        let mut invalid = value;
+        invalid["win_gravity"] = json!(42);
+        assert!(serde_json::from_value::<WmSizeHints>(invalid).is_err());
+
+        let mut hints = WmHints::default();
+        hints.initial_state(WmInitialState::Iconic);
+        hints.window_group(xcb::x::Window::new(0x200001));
+        assert_eq!(round_trip(&hints), hints);
+
+        let value = serde_json::to_value(&hints).unwrap();
+        assert_eq!(value["window_group"], json!(0x200001));
+        assert_eq!(value["initial_state"], json!("Iconic"));
+    }
+}
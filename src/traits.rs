@@ -0,0 +1,148 @@
+//! Bridge from the `ewmh` and `icccm` request traits to the unified [`Connection`]
+//!
+//! Each trait is implemented for every `ewmh` and every `icccm` request (or cookie). The marker
+//! parameter ([`Ewmh`] or [`Icccm`]) keeps the two blanket implementations apart. It is inferred
+//! from the request type, so callers never have to spell it out.
+
+use crate::backend::VoidCookieChecked;
+use crate::connection::Connection;
+use crate::ewmh::traits::{
+    EwmhPropertyCookieChecked, EwmhPropertyCookieUnchecked, EwmhPropertyRequestUnchecked,
+    EwmhRequest, EwmhVoidRequestChecked,
+};
+use crate::icccm::traits::{
+    IcccmPropertyCookieChecked, IcccmPropertyCookieUnchecked, IcccmPropertyRequestUnchecked,
+    IcccmRequest, IcccmVoidRequestChecked,
+};
+
+/// Marker for requests and cookies of the `ewmh` module
+pub struct Ewmh;
+
+/// Marker for requests and cookies of the `icccm` module
+pub struct Icccm;
+
+/// Requests that can be sent by [`Connection::send_request`]
+///
+/// Together with the cookie traits it allows helpers that work for requests of both protocols:
+///
+/// ```no_run
+/// use xcb_wm::{Connection, WmPropertyCookieChecked, WmRequest};
+///
+/// fn fetch<'a, R, M>(
+///     con: &Connection,
+///     request: &'a R,
+/// ) -> xcb_wm::Result<<R::Cookie as WmPropertyCookieChecked<M>>::Reply>
+/// where
+///     R: WmRequest<'a, M>,
+///     R::Cookie: WmPropertyCookieChecked<M>,
+/// {
+///     con.wait_for_reply(con.send_request(request))
+/// }
+/// ```
+pub trait WmRequest<'a, M> {
+    type Cookie;
+
+    fn send(&'a self, con: &Connection) -> Self::Cookie;
+}
+
+impl<'a, R: EwmhRequest<'a>> WmRequest<'a, Ewmh> for R {
+    type Cookie = R::EwmhCookie;
+
+    fn send(&'a self, con: &Connection) -> Self::Cookie {
+        EwmhRequest::send(self, con.ewmh())
+    }
+}
+
+impl<'a, R: IcccmRequest<'a>> WmRequest<'a, Icccm> for R {
+    type Cookie = R::IcccmCookie;
+
+    fn send(&'a self, con: &Connection) -> Self::Cookie {
+        IcccmRequest::send(self, con.icccm())
+    }
+}
+
+/// Requests that can be sent by [`Connection::send_request_checked`]
+pub trait WmVoidRequestChecked<'a, M> {
+    fn send(&'a self, con: &Connection) -> VoidCookieChecked;
+}
+
+impl<'a, R: EwmhVoidRequestChecked<'a>> WmVoidRequestChecked<'a, Ewmh> for R {
+    fn send(&'a self, con: &Connection) -> VoidCookieChecked {
+        EwmhVoidRequestChecked::send(self, con.ewmh())
+    }
+}
+
+impl<'a, R: IcccmVoidRequestChecked<'a>> WmVoidRequestChecked<'a, Icccm> for R {
+    fn send(&'a self, con: &Connection) -> VoidCookieChecked {
+        IcccmVoidRequestChecked::send(self, con.icccm())
+    }
+}
+
+/// Requests that can be sent by [`Connection::send_request_unchecked`]
+pub trait WmPropertyRequestUnchecked<M> {
+    type Cookie;
+
+    fn send(&self, con: &Connection) -> Self::Cookie;
+}
+
+impl<R: EwmhPropertyRequestUnchecked> WmPropertyRequestUnchecked<Ewmh> for R {
+    type Cookie = R::EwmhCookie;
+
+    fn send(&self, con: &Connection) -> Self::Cookie {
+        EwmhPropertyRequestUnchecked::send(self, con.ewmh())
+    }
+}
+
+impl<R: IcccmPropertyRequestUnchecked> WmPropertyRequestUnchecked<Icccm> for R {
+    type Cookie = R::IcccmCookie;
+
+    fn send(&self, con: &Connection) -> Self::Cookie {
+        IcccmPropertyRequestUnchecked::send(self, con.icccm())
+    }
+}
+
+/// Cookies that can be retrieved by [`Connection::wait_for_reply`]
+pub trait WmPropertyCookieChecked<M> {
+    type Reply;
+
+    fn wait(self, con: &Connection) -> crate::Result<Self::Reply>;
+}
+
+impl<C: EwmhPropertyCookieChecked> WmPropertyCookieChecked<Ewmh> for C {
+    type Reply = C::Reply;
+
+    fn wait(self, con: &Connection) -> crate::Result<Self::Reply> {
+        con.ewmh().wait_for_reply(self)
+    }
+}
+
+impl<C: IcccmPropertyCookieChecked> WmPropertyCookieChecked<Icccm> for C {
+    type Reply = C::Reply;
+
+    fn wait(self, con: &Connection) -> crate::Result<Self::Reply> {
+        con.icccm().wait_for_reply(self)
+    }
+}
+
+/// Cookies that can be retrieved by [`Connection::wait_for_reply_unchecked`]
+pub trait WmPropertyCookieUnchecked<M> {
+    type Reply;
+
+    fn wait(self, con: &Connection) -> crate::Result<Option<Self::Reply>>;
+}
+
+impl<C: EwmhPropertyCookieUnchecked> WmPropertyCookieUnchecked<Ewmh> for C {
+    type Reply = C::Reply;
+
+    fn wait(self, con: &Connection) -> crate::Result<Option<Self::Reply>> {
+        con.ewmh().wait_for_reply_unchecked(self)
+    }
+}
+
+impl<C: IcccmPropertyCookieUnchecked> WmPropertyCookieUnchecked<Icccm> for C {
+    type Reply = C::Reply;
+
+    fn wait(self, con: &Connection) -> crate::Result<Option<Self::Reply>> {
+        con.icccm().wait_for_reply_unchecked(self)
+    }
+}
@@ -0,0 +1,216 @@
+#[cfg(feature = "async")]
+use crate::backend::{PollBackend, PropertyCookie};
+use crate::error::{Error, Result};
+use crate::property::Property;
+
+/// Wait for the reply to `cookie` without blocking
+///
+/// Flushes the connection, then alternates between polling for the reply and awaiting
+/// `readable`, which resolves once the connection's file descriptor is readable.
+#[cfg(feature = "async")]
+pub(crate) async fn poll_for_reply<R, F>(
+    con: &dyn PollBackend,
+    cookie: &PropertyCookie,
+    mut readable: R,
+) -> Result<Property>
+where
+    R: FnMut() -> F,
+    F: std::future::Future<Output = std::io::Result<()>>,
+{
+    con.flush()?;
+
+    loop {
+        if let Some(reply) = con.poll_for_property(cookie) {
+            return reply;
+        }
+
+        readable().await?;
+    }
+}
+
+/// Treat a property that cannot be decoded as absent, but keep connection and protocol errors
+#[cfg(feature = "ewmh")]
+pub(crate) fn optional<T>(reply: Result<T>) -> Result<Option<T>> {
+    match reply {
+        Ok(reply) => Ok(Some(reply)),
+        Err(e @ Error::Xcb(_)) | Err(e @ Error::Backend(_)) => Err(e),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Checked access to the value of a [`Property`]
+///
+/// Fails if the property is missing, has a different type than requested, is not of the format
+/// of `P` or has less than `min_len` elements.
+pub(crate) fn property_value<P: xcb::x::PropEl>(reply: &Property, min_len: usize) -> Result<&[P]> {
+    if reply.r#type() == xcb::x::ATOM_NONE {
+        return Err(Error::PropertyMissing);
+    }
+
+    // On a type mismatch the server returns the actual type but no value
+    if reply.is_empty() && reply.bytes_after() > 0 {
+        return Err(Error::WrongType {
+            actual: reply.r#type(),
+        });
+    }
+
+    if reply.format() != P::FORMAT {
+        return Err(Error::WrongFormat {
+            expected: P::FORMAT,
+            actual: reply.format(),
+        });
+    }
+
+    let value = reply.value::<P>();
+
+    if value.len() < min_len {
+        return Err(Error::Truncated {
+            expected: min_len,
+            actual: value.len(),
+        });
+    }
+
+    Ok(value)
+}
+
+/// Split a NULL separated X buffer into its (non-empty) elements
+#[cfg(feature = "ewmh")]
+pub(crate) fn x_buffer_split(xbuf: &[u8]) -> Vec<&[u8]> {
+    xbuf.split(|b| *b == 0x00)
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+/// Split a list of NULL terminated elements, keeping empty elements
+///
+/// For lists like `_NET_DESKTOP_NAMES` or `WM_COMMAND`, where the position of an element matters
+/// and an empty element is a value of its own.
+pub(crate) fn x_buffer_split_list(xbuf: &[u8]) -> Vec<&[u8]> {
+    let xbuf = xbuf.strip_suffix(&[0x00]).unwrap_or(xbuf);
+    if xbuf.is_empty() {
+        return vec![];
+    }
+
+    xbuf.split(|b| *b == 0x00).collect()
+}
+
+#[cfg(feature = "ewmh")]
+pub(crate) fn x_buffer_to_strings(xbuf: &[u8]) -> Result<Vec<String>> {
+    x_buffer_split(xbuf)
+        .into_iter()
+        .map(|v| Ok(String::from_utf8(v.to_vec())?))
+        .collect()
+}
+
+/// Split a list of NULL terminated strings, keeping empty elements, see [`x_buffer_split_list`]
+#[cfg(feature = "ewmh")]
+pub(crate) fn x_buffer_to_string_list(xbuf: &[u8]) -> Result<Vec<String>> {
+    x_buffer_split_list(xbuf)
+        .into_iter()
+        .map(|v| Ok(String::from_utf8(v.to_vec())?))
+        .collect()
+}
+
+#[cfg(feature = "ewmh")]
+pub(crate) fn strings_to_x_buffer(strings: Vec<&str>) -> Vec<u8> {
+    let mut data = vec![];
+
+    // flatten `strings` into a continuous, NULL separated, array of bytes
+    for s in strings {
+        let mut bs = s.as_bytes().to_owned();
+        bs.push(0b00);
+        data.extend(bs)
+    }
+
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn property_value_checks() {
+        let cardinals = Property::format32(xcb::x::ATOM_CARDINAL, vec![1, 2, 3]);
+        assert_eq!(property_value::<u32>(&cardinals, 3).unwrap(), &[1, 2, 3]);
+
+        assert!(matches!(
+            property_value::<u32>(&Property::missing(), 0),
+            Err(Error::PropertyMissing)
+        ));
+        assert!(matches!(
+            property_value::<u8>(&cardinals, 0),
+            Err(Error::WrongFormat {
+                expected: 8,
+                actual: 32
+            })
+        ));
+        assert!(matches!(
+            property_value::<u32>(&cardinals, 4),
+            Err(Error::Truncated {
+                expected: 4,
+                actual: 3
+            })
+        ));
+
+        let mismatch = Property::format8(xcb::x::ATOM_STRING, vec![]).with_bytes_after(5);
+        assert!(matches!(
+            property_value::<u32>(&mismatch, 0),
+            Err(Error::WrongType { actual }) if actual == xcb::x::ATOM_STRING
+        ));
+
+        // An empty value of the requested type is not a mismatch
+        let empty = Property::format32(xcb::x::ATOM_CARDINAL, vec![]);
+        assert_eq!(property_value::<u32>(&empty, 0).unwrap(), &[] as &[u32]);
+    }
+
+    #[test]
+    #[cfg(feature = "ewmh")]
+    fn x_buffer_split_skips_empty_elements() {
+        assert_eq!(
+            x_buffer_split(b"\0a\0\0bc\0"),
+            vec![b"a".as_ref(), b"bc".as_ref()]
+        );
+        assert!(x_buffer_split(b"").is_empty());
+    }
+
+    #[test]
+    fn x_buffer_split_list_keeps_empty_elements() {
+        assert_eq!(
+            x_buffer_split_list(b"\0a\0\0bc\0"),
+            vec![b"".as_ref(), b"a", b"", b"bc"]
+        );
+        assert_eq!(x_buffer_split_list(b"\0\0"), vec![b"".as_ref(), b""]);
+        assert!(x_buffer_split_list(b"").is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "ewmh")]
+    fn strings_round_trip() {
+        let strings = vec!["one", "zwei", "три", ""];
+        let buffer = strings_to_x_buffer(strings.clone());
+
+        assert_eq!(buffer, b"one\0zwei\0\xd1\x82\xd1\x80\xd0\xb8\0\0");
+        // Empty strings do not survive the trip
+        assert_eq!(x_buffer_to_strings(&buffer).unwrap(), &strings[..3]);
+        // The trailing NULL is optional
+        assert_eq!(x_buffer_to_strings(b"a\0b").unwrap(), vec!["a", "b"]);
+        assert!(matches!(
+            x_buffer_to_strings(b"ok\0\xff\xfe\0"),
+            Err(Error::InvalidUtf8(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "ewmh")]
+    fn string_lists_keep_positions() {
+        let buffer = strings_to_x_buffer(vec!["one", "", "three"]);
+
+        assert_eq!(
+            x_buffer_to_string_list(&buffer).unwrap(),
+            vec!["one", "", "three"]
+        );
+        assert_eq!(x_buffer_to_string_list(b"a\0b").unwrap(), vec!["a", "b"]);
+        assert!(x_buffer_to_string_list(b"").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,187 @@
+//! Typed `PropertyNotify` handling
+//!
+//! [`Watcher::watch`] adds `PropertyChange` to the events selected on a window. The events
+//! themselves are still read from the event loop of the X connection, [`Watcher`] only maps them
+//! to a property of the protocol and, if asked to, fetches and decodes the new value.
+//!
+//! `ewmh::Watcher` and `icccm::Watcher` watch the properties of the respective protocol.
+
+use crate::backend::Backend;
+use crate::Result;
+
+/// Connection of a protocol whose properties a [`Watcher`] can watch
+pub trait WatchedConnection {
+    /// The properties of the protocol that can be watched
+    type Property: Copy;
+
+    /// Decoded new value of a changed property
+    type Event;
+
+    fn backend(&self) -> &dyn Backend;
+
+    fn atom_name(&self, atom: xcb::x::Atom) -> Result<String>;
+
+    /// The property called `name`, `None` if it is not one of the protocol
+    fn property_named(name: &str) -> Option<Self::Property>;
+
+    /// The event of a deleted property
+    fn deleted(property: Self::Property) -> Self::Event;
+
+    /// Fetch and decode `property` of `window`
+    fn fetch_property(
+        &self,
+        property: Self::Property,
+        window: xcb::x::Window,
+    ) -> Result<Self::Event>;
+}
+
+/// Define the property and event enums of a protocol and implement [`WatchedConnection`]
+///
+/// Each property is given with its name, the event variant and type of its decoded value, the
+/// request that fetches it from a window and how the value is taken from the reply.
+macro_rules! watched_properties {
+    (protocol=$protocol:literal, property=$prop_enum:ident, event=$event_enum:ident;
+     $($property:ident = $name:literal, $event:ident($value:ty),
+       |$window:ident| $request:expr, |$reply:ident| $decode:expr;)+) => {
+        #[doc = concat!("`", $protocol, "` properties that can be watched")]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub enum $prop_enum {
+            $($property,)+
+        }
+
+        impl $prop_enum {
+            pub fn name(self) -> &'static str {
+                match self {
+                    $($prop_enum::$property => $name,)+
+                }
+            }
+
+            pub fn from_name(name: &str) -> Option<$prop_enum> {
+                match name {
+                    $($name => Some($prop_enum::$property),)+
+                    _ => None,
+                }
+            }
+        }
+
+        #[doc = concat!("Decoded new value of a changed `", $protocol, "` property")]
+        #[derive(Debug)]
+        pub enum $event_enum {
+            $($event($value),)+
+
+            /// The property was deleted
+            Deleted($prop_enum),
+        }
+
+        impl crate::watch::WatchedConnection for Connection<'_> {
+            type Property = $prop_enum;
+            type Event = $event_enum;
+
+            fn backend(&self) -> &dyn crate::backend::Backend {
+                self.con
+            }
+
+            fn atom_name(&self, atom: xcb::x::Atom) -> Result<String> {
+                Connection::atom_name(self, atom)
+            }
+
+            fn property_named(name: &str) -> Option<$prop_enum> {
+                $prop_enum::from_name(name)
+            }
+
+            fn deleted(property: $prop_enum) -> $event_enum {
+                $event_enum::Deleted(property)
+            }
+
+            fn fetch_property(
+                &self,
+                property: $prop_enum,
+                window: xcb::x::Window,
+            ) -> Result<$event_enum> {
+                match property {
+                    $($prop_enum::$property => {
+                        let $window = window;
+                        let cookie = self.send_request(&$request);
+                        let $reply = self.wait_for_reply(cookie)?;
+                        Ok($event_enum::$event($decode))
+                    })+
+                }
+            }
+        }
+    };
+}
+
+/// Watches windows for changes of the properties of a protocol
+pub struct Watcher<'a, C> {
+    con: &'a C,
+}
+
+impl<'a, C: WatchedConnection> Watcher<'a, C> {
+    pub fn new(con: &'a C) -> Watcher<'a, C> {
+        Watcher { con }
+    }
+
+    /// Add `PropertyChange` to the events this X client selected on `window`
+    ///
+    /// The events selected before are kept, which takes a round-trip to read them.
+    pub fn watch(&self, window: xcb::x::Window) -> Result<()> {
+        let backend = self.con.backend();
+        let mask = backend.event_mask(window)?;
+
+        if mask.contains(xcb::x::EventMask::PROPERTY_CHANGE) {
+            return Ok(());
+        }
+        backend.select_events(window, mask | xcb::x::EventMask::PROPERTY_CHANGE)
+    }
+
+    /// The property `event` is about, `None` for a property of another protocol
+    pub fn property(&self, event: &xcb::x::PropertyNotifyEvent) -> Result<Option<C::Property>> {
+        Ok(C::property_named(&self.con.atom_name(event.atom())?))
+    }
+
+    /// Fetch and decode the new value of the property `event` is about
+    ///
+    /// `None` if `event` is about a property of another protocol.
+    pub fn fetch(&self, event: &xcb::x::PropertyNotifyEvent) -> Result<Option<C::Event>> {
+        let property = match self.property(event)? {
+            Some(property) => property,
+            None => return Ok(None),
+        };
+
+        if event.state() == xcb::x::Property::Delete {
+            return Ok(Some(C::deleted(property)));
+        }
+
+        self.con.fetch_property(property, event.window()).map(Some)
+    }
+}
+
+#[cfg(all(test, feature = "mock", feature = "ewmh"))]
+mod tests {
+    use crate::backend::Backend;
+    use crate::ewmh::{Connection, Watcher};
+    use crate::mock::PropertyStore;
+
+    #[test]
+    fn watch_keeps_selected_events() {
+        let store = PropertyStore::new();
+        let window = store.create_window();
+        store
+            .select_events(window, xcb::x::EventMask::STRUCTURE_NOTIFY)
+            .unwrap();
+        let ewmh_con = Connection::connect(&store);
+
+        Watcher::new(&ewmh_con).watch(window).unwrap();
+        assert_eq!(
+            store.selected_events(window),
+            xcb::x::EventMask::STRUCTURE_NOTIFY | xcb::x::EventMask::PROPERTY_CHANGE
+        );
+
+        Watcher::new(&ewmh_con).watch(store.root()).unwrap();
+        assert_eq!(
+            store.selected_events(store.root()),
+            xcb::x::EventMask::PROPERTY_CHANGE
+        );
+    }
+}
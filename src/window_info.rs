@@ -0,0 +1,88 @@
+//! Snapshot of the `ewmh` and `icccm` properties of a client window
+
+use crate::ewmh::proto::{
+    GetFrameExtents, GetFrameExtentsReply, GetWmDesktop, GetWmIcon, GetWmPid, GetWmState,
+    GetWmWindowType, WmIcon,
+};
+use crate::icccm::proto::{GetWmClass, GetWmHints, GetWmTransientFor, WmHints};
+use crate::util::optional;
+use crate::{ewmh, icccm, Connection, Result};
+
+/// Everything a window switcher or taskbar shows about a window
+///
+/// Properties that are not set or cannot be decoded are `None` (or empty). `title` prefers
+/// `_NET_WM_NAME` and falls back to `WM_NAME`.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowInfo {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::xid"))]
+    pub window: xcb::x::Window,
+    pub title: Option<String>,
+    pub instance: Option<String>,
+    pub class: Option<String>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::atoms"))]
+    pub window_types: Vec<xcb::x::Atom>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::atoms"))]
+    pub states: Vec<xcb::x::Atom>,
+    pub desktop: Option<u32>,
+    pub pid: Option<u32>,
+    pub icons: Vec<WmIcon>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::optional_xid"))]
+    pub transient_for: Option<xcb::x::Window>,
+    pub hints: Option<WmHints>,
+    pub frame_extents: Option<GetFrameExtentsReply>,
+}
+
+impl WindowInfo {
+    /// Fetch all properties of `window` in one round-trip
+    ///
+    /// Only connection and protocol errors (e.g. `window` does not exist) fail the fetch.
+    pub fn fetch(con: &Connection, window: xcb::x::Window) -> Result<WindowInfo> {
+        let (
+            net_wm_name,
+            wm_name,
+            class,
+            window_types,
+            states,
+            desktop,
+            pid,
+            icons,
+            transient_for,
+            hints,
+            frame_extents,
+        ) = con.wait_for_batch(con.send_batch(&(
+            ewmh::proto::GetWmName(window),
+            icccm::proto::GetWmName::new(window),
+            GetWmClass::new(window),
+            GetWmWindowType(window),
+            GetWmState(window),
+            GetWmDesktop(window),
+            GetWmPid(window),
+            GetWmIcon(window),
+            GetWmTransientFor::new(window),
+            GetWmHints::new(window),
+            GetFrameExtents(window),
+        )));
+
+        let title = optional(net_wm_name)?
+            .map(|r| r.name)
+            .filter(|name| !name.is_empty())
+            .or(optional(wm_name)?.map(|r| r.name));
+        let class = optional(class)?;
+
+        Ok(WindowInfo {
+            window,
+            title,
+            instance: class.as_ref().map(|r| r.instance.clone()),
+            class: class.map(|r| r.class),
+            window_types: optional(window_types)?.map_or(vec![], |r| r.window_types),
+            states: optional(states)?.map_or(vec![], |r| r.states),
+            desktop: optional(desktop)?.map(|r| r.desktop),
+            pid: optional(pid)?.map(|r| r.pid),
+            icons: optional(icons)?.map_or(vec![], |r| r.icons),
+            transient_for: optional(transient_for)?.map(|r| r.window),
+            hints: optional(hints)?.map(|r| r.size_hints),
+            frame_extents: optional(frame_extents)?,
+        })
+    }
+}
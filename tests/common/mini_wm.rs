@@ -0,0 +1,469 @@
+//! A tiny EWMH window manager that answers the client messages of the tests
+//!
+//! It runs in a thread with its own connection, so `Send*` requests from the test connection
+//! round-trip through a real WM. Properties with a `Set*` request in the crate are written with
+//! it, WM-only root properties with a plain `ChangeProperty`.
+
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use xcb::{Xid, XidNew};
+use xcb_wm::{ewmh, icccm};
+
+use super::{create_window, Xvfb};
+
+pub const SCREEN_SIZE: (u32, u32) = (1280, 800);
+pub const DESKTOP_NAMES: [&str; 2] = ["one", "two"];
+/// Left, right, top and bottom border the WM puts around every window
+pub const FRAME_EXTENTS: (u32, u32, u32, u32) = (1, 2, 3, 4);
+pub const ICON_SIZE: (u32, u32) = (16, 64);
+
+const WM_NAME: &str = "mini-wm";
+const QUIT: &str = "_XCB_WM_TEST_QUIT";
+
+const SUPPORTED: [&str; 19] = [
+    "_NET_SUPPORTED",
+    "_NET_SUPPORTING_WM_CHECK",
+    "_NET_CLIENT_LIST",
+    "_NET_CLIENT_LIST_STACKING",
+    "_NET_NUMBER_OF_DESKTOPS",
+    "_NET_DESKTOP_GEOMETRY",
+    "_NET_DESKTOP_VIEWPORT",
+    "_NET_CURRENT_DESKTOP",
+    "_NET_DESKTOP_NAMES",
+    "_NET_ACTIVE_WINDOW",
+    "_NET_WORKAREA",
+    "_NET_SHOWING_DESKTOP",
+    "_NET_CLOSE_WINDOW",
+    "_NET_REQUEST_FRAME_EXTENTS",
+    "_NET_WM_DESKTOP",
+    "_NET_WM_STATE",
+    "_NET_WM_MOVERESIZE",
+    "_NET_WM_ALLOWED_ACTIONS",
+    "_NET_FRAME_EXTENTS",
+];
+
+/// `_NET_WM_ALLOWED_ACTIONS` of every managed window
+pub const ALLOWED_ACTIONS: [&str; 4] = [
+    "_NET_WM_ACTION_MOVE",
+    "_NET_WM_ACTION_MINIMIZE",
+    "_NET_WM_ACTION_CHANGE_DESKTOP",
+    "_NET_WM_ACTION_CLOSE",
+];
+
+/// Handle to the running window manager, stops it on drop
+pub struct MiniWm {
+    display: String,
+    check_window: xcb::x::Window,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MiniWm {
+    /// Start managing the root window of `xvfb` and wait until all root properties are set
+    pub fn start(xvfb: &Xvfb) -> MiniWm {
+        let display = xvfb.display().to_owned();
+        let (ready, started) = mpsc::channel();
+
+        let thread = thread::spawn({
+            let display = display.clone();
+            move || {
+                let (con, _) = xcb::Connection::connect(Some(&display)).unwrap();
+                let mut wm = Wm::manage(&con);
+                ready.send(wm.check_window).unwrap();
+                wm.run();
+            }
+        });
+
+        let check_window = started
+            .recv_timeout(Duration::from_secs(10))
+            .expect("mini WM did not start");
+
+        MiniWm {
+            display,
+            check_window,
+            thread: Some(thread),
+        }
+    }
+
+    /// The `_NET_SUPPORTING_WM_CHECK` window
+    pub fn check_window(&self) -> xcb::x::Window {
+        self.check_window
+    }
+
+    pub fn name(&self) -> &'static str {
+        WM_NAME
+    }
+}
+
+impl Drop for MiniWm {
+    fn drop(&mut self) {
+        // Without an event mask the message goes to the client that created the check window
+        if let Ok((con, _)) = xcb::Connection::connect(Some(&self.display)) {
+            let quit = con.wait_for_reply(con.send_request(&xcb::x::InternAtom {
+                only_if_exists: false,
+                name: QUIT.as_bytes(),
+            }));
+
+            if let Ok(quit) = quit {
+                let event = xcb::x::ClientMessageEvent::new(
+                    self.check_window,
+                    quit.atom(),
+                    xcb::x::ClientMessageData::Data32([0; 5]),
+                );
+                let _ = con.send_and_check_request(&xcb::x::SendEvent {
+                    propagate: false,
+                    destination: xcb::x::SendEventDest::Window(self.check_window),
+                    event_mask: xcb::x::EventMask::NO_EVENT,
+                    event: &event,
+                });
+            }
+        }
+
+        if let Some(thread) = self.thread.take() {
+            let result = thread.join();
+            if !thread::panicking() {
+                result.expect("mini WM panicked");
+            }
+        }
+    }
+}
+
+struct Wm<'a> {
+    xcb: &'a xcb::Connection,
+    ewmh: ewmh::Connection<'a>,
+    icccm: icccm::Connection<'a>,
+    check_window: xcb::x::Window,
+    clients: Vec<xcb::x::Window>,
+}
+
+impl<'a> Wm<'a> {
+    fn manage(xcb: &'a xcb::Connection) -> Wm<'a> {
+        let ewmh = ewmh::Connection::connect(xcb);
+        let icccm = icccm::Connection::connect(xcb);
+        let root = ewmh.root();
+
+        xcb.send_and_check_request(&xcb::x::ChangeWindowAttributes {
+            window: root,
+            value_list: &[xcb::x::Cw::EventMask(
+                xcb::x::EventMask::SUBSTRUCTURE_REDIRECT | xcb::x::EventMask::SUBSTRUCTURE_NOTIFY,
+            )],
+        })
+        .expect("another window manager is running");
+
+        let check_window = create_window(xcb);
+        let wm = Wm {
+            xcb,
+            ewmh,
+            icccm,
+            check_window,
+            clients: Vec::new(),
+        };
+
+        wm.set(
+            root,
+            "_NET_SUPPORTING_WM_CHECK",
+            xcb::x::ATOM_WINDOW,
+            &[check_window],
+        );
+        wm.set(
+            check_window,
+            "_NET_SUPPORTING_WM_CHECK",
+            xcb::x::ATOM_WINDOW,
+            &[check_window],
+        );
+        wm.ewmh
+            .send_and_check_request(&ewmh::proto::SetWmName::new(check_window, WM_NAME))
+            .unwrap();
+
+        let supported: Vec<xcb::x::Atom> = SUPPORTED
+            .iter()
+            .map(|name| wm.ewmh.atom(name).unwrap())
+            .collect();
+        wm.set(root, "_NET_SUPPORTED", xcb::x::ATOM_ATOM, &supported);
+
+        let (width, height) = SCREEN_SIZE;
+        wm.set_cardinals("_NET_NUMBER_OF_DESKTOPS", &[DESKTOP_NAMES.len() as u32]);
+        wm.set_cardinals("_NET_CURRENT_DESKTOP", &[0]);
+        wm.set_cardinals("_NET_DESKTOP_GEOMETRY", &[width, height]);
+        wm.set_cardinals("_NET_DESKTOP_VIEWPORT", &[0, 0]);
+        wm.set_cardinals("_NET_WORKAREA", &[0, 0, width, height]);
+        wm.set_cardinals("_NET_SHOWING_DESKTOP", &[0]);
+        wm.set_cardinals(
+            "_NET_DESKTOP_LAYOUT",
+            &[0, DESKTOP_NAMES.len() as u32, 1, 0],
+        );
+        wm.ewmh
+            .send_and_check_request(&ewmh::proto::SetDesktopNames::new(DESKTOP_NAMES.to_vec()))
+            .unwrap();
+        wm.update_client_list();
+
+        let (min, max) = ICON_SIZE;
+        let sizes = [icccm::proto::IconSize::new(
+            (min, min),
+            (max, max),
+            (min, min),
+        )];
+        wm.icccm
+            .send_and_check_request(&icccm::proto::SetWmIconSize::new(&sizes))
+            .unwrap();
+
+        wm
+    }
+
+    fn run(&mut self) {
+        loop {
+            // The server is gone
+            let event = match self.xcb.wait_for_event() {
+                Ok(event) => event,
+                Err(_) => return,
+            };
+
+            match event {
+                xcb::Event::X(xcb::x::Event::MapRequest(ev)) => self.map(ev.window()),
+                xcb::Event::X(xcb::x::Event::DestroyNotify(ev)) => self.forget(ev.window()),
+                xcb::Event::X(xcb::x::Event::ClientMessage(ev)) if self.is_quit(&ev) => return,
+                xcb::Event::X(xcb::x::Event::ClientMessage(ev)) => self.client_message(&ev),
+                _ => {}
+            }
+        }
+    }
+
+    fn map(&mut self, window: xcb::x::Window) {
+        self.xcb
+            .send_and_check_request(&xcb::x::MapWindow { window })
+            .unwrap();
+
+        let desktop = self
+            .ewmh
+            .wait_for_optional_reply(self.ewmh.send_request(&ewmh::proto::GetWmDesktop(window)))
+            .unwrap();
+        if desktop.is_none() {
+            let current = self.current_desktop();
+            self.ewmh
+                .send_and_check_request(&ewmh::proto::SetWmDesktop::new(window, current))
+                .unwrap();
+        }
+
+        self.set_frame_extents(window);
+
+        let actions: Vec<_> = ALLOWED_ACTIONS
+            .iter()
+            .map(|action| self.ewmh.atom(action).unwrap())
+            .collect();
+        self.set(
+            window,
+            "_NET_WM_ALLOWED_ACTIONS",
+            xcb::x::ATOM_ATOM,
+            &actions,
+        );
+
+        if !self.clients.contains(&window) {
+            self.clients.push(window);
+            self.update_client_list();
+        }
+    }
+
+    fn forget(&mut self, window: xcb::x::Window) {
+        self.clients.retain(|client| *client != window);
+        self.update_client_list();
+
+        let active = self
+            .ewmh
+            .wait_for_optional_reply(self.ewmh.send_request(&ewmh::proto::GetActiveWindow))
+            .unwrap();
+        if active.is_some_and(|active| active.window == window) {
+            self.set(
+                self.ewmh.root(),
+                "_NET_ACTIVE_WINDOW",
+                xcb::x::ATOM_WINDOW,
+                &[xcb::x::Window::none()],
+            );
+        }
+    }
+
+    /// The message [`MiniWm`] sends on drop
+    fn is_quit(&self, ev: &xcb::x::ClientMessageEvent) -> bool {
+        self.ewmh.atom_name(ev.r#type()).unwrap() == QUIT
+    }
+
+    fn client_message(&mut self, ev: &xcb::x::ClientMessageEvent) {
+        let data = match ev.data() {
+            xcb::x::ClientMessageData::Data32(data) => data,
+            _ => return,
+        };
+        let window = ev.window();
+
+        match self.ewmh.atom_name(ev.r#type()).unwrap().as_str() {
+            "_NET_NUMBER_OF_DESKTOPS" => self.set_cardinals("_NET_NUMBER_OF_DESKTOPS", &data[..1]),
+            "_NET_CURRENT_DESKTOP" => self.set_cardinals("_NET_CURRENT_DESKTOP", &data[..1]),
+            "_NET_DESKTOP_GEOMETRY" => self.set_cardinals("_NET_DESKTOP_GEOMETRY", &data[..2]),
+            "_NET_DESKTOP_VIEWPORT" => self.set_cardinals("_NET_DESKTOP_VIEWPORT", &data[..2]),
+            "_NET_SHOWING_DESKTOP" => self.set_cardinals("_NET_SHOWING_DESKTOP", &data[..1]),
+            "_NET_ACTIVE_WINDOW" => self.set(
+                self.ewmh.root(),
+                "_NET_ACTIVE_WINDOW",
+                xcb::x::ATOM_WINDOW,
+                &[window],
+            ),
+            "_NET_CLOSE_WINDOW" => self
+                .xcb
+                .send_and_check_request(&xcb::x::DestroyWindow { window })
+                .unwrap(),
+            "_NET_WM_DESKTOP" => self
+                .ewmh
+                .send_and_check_request(&ewmh::proto::SetWmDesktop::new(window, data[0]))
+                .unwrap(),
+            "_NET_WM_STATE" => self.change_state(window, data),
+            "WM_CHANGE_STATE" => self.iconify(window, data),
+            "_NET_WM_MOVERESIZE" => self.interactive_move(window, data),
+            "_NET_MOVERESIZE_WINDOW" => self.moveresize(window, data),
+            "_NET_RESTACK_WINDOW" => {
+                let mut value_list = Vec::new();
+                if data[1] != 0 {
+                    value_list.push(xcb::x::ConfigWindow::Sibling(xcb::x::Window::new(data[1])));
+                }
+                value_list.push(xcb::x::ConfigWindow::StackMode(stack_mode(data[2])));
+                self.configure(window, &value_list);
+            }
+            "_NET_REQUEST_FRAME_EXTENTS" => self.set_frame_extents(window),
+            _ => {}
+        }
+    }
+
+    /// The frame the window manager would draw around `window`
+    fn set_frame_extents(&self, window: xcb::x::Window) {
+        let (left, right, top, bottom) = FRAME_EXTENTS;
+        self.ewmh
+            .send_and_check_request(&ewmh::proto::SetFrameExtents::new(
+                window, left, right, top, bottom,
+            ))
+            .unwrap()
+    }
+
+    /// Apply a `_NET_WM_STATE` message, `data[0]` is 0 (remove), 1 (add) or 2 (toggle)
+    fn change_state(&self, window: xcb::x::Window, data: [u32; 5]) {
+        let mut states = self
+            .ewmh
+            .wait_for_optional_reply(self.ewmh.send_request(&ewmh::proto::GetWmState(window)))
+            .unwrap()
+            .map_or_else(Vec::new, |reply| reply.states);
+
+        for state in data[1..3].iter().filter(|state| **state != 0) {
+            let state = xcb::x::Atom::new(*state);
+            let present = states.contains(&state);
+
+            match (data[0], present) {
+                (0, _) | (2, true) => states.retain(|s| *s != state),
+                (1, false) | (2, false) => states.push(state),
+                _ => {}
+            }
+        }
+
+        self.set(window, "_NET_WM_STATE", xcb::x::ATOM_ATOM, &states);
+    }
+
+    /// Apply a `WM_CHANGE_STATE` message by marking the window `_NET_WM_STATE_HIDDEN`
+    ///
+    /// The window stays mapped, only `IconicState` (3) is accepted.
+    fn iconify(&self, window: xcb::x::Window, data: [u32; 5]) {
+        if data[0] != 3 {
+            return;
+        }
+
+        let hidden = self.ewmh.atom("_NET_WM_STATE_HIDDEN").unwrap();
+        self.change_state(window, [1, hidden.resource_id(), 0, 0, 0]);
+    }
+
+    /// Apply a `_NET_WM_MOVERESIZE` move right away instead of grabbing the pointer
+    ///
+    /// The window ends up at the pointer position, resizing is ignored.
+    fn interactive_move(&self, window: xcb::x::Window, data: [u32; 5]) {
+        // `_NET_WM_MOVERESIZE_MOVE` and `_NET_WM_MOVERESIZE_MOVE_KEYBOARD`
+        if data[2] != 8 && data[2] != 10 {
+            return;
+        }
+
+        self.configure(
+            window,
+            &[
+                xcb::x::ConfigWindow::X(data[0] as i32),
+                xcb::x::ConfigWindow::Y(data[1] as i32),
+            ],
+        );
+    }
+
+    /// Apply a `_NET_MOVERESIZE_WINDOW` message, gravity is ignored
+    fn moveresize(&self, window: xcb::x::Window, data: [u32; 5]) {
+        let fields = [
+            xcb::x::ConfigWindow::X(data[1] as i32),
+            xcb::x::ConfigWindow::Y(data[2] as i32),
+            xcb::x::ConfigWindow::Width(data[3]),
+            xcb::x::ConfigWindow::Height(data[4]),
+        ];
+        let value_list: Vec<_> = fields
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| data[0] & 1 << (8 + bit) != 0)
+            .map(|(_, field)| field.clone())
+            .collect();
+
+        self.configure(window, &value_list);
+    }
+
+    fn configure(&self, window: xcb::x::Window, value_list: &[xcb::x::ConfigWindow]) {
+        self.xcb
+            .send_and_check_request(&xcb::x::ConfigureWindow { window, value_list })
+            .unwrap();
+    }
+
+    fn current_desktop(&self) -> u32 {
+        self.ewmh
+            .wait_for_reply(self.ewmh.send_request(&ewmh::proto::GetCurrentDesktop))
+            .unwrap()
+            .desktop
+    }
+
+    fn update_client_list(&self) {
+        let root = self.ewmh.root();
+        self.set(root, "_NET_CLIENT_LIST", xcb::x::ATOM_WINDOW, &self.clients);
+        self.set(
+            root,
+            "_NET_CLIENT_LIST_STACKING",
+            xcb::x::ATOM_WINDOW,
+            &self.clients,
+        );
+    }
+
+    fn set_cardinals(&self, property: &str, data: &[u32]) {
+        self.set(self.ewmh.root(), property, xcb::x::ATOM_CARDINAL, data);
+    }
+
+    fn set<P: xcb::x::PropEl>(
+        &self,
+        window: xcb::x::Window,
+        property: &str,
+        r#type: xcb::x::Atom,
+        data: &[P],
+    ) {
+        self.xcb
+            .send_and_check_request(&xcb::x::ChangeProperty {
+                mode: xcb::x::PropMode::Replace,
+                window,
+                property: self.ewmh.atom(property).unwrap(),
+                r#type,
+                data,
+            })
+            .unwrap();
+    }
+}
+
+fn stack_mode(detail: u32) -> xcb::x::StackMode {
+    match detail {
+        0 => xcb::x::StackMode::Above,
+        1 => xcb::x::StackMode::Below,
+        2 => xcb::x::StackMode::TopIf,
+        3 => xcb::x::StackMode::BottomIf,
+        _ => xcb::x::StackMode::Opposite,
+    }
+}
@@ -0,0 +1,143 @@
+//! Shared setup of the integration tests
+//!
+//! Every test runs against its own `Xvfb` server. If `Xvfb` is not installed, [`Xvfb::start`]
+//! returns `None` and the test returns early instead of failing.
+
+#![allow(dead_code)]
+
+pub mod mini_wm;
+
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+static NEXT_DISPLAY: AtomicU32 = AtomicU32::new(0);
+
+/// A headless X server that is shut down on drop
+pub struct Xvfb {
+    display: String,
+    server: Child,
+}
+
+impl Xvfb {
+    /// Start `Xvfb` on a free display, `None` if it is not installed
+    pub fn start() -> Option<Xvfb> {
+        loop {
+            let number = 99 + NEXT_DISPLAY.fetch_add(1, Ordering::SeqCst);
+            assert!(number < 1000, "no free display for Xvfb");
+
+            let lock = format!("/tmp/.X{}-lock", number);
+            let socket = format!("/tmp/.X11-unix/X{}", number);
+            if Path::new(&lock).exists() || Path::new(&socket).exists() {
+                continue;
+            }
+
+            let display = format!(":{}", number);
+            let size = format!("{}x{}x24", mini_wm::SCREEN_SIZE.0, mini_wm::SCREEN_SIZE.1);
+            let server = match Command::new("Xvfb")
+                .args([&display, "-screen", "0", &size, "-nolisten", "tcp"])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+            {
+                Ok(server) => server,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    eprintln!("Xvfb is not installed, skipping");
+                    return None;
+                }
+                Err(e) => panic!("cannot start Xvfb: {}", e),
+            };
+
+            let mut xvfb = Xvfb { display, server };
+            if xvfb.wait_until_ready() {
+                return Some(xvfb);
+            }
+            // Another server grabbed the display first, try the next one
+        }
+    }
+
+    fn wait_until_ready(&mut self) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(10);
+
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.server.try_wait() {
+                return false;
+            }
+            if xcb::Connection::connect(Some(&self.display)).is_ok() {
+                return true;
+            }
+            sleep(Duration::from_millis(20));
+        }
+
+        panic!("Xvfb on {} did not come up", self.display);
+    }
+
+    pub fn display(&self) -> &str {
+        &self.display
+    }
+
+    pub fn connect(&self) -> xcb::Connection {
+        xcb::Connection::connect(Some(&self.display))
+            .expect("cannot connect to Xvfb")
+            .0
+    }
+}
+
+impl Drop for Xvfb {
+    fn drop(&mut self) {
+        // SIGTERM lets the server remove its lock file and socket
+        let terminated = Command::new("kill")
+            .arg(self.server.id().to_string())
+            .status()
+            .is_ok_and(|status| status.success());
+
+        if !terminated {
+            let _ = self.server.kill();
+        }
+        let _ = self.server.wait();
+    }
+}
+
+/// Create an unmapped 100x100 top-level window
+pub fn create_window(con: &xcb::Connection) -> xcb::x::Window {
+    let screen = con.get_setup().roots().next().expect("no screen");
+    let window = con.generate_id();
+
+    con.send_and_check_request(&xcb::x::CreateWindow {
+        depth: xcb::x::COPY_FROM_PARENT as u8,
+        wid: window,
+        parent: screen.root(),
+        x: 0,
+        y: 0,
+        width: 100,
+        height: 100,
+        border_width: 0,
+        class: xcb::x::WindowClass::InputOutput,
+        visual: screen.root_visual(),
+        value_list: &[],
+    })
+    .expect("cannot create window");
+
+    window
+}
+
+/// Ask the window manager to map `window`
+pub fn map_window(con: &xcb::Connection, window: xcb::x::Window) {
+    con.send_and_check_request(&xcb::x::MapWindow { window })
+        .expect("cannot map window");
+}
+
+/// Retry `f` until it returns `Some`, the window manager answers asynchronously
+pub fn eventually<T>(what: &str, mut f: impl FnMut() -> Option<T>) -> T {
+    let deadline = Instant::now() + Duration::from_secs(5);
+
+    loop {
+        if let Some(value) = f() {
+            return value;
+        }
+        assert!(Instant::now() < deadline, "timed out waiting for {}", what);
+        sleep(Duration::from_millis(10));
+    }
+}
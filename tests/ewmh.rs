@@ -0,0 +1,695 @@
+//! `ewmh` requests against `Xvfb` and the reference WM in `common::mini_wm`
+
+mod common;
+
+use common::mini_wm::{MiniWm, ALLOWED_ACTIONS, DESKTOP_NAMES, FRAME_EXTENTS, SCREEN_SIZE};
+use common::{create_window, eventually, map_window, Xvfb};
+use xcb_wm::ewmh;
+
+fn set_utf8(xcb_con: &xcb::Connection, window: xcb::x::Window, property: &str, value: &str) {
+    let ewmh_con = ewmh::Connection::connect(xcb_con);
+    xcb_con
+        .send_and_check_request(&xcb::x::ChangeProperty {
+            mode: xcb::x::PropMode::Replace,
+            window,
+            property: ewmh_con.atom(property).unwrap(),
+            r#type: ewmh_con.atom("UTF8_STRING").unwrap(),
+            data: value.as_bytes(),
+        })
+        .unwrap();
+}
+
+#[test]
+fn root_properties_of_the_wm() {
+    let xvfb = match Xvfb::start() {
+        Some(xvfb) => xvfb,
+        None => return,
+    };
+    let wm = MiniWm::start(&xvfb);
+    let xcb_con = xvfb.connect();
+    let ewmh_con = ewmh::Connection::connect(&xcb_con);
+
+    let supported = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetSupported))
+        .unwrap();
+    assert!(supported
+        .atoms
+        .contains(&ewmh_con.atom("_NET_ACTIVE_WINDOW").unwrap()));
+
+    let check = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetSupportingWmCheck))
+        .unwrap();
+    assert_eq!(check.window, wm.check_window());
+
+    let name = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetWmName(check.window)))
+        .unwrap();
+    assert_eq!(name.name, wm.name());
+
+    let desktops = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetNumberOfDesktops))
+        .unwrap();
+    assert_eq!(desktops.desktops, DESKTOP_NAMES.len() as u32);
+
+    let current = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetCurrentDesktop))
+        .unwrap();
+    assert_eq!(current.desktop, 0);
+
+    let names = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetDesktopNames))
+        .unwrap();
+    assert_eq!(names.names, DESKTOP_NAMES);
+
+    let geometry = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetDesktopGeometry))
+        .unwrap();
+    assert_eq!((geometry.width, geometry.height), SCREEN_SIZE);
+
+    let viewport = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetDesktopViewport))
+        .unwrap();
+    assert_eq!((viewport.x, viewport.y), (0, 0));
+
+    let workarea = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetWorkarea))
+        .unwrap();
+    assert_eq!(
+        (workarea.x, workarea.y, workarea.width, workarea.height),
+        (0, 0, SCREEN_SIZE.0, SCREEN_SIZE.1)
+    );
+
+    let layout = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::DesktopLayout))
+        .unwrap();
+    assert_eq!(layout.columns, DESKTOP_NAMES.len() as u32);
+    assert_eq!(layout.rows, 1);
+
+    let showing = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetShowingDesktop))
+        .unwrap();
+    assert!(!showing.is_showing_desktop);
+
+    let clients = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetClientList))
+        .unwrap();
+    assert!(clients.clients.is_empty());
+
+    let stacking = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetClientListStacking))
+        .unwrap();
+    assert!(stacking.clients.is_empty());
+
+    // The mini WM does not use virtual roots
+    let virtual_roots = ewmh_con
+        .wait_for_optional_reply(ewmh_con.send_request(&ewmh::proto::GetVirtualRoots))
+        .unwrap();
+    assert!(virtual_roots.is_none());
+}
+
+#[test]
+fn set_desktop_names() {
+    let xvfb = match Xvfb::start() {
+        Some(xvfb) => xvfb,
+        None => return,
+    };
+    let xcb_con = xvfb.connect();
+    let ewmh_con = ewmh::Connection::connect(&xcb_con);
+
+    ewmh_con
+        .send_and_check_request(&ewmh::proto::SetDesktopNames::new(vec!["mail", "web"]))
+        .unwrap();
+
+    let names = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetDesktopNames))
+        .unwrap();
+    assert_eq!(names.names, vec!["mail", "web"]);
+}
+
+#[test]
+fn root_client_messages() {
+    let xvfb = match Xvfb::start() {
+        Some(xvfb) => xvfb,
+        None => return,
+    };
+    let _wm = MiniWm::start(&xvfb);
+    let xcb_con = xvfb.connect();
+    let ewmh_con = ewmh::Connection::connect(&xcb_con);
+
+    ewmh_con
+        .send_and_check_request(&ewmh::proto::SendNumberOfDesktops::new(&ewmh_con, 4).unwrap())
+        .unwrap();
+    eventually("_NET_NUMBER_OF_DESKTOPS", || {
+        let reply = ewmh_con
+            .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetNumberOfDesktops))
+            .unwrap();
+        Some(()).filter(|_| reply.desktops == 4)
+    });
+
+    ewmh_con
+        .send_and_check_request(&ewmh::proto::SendCurrentDesktop::new(&ewmh_con, 1).unwrap())
+        .unwrap();
+    eventually("_NET_CURRENT_DESKTOP", || {
+        let reply = ewmh_con
+            .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetCurrentDesktop))
+            .unwrap();
+        Some(()).filter(|_| reply.desktop == 1)
+    });
+
+    ewmh_con
+        .send_and_check_request(
+            &ewmh::proto::SendDesktopGeometry::new(&ewmh_con, 2 * SCREEN_SIZE.0, SCREEN_SIZE.1)
+                .unwrap(),
+        )
+        .unwrap();
+    eventually("_NET_DESKTOP_GEOMETRY", || {
+        let reply = ewmh_con
+            .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetDesktopGeometry))
+            .unwrap();
+        Some(()).filter(|_| (reply.width, reply.height) == (2 * SCREEN_SIZE.0, SCREEN_SIZE.1))
+    });
+
+    ewmh_con
+        .send_and_check_request(
+            &ewmh::proto::SendDesktopViewport::new(&ewmh_con, SCREEN_SIZE.0, 0).unwrap(),
+        )
+        .unwrap();
+    eventually("_NET_DESKTOP_VIEWPORT", || {
+        let reply = ewmh_con
+            .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetDesktopViewport))
+            .unwrap();
+        Some(()).filter(|_| (reply.x, reply.y) == (SCREEN_SIZE.0, 0))
+    });
+
+    ewmh_con
+        .send_and_check_request(&ewmh::proto::SetShowingDesktop::new(&ewmh_con, true).unwrap())
+        .unwrap();
+    eventually("_NET_SHOWING_DESKTOP", || {
+        let reply = ewmh_con
+            .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetShowingDesktop))
+            .unwrap();
+        Some(()).filter(|_| reply.is_showing_desktop)
+    });
+}
+
+#[test]
+fn clients_activate_and_close() {
+    let xvfb = match Xvfb::start() {
+        Some(xvfb) => xvfb,
+        None => return,
+    };
+    let _wm = MiniWm::start(&xvfb);
+    let xcb_con = xvfb.connect();
+    let ewmh_con = ewmh::Connection::connect(&xcb_con);
+
+    let first = create_window(&xcb_con);
+    let second = create_window(&xcb_con);
+    map_window(&xcb_con, first);
+    map_window(&xcb_con, second);
+
+    eventually("_NET_CLIENT_LIST", || {
+        let reply = ewmh_con
+            .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetClientList))
+            .unwrap();
+        Some(()).filter(|_| reply.clients == vec![first, second])
+    });
+    let stacking = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetClientListStacking))
+        .unwrap();
+    assert_eq!(stacking.clients, vec![first, second]);
+
+    // The WM puts new windows on the current desktop
+    let desktop = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetWmDesktop(first)))
+        .unwrap();
+    assert_eq!(desktop.desktop, 0);
+
+    ewmh_con
+        .send_and_check_request(
+            &ewmh::proto::SendActiveWindow::new(&ewmh_con, second, 2, 0, None).unwrap(),
+        )
+        .unwrap();
+    eventually("_NET_ACTIVE_WINDOW", || {
+        let reply = ewmh_con
+            .wait_for_optional_reply(ewmh_con.send_request(&ewmh::proto::GetActiveWindow))
+            .unwrap();
+        Some(()).filter(|_| reply.map(|r| r.window) == Some(second))
+    });
+
+    ewmh_con
+        .send_and_check_request(
+            &ewmh::proto::SendCloseWindow::new(&ewmh_con, second, 2, 0).unwrap(),
+        )
+        .unwrap();
+    eventually("_NET_CLIENT_LIST without the closed window", || {
+        let reply = ewmh_con
+            .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetClientList))
+            .unwrap();
+        Some(()).filter(|_| reply.clients == vec![first])
+    });
+}
+
+#[test]
+fn window_client_messages() {
+    let xvfb = match Xvfb::start() {
+        Some(xvfb) => xvfb,
+        None => return,
+    };
+    let _wm = MiniWm::start(&xvfb);
+    let xcb_con = xvfb.connect();
+    let ewmh_con = ewmh::Connection::connect(&xcb_con);
+
+    // Frame extents are requested before the window is mapped
+    let window = create_window(&xcb_con);
+    ewmh_con
+        .send_and_check_request(
+            &ewmh::proto::SendRequestFrameExtents::new(&ewmh_con, window).unwrap(),
+        )
+        .unwrap();
+    let extents = eventually("_NET_FRAME_EXTENTS", || {
+        ewmh_con
+            .wait_for_optional_reply(ewmh_con.send_request(&ewmh::proto::GetFrameExtents(window)))
+            .unwrap()
+    });
+    assert_eq!(
+        (extents.left, extents.right, extents.top, extents.bottom),
+        FRAME_EXTENTS
+    );
+
+    map_window(&xcb_con, window);
+    eventually("the window to be managed", || {
+        ewmh_con
+            .wait_for_optional_reply(ewmh_con.send_request(&ewmh::proto::GetWmDesktop(window)))
+            .unwrap()
+    });
+
+    ewmh_con
+        .send_and_check_request(&ewmh::proto::SendWmDesktop::new(&ewmh_con, window, 1, 2).unwrap())
+        .unwrap();
+    eventually("_NET_WM_DESKTOP", || {
+        let reply = ewmh_con
+            .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetWmDesktop(window)))
+            .unwrap();
+        Some(()).filter(|_| reply.desktop == 1)
+    });
+
+    let fullscreen = ewmh_con.atom("_NET_WM_STATE_FULLSCREEN").unwrap();
+    let above = ewmh_con.atom("_NET_WM_STATE_ABOVE").unwrap();
+    let get_states = || {
+        ewmh_con
+            .wait_for_optional_reply(ewmh_con.send_request(&ewmh::proto::GetWmState(window)))
+            .unwrap()
+            .map_or_else(Vec::new, |r| r.states)
+    };
+
+    ewmh_con
+        .send_and_check_request(
+            &ewmh::proto::SendWmState::new(
+                &ewmh_con,
+                window,
+                ewmh::proto::WmStateAction::Add,
+                [fullscreen, above],
+                2,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+    eventually("_NET_WM_STATE after adding", || {
+        Some(()).filter(|_| get_states() == vec![fullscreen, above])
+    });
+
+    ewmh_con
+        .send_and_check_request(
+            &ewmh::proto::SendWmState::new(
+                &ewmh_con,
+                window,
+                ewmh::proto::WmStateAction::Toggle,
+                [fullscreen, xcb::x::ATOM_NONE],
+                2,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+    eventually("_NET_WM_STATE after toggling", || {
+        Some(()).filter(|_| get_states() == vec![above])
+    });
+
+    ewmh_con
+        .send_and_check_request(
+            &ewmh::proto::SendWmState::new(
+                &ewmh_con,
+                window,
+                ewmh::proto::WmStateAction::Remove,
+                [above, xcb::x::ATOM_NONE],
+                2,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+    eventually("_NET_WM_STATE after removing", || {
+        Some(()).filter(|_| get_states().is_empty())
+    });
+}
+
+#[test]
+fn moveresize_and_restack() {
+    let xvfb = match Xvfb::start() {
+        Some(xvfb) => xvfb,
+        None => return,
+    };
+    let _wm = MiniWm::start(&xvfb);
+    let xcb_con = xvfb.connect();
+    let ewmh_con = ewmh::Connection::connect(&xcb_con);
+
+    let lower = create_window(&xcb_con);
+    let window = create_window(&xcb_con);
+    for w in [lower, window] {
+        map_window(&xcb_con, w);
+    }
+
+    // Only `x` and `width` are set, `y` and `height` stay as created
+    ewmh_con
+        .send_and_check_request(
+            &ewmh::proto::SendMoveresizeWindow::new(
+                &ewmh_con,
+                window,
+                xcb::x::Gravity::NorthWest,
+                2,
+                Some(30),
+                None,
+                Some(200),
+                None,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+    let geometry = eventually("the window to be moved", || {
+        let geometry = xcb_con
+            .wait_for_reply(xcb_con.send_request(&xcb::x::GetGeometry {
+                drawable: xcb::x::Drawable::Window(window),
+            }))
+            .unwrap();
+        Some(geometry).filter(|g| g.x() == 30)
+    });
+    assert_eq!(
+        (geometry.y(), geometry.width(), geometry.height()),
+        (0, 200, 100)
+    );
+
+    let stacking = || {
+        let tree = xcb_con
+            .wait_for_reply(xcb_con.send_request(&xcb::x::QueryTree {
+                window: ewmh_con.root(),
+            }))
+            .unwrap();
+        let children = tree.children();
+        let position = |w| children.iter().position(|c| *c == w).unwrap();
+        position(window) < position(lower)
+    };
+    assert!(!stacking());
+
+    ewmh_con
+        .send_and_check_request(
+            &ewmh::proto::SendRestackWindow::new(
+                &ewmh_con,
+                window,
+                2,
+                Some(lower),
+                xcb::x::StackMode::Below,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+    eventually("the window to be restacked", || {
+        Some(()).filter(|_| stacking())
+    });
+}
+
+#[test]
+fn minimize_move_and_allowed_actions() {
+    let xvfb = match Xvfb::start() {
+        Some(xvfb) => xvfb,
+        None => return,
+    };
+    let _wm = MiniWm::start(&xvfb);
+    let xcb_con = xvfb.connect();
+    let ewmh_con = ewmh::Connection::connect(&xcb_con);
+
+    let window = create_window(&xcb_con);
+    map_window(&xcb_con, window);
+
+    let actions = eventually("_NET_WM_ALLOWED_ACTIONS", || {
+        ewmh_con
+            .wait_for_optional_reply(
+                ewmh_con.send_request(&ewmh::proto::GetWmAllowedActions(window)),
+            )
+            .unwrap()
+    });
+    let expected: Vec<_> = ALLOWED_ACTIONS
+        .iter()
+        .map(|action| ewmh_con.atom(action).unwrap())
+        .collect();
+    assert_eq!(actions.actions, expected);
+
+    ewmh_con
+        .send_and_check_request(&ewmh::proto::SendWmChangeState::new(&ewmh_con, window).unwrap())
+        .unwrap();
+    let hidden = ewmh_con.atom("_NET_WM_STATE_HIDDEN").unwrap();
+    eventually("_NET_WM_STATE_HIDDEN", || {
+        let reply = ewmh_con
+            .wait_for_optional_reply(ewmh_con.send_request(&ewmh::proto::GetWmState(window)))
+            .unwrap();
+        Some(()).filter(|_| reply.is_some_and(|r| r.states == vec![hidden]))
+    });
+
+    ewmh_con
+        .send_and_check_request(
+            &ewmh::proto::SendWmMoveresize::new(
+                &ewmh_con,
+                window,
+                40,
+                50,
+                ewmh::proto::WmMoveresizeDirection::Move,
+                1,
+                2,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+    eventually("the window to be moved", || {
+        let geometry = xcb_con
+            .wait_for_reply(xcb_con.send_request(&xcb::x::GetGeometry {
+                drawable: xcb::x::Drawable::Window(window),
+            }))
+            .unwrap();
+        Some(()).filter(|_| (geometry.x(), geometry.y()) == (40, 50))
+    });
+}
+
+#[test]
+fn window_handle_actions() {
+    let xvfb = match Xvfb::start() {
+        Some(xvfb) => xvfb,
+        None => return,
+    };
+    let _wm = MiniWm::start(&xvfb);
+    let xcb_con = xvfb.connect();
+    let ewmh_con = ewmh::Connection::connect(&xcb_con);
+
+    let window = create_window(&xcb_con);
+    map_window(&xcb_con, window);
+    let handle = ewmh::WindowHandle::new(&ewmh_con, window).as_pager();
+
+    handle.activate().unwrap();
+    eventually("_NET_ACTIVE_WINDOW", || {
+        let reply = ewmh_con
+            .wait_for_optional_reply(ewmh_con.send_request(&ewmh::proto::GetActiveWindow))
+            .unwrap();
+        Some(()).filter(|_| reply.map(|r| r.window) == Some(window))
+    });
+
+    let fullscreen = ewmh_con.atom("_NET_WM_STATE_FULLSCREEN").unwrap();
+    handle.set_fullscreen(true).unwrap();
+    eventually("_NET_WM_STATE_FULLSCREEN", || {
+        let reply = ewmh_con
+            .wait_for_optional_reply(ewmh_con.send_request(&ewmh::proto::GetWmState(window)))
+            .unwrap();
+        Some(()).filter(|_| reply.is_some_and(|r| r.states == vec![fullscreen]))
+    });
+
+    handle.move_to_desktop(1).unwrap();
+    eventually("_NET_WM_DESKTOP", || {
+        let reply = ewmh_con
+            .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetWmDesktop(window)))
+            .unwrap();
+        Some(()).filter(|_| reply.desktop == 1)
+    });
+
+    handle.close().unwrap();
+    eventually("_NET_CLIENT_LIST without the closed window", || {
+        let reply = ewmh_con
+            .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetClientList))
+            .unwrap();
+        Some(()).filter(|_| reply.clients.is_empty())
+    });
+}
+
+#[test]
+fn manage_desktops() {
+    let xvfb = match Xvfb::start() {
+        Some(xvfb) => xvfb,
+        None => return,
+    };
+    let _wm = MiniWm::start(&xvfb);
+    let xcb_con = xvfb.connect();
+    let ewmh_con = ewmh::Connection::connect(&xcb_con);
+
+    let mut desktops = ewmh::Desktops::load(&ewmh_con).unwrap();
+    assert_eq!(desktops.count(), DESKTOP_NAMES.len() as u32);
+    assert_eq!(desktops.names(), DESKTOP_NAMES);
+    assert_eq!(desktops.current(), Some(0));
+
+    desktops.add("three").unwrap();
+    desktops.switch_to(2).unwrap();
+    eventually("the new desktop to be current", || {
+        desktops.reload().unwrap();
+        Some(()).filter(|_| desktops.current() == Some(2))
+    });
+    assert_eq!(desktops.names(), ["one", "two", "three"]);
+
+    desktops.rename(0, "first").unwrap();
+    desktops.switch_to(0).unwrap();
+    desktops.remove().unwrap();
+    eventually("the desktop to be removed", || {
+        desktops.reload().unwrap();
+        Some(()).filter(|_| desktops.count() == 2)
+    });
+    assert_eq!(desktops.names(), ["first", "two"]);
+}
+
+#[test]
+fn compliance_of_the_mini_wm() {
+    let xvfb = match Xvfb::start() {
+        Some(xvfb) => xvfb,
+        None => return,
+    };
+    let _wm = MiniWm::start(&xvfb);
+    let xcb_con = xvfb.connect();
+    let ewmh_con = ewmh::Connection::connect(&xcb_con);
+
+    let window = create_window(&xcb_con);
+    map_window(&xcb_con, window);
+
+    let report = ewmh::compliance::Checker::new(&ewmh_con)
+        .check(Some(window))
+        .unwrap();
+    assert!(report.passed(), "{}", report);
+    assert_eq!(
+        report
+            .check("_NET_ACTIVE_WINDOW", "activates the test window")
+            .unwrap()
+            .outcome,
+        ewmh::compliance::Outcome::Pass
+    );
+}
+
+#[test]
+fn application_properties() {
+    let xvfb = match Xvfb::start() {
+        Some(xvfb) => xvfb,
+        None => return,
+    };
+    let xcb_con = xvfb.connect();
+    let ewmh_con = ewmh::Connection::connect(&xcb_con);
+    let window = create_window(&xcb_con);
+
+    ewmh_con
+        .send_and_check_request(&ewmh::proto::SetWmName::new(window, "Grüße"))
+        .unwrap();
+    let name = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetWmName(window)))
+        .unwrap();
+    assert_eq!(name.name, "Grüße");
+
+    // Visible and icon names are set by the WM, there are no `Set*` requests for them
+    set_utf8(&xcb_con, window, "_NET_WM_VISIBLE_NAME", "Grüße <2>");
+    set_utf8(&xcb_con, window, "_NET_WM_ICON_NAME", "grüße");
+    set_utf8(&xcb_con, window, "_NET_WM_VISIBLE_ICON_NAME", "grüße <2>");
+
+    let visible_name = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetWmVisibleName(window)))
+        .unwrap();
+    assert_eq!(visible_name.name, "Grüße <2>");
+
+    let icon_name = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetWmIconName(window)))
+        .unwrap();
+    assert_eq!(icon_name.name, "grüße");
+
+    let visible_icon_name = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetWmVisibleIconName(window)))
+        .unwrap();
+    assert_eq!(visible_icon_name.name, "grüße <2>");
+
+    ewmh_con
+        .send_and_check_request(&ewmh::proto::SetWmDesktop::new(window, 1))
+        .unwrap();
+    let desktop = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetWmDesktop(window)))
+        .unwrap();
+    assert_eq!(desktop.desktop, 1);
+
+    let dialog = ewmh_con.atom("_NET_WM_WINDOW_TYPE_DIALOG").unwrap();
+    ewmh_con
+        .send_and_check_request(&ewmh::proto::SetWmWindowType::new(window, vec![dialog]))
+        .unwrap();
+    let window_type = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetWmWindowType(window)))
+        .unwrap();
+    assert_eq!(window_type.window_types, vec![dialog]);
+
+    let icons = vec![
+        ewmh::proto::WmIcon {
+            width: 2,
+            height: 1,
+            data: vec![0xff00_0000, 0xffff_ffff],
+        },
+        ewmh::proto::WmIcon {
+            width: 1,
+            height: 1,
+            data: vec![0x8000_ff00],
+        },
+    ];
+    ewmh_con
+        .send_and_check_request(&ewmh::proto::SetWmIcon::new(window, &icons))
+        .unwrap();
+    let icon = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetWmIcon(window)))
+        .unwrap();
+    assert_eq!(icon.icons, icons);
+
+    ewmh_con
+        .send_and_check_request(&ewmh::proto::SetWmPid::new(window, std::process::id()))
+        .unwrap();
+    let pid = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetWmPid(window)))
+        .unwrap();
+    assert_eq!(pid.pid, std::process::id());
+
+    ewmh_con
+        .send_and_check_request(&ewmh::proto::SetFrameExtents::new(window, 5, 6, 7, 8))
+        .unwrap();
+    let extents = ewmh_con
+        .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetFrameExtents(window)))
+        .unwrap();
+    assert_eq!(
+        (extents.left, extents.right, extents.top, extents.bottom),
+        (5, 6, 7, 8)
+    );
+
+    // A window without the property
+    let state = ewmh_con
+        .wait_for_optional_reply(ewmh_con.send_request(&ewmh::proto::GetWmState(window)))
+        .unwrap();
+    assert!(state.is_none());
+}
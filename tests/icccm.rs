@@ -0,0 +1,238 @@
+//! `icccm` requests against `Xvfb` and the reference WM in `common::mini_wm`
+
+mod common;
+
+use common::mini_wm::{MiniWm, ICON_SIZE};
+use common::{create_window, Xvfb};
+use xcb_wm::icccm;
+
+#[test]
+fn text_properties() {
+    let xvfb = match Xvfb::start() {
+        Some(xvfb) => xvfb,
+        None => return,
+    };
+    let xcb_con = xvfb.connect();
+    let icccm_con = icccm::Connection::connect(&xcb_con);
+    let window = create_window(&xcb_con);
+
+    // Latin-1 text is sent as `STRING`
+    icccm_con
+        .send_and_check_request(&icccm::proto::SetWmName::with_text(
+            &icccm_con, window, "Grüße",
+        ))
+        .unwrap();
+    let name = icccm_con
+        .wait_for_reply(icccm_con.send_request(&icccm::proto::GetWmName::new(window)))
+        .unwrap();
+    assert_eq!(name.name, "Grüße");
+
+    // Everything else as `COMPOUND_TEXT`
+    icccm_con
+        .send_and_check_request(&icccm::proto::SetWmIconName::with_text(
+            &icccm_con,
+            window,
+            "Καλημέρα",
+        ))
+        .unwrap();
+    let icon_name = icccm_con
+        .wait_for_reply(icccm_con.send_request(&icccm::proto::GetWmIconName::new(window)))
+        .unwrap();
+    assert_eq!(icon_name.name, "Καλημέρα");
+
+    icccm_con
+        .send_and_check_request(&icccm::proto::SetWmClientMachine::new(
+            window,
+            xcb::x::ATOM_STRING,
+            b"localhost".to_vec(),
+        ))
+        .unwrap();
+    let machine = icccm_con
+        .wait_for_reply(icccm_con.send_request(&icccm::proto::GetWmClientMachine::new(window)))
+        .unwrap();
+    assert_eq!(machine.name, "localhost");
+
+    icccm_con
+        .send_and_check_request(&icccm::proto::SetWmClientMachine::with_text(
+            &icccm_con,
+            window,
+            "gästehaus",
+        ))
+        .unwrap();
+    let machine = icccm_con
+        .wait_for_reply(icccm_con.send_request(&icccm::proto::GetWmClientMachine::new(window)))
+        .unwrap();
+    assert_eq!(machine.name, "gästehaus");
+}
+
+#[test]
+fn string_properties() {
+    let xvfb = match Xvfb::start() {
+        Some(xvfb) => xvfb,
+        None => return,
+    };
+    let xcb_con = xvfb.connect();
+    let icccm_con = icccm::Connection::connect(&xcb_con);
+    let window = create_window(&xcb_con);
+
+    icccm_con
+        .send_and_check_request(&icccm::proto::SetWmClass::new(window, "xterm", "XTerm"))
+        .unwrap();
+    let class = icccm_con
+        .wait_for_reply(icccm_con.send_request(&icccm::proto::GetWmClass::new(window)))
+        .unwrap();
+    assert_eq!(
+        (class.instance.as_str(), class.class.as_str()),
+        ("xterm", "XTerm")
+    );
+
+    icccm_con
+        .send_and_check_request(&icccm::proto::SetWmWindowRole::new(window, "browser"))
+        .unwrap();
+    let role = icccm_con
+        .wait_for_reply(icccm_con.send_request(&icccm::proto::GetWmWindowRole::new(window)))
+        .unwrap();
+    assert_eq!(role.role, "browser");
+
+    icccm_con
+        .send_and_check_request(&icccm::proto::SetSmClientId::new(window, "1a2b3c"))
+        .unwrap();
+    let client_id = icccm_con
+        .wait_for_reply(icccm_con.send_request(&icccm::proto::GetSmClientId::new(window)))
+        .unwrap();
+    assert_eq!(client_id.client_id, "1a2b3c");
+
+    icccm_con
+        .send_and_check_request(&icccm::proto::SetWmCommand::new(
+            window,
+            vec!["xterm", "-e", "top"],
+        ))
+        .unwrap();
+    let command = icccm_con
+        .wait_for_reply(icccm_con.send_request(&icccm::proto::GetWmCommand::new(window)))
+        .unwrap();
+    assert_eq!(command.argv, vec!["xterm", "-e", "top"]);
+
+    icccm_con
+        .send_and_check_request(&icccm::proto::SetWmLocaleName::new(window, "de_AT.UTF-8"))
+        .unwrap();
+    let locale = icccm_con
+        .wait_for_reply(icccm_con.send_request(&icccm::proto::GetWmLocaleName::new(window)))
+        .unwrap();
+    assert_eq!(locale.locale, "de_AT.UTF-8");
+}
+
+#[test]
+fn window_properties() {
+    let xvfb = match Xvfb::start() {
+        Some(xvfb) => xvfb,
+        None => return,
+    };
+    let xcb_con = xvfb.connect();
+    let icccm_con = icccm::Connection::connect(&xcb_con);
+    let window = create_window(&xcb_con);
+    let leader = create_window(&xcb_con);
+
+    icccm_con
+        .send_and_check_request(&icccm::proto::SetWmTransientFor::new(window, leader))
+        .unwrap();
+    let transient_for = icccm_con
+        .wait_for_reply(icccm_con.send_request(&icccm::proto::GetWmTransientFor::new(window)))
+        .unwrap();
+    assert_eq!(transient_for.window, leader);
+
+    icccm_con
+        .send_and_check_request(&icccm::proto::SetWmClientLeader::new(window, leader))
+        .unwrap();
+    let client_leader = icccm_con
+        .wait_for_reply(icccm_con.send_request(&icccm::proto::GetWmClientLeader::new(window)))
+        .unwrap();
+    assert_eq!(client_leader.window, leader);
+
+    icccm_con
+        .send_and_check_request(&icccm::proto::SetWmColorMapWindows::new(
+            window,
+            vec![leader, window],
+        ))
+        .unwrap();
+    let colormap_windows = icccm_con
+        .wait_for_reply(icccm_con.send_request(&icccm::proto::GetWmColorMapWindows::new(window)))
+        .unwrap();
+    assert_eq!(colormap_windows.windows, vec![leader, window]);
+
+    // A window without the property
+    let missing = icccm_con
+        .wait_for_optional_reply(
+            icccm_con.send_request(&icccm::proto::GetWmTransientFor::new(leader)),
+        )
+        .unwrap();
+    assert!(missing.is_none());
+}
+
+#[test]
+fn hints() {
+    let xvfb = match Xvfb::start() {
+        Some(xvfb) => xvfb,
+        None => return,
+    };
+    let xcb_con = xvfb.connect();
+    let icccm_con = icccm::Connection::connect(&xcb_con);
+    let window = create_window(&xcb_con);
+    let group = create_window(&xcb_con);
+
+    let mut size_hints = icccm::proto::WmSizeHints::default();
+    size_hints.size(true, 640, 480);
+    size_hints.min_size(100, 50);
+    size_hints.resize_inc(8, 16);
+    size_hints.win_gravity(xcb::x::Gravity::Center);
+    icccm_con
+        .send_and_check_request(&icccm::proto::SetWmNormalHints::new(
+            window,
+            &mut size_hints,
+        ))
+        .unwrap();
+    let normal_hints = icccm_con
+        .wait_for_reply(icccm_con.send_request(&icccm::proto::GetWmNormalHints::new(window)))
+        .unwrap();
+    assert_eq!(normal_hints.size_hints, size_hints);
+
+    let mut hints = icccm::proto::WmHints::default();
+    hints.input(false);
+    hints.initial_state(icccm::proto::WmInitialState::Iconic);
+    hints.window_group(group);
+    hints.toggle_urgent();
+    icccm_con
+        .send_and_check_request(&icccm::proto::SetWmHints::new(window, &mut hints))
+        .unwrap();
+    let wm_hints = icccm_con
+        .wait_for_reply(icccm_con.send_request(&icccm::proto::GetWmHints::new(window)))
+        .unwrap();
+    assert_eq!(wm_hints.size_hints, hints);
+}
+
+#[test]
+fn icon_size_of_the_wm() {
+    let xvfb = match Xvfb::start() {
+        Some(xvfb) => xvfb,
+        None => return,
+    };
+    let _wm = MiniWm::start(&xvfb);
+    let xcb_con = xvfb.connect();
+    let icccm_con = icccm::Connection::connect(&xcb_con);
+
+    let icon_size = icccm_con
+        .wait_for_reply(icccm_con.send_request(&icccm::proto::GetWmIconSize))
+        .unwrap();
+    let (min, max) = ICON_SIZE;
+    assert_eq!(icon_size.closest_size(36, 36), Some((32, 32)));
+    assert_eq!(icon_size.closest_size(0, 1000), Some((min, max)));
+
+    let sizes = [icccm::proto::IconSize::new((48, 48), (48, 48), (0, 0))];
+    icccm_con
+        .send_and_check_request(&icccm::proto::SetWmIconSize::new(&sizes))
+        .unwrap();
+    let icon_size = icccm_con
+        .wait_for_reply(icccm_con.send_request(&icccm::proto::GetWmIconSize))
+        .unwrap();
+    assert_eq!(icon_size.sizes, sizes);
+}
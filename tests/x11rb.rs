@@ -0,0 +1,78 @@
+//! `ewmh` and `icccm` requests over `x11rb`, against `Xvfb` and the reference WM in
+//! `common::mini_wm`
+
+mod common;
+
+use common::mini_wm::{MiniWm, DESKTOP_NAMES};
+use common::{create_window, eventually, Xvfb};
+use xcb::Xid;
+use xcb_wm::backend::X11rbBackend;
+use xcb_wm::{ewmh, icccm, Error};
+
+fn connect(xvfb: &Xvfb) -> x11rb::rust_connection::RustConnection {
+    x11rb::connect(Some(xvfb.display()))
+        .expect("cannot connect to Xvfb")
+        .0
+}
+
+#[test]
+fn ewmh_over_x11rb() {
+    let xvfb = match Xvfb::start() {
+        Some(xvfb) => xvfb,
+        None => return,
+    };
+    let wm = MiniWm::start(&xvfb);
+    let x11rb_con = connect(&xvfb);
+    let backend = X11rbBackend::new(&x11rb_con);
+    let ewmh_con = ewmh::Connection::try_connect(&backend, 0).unwrap();
+
+    let (check, names) = ewmh_con.wait_for_batch(ewmh_con.send_batch(&(
+        ewmh::proto::GetSupportingWmCheck,
+        ewmh::proto::GetDesktopNames,
+    )));
+    assert_eq!(check.unwrap().window, wm.check_window());
+    assert_eq!(names.unwrap().names, DESKTOP_NAMES);
+
+    ewmh_con
+        .send_and_check_request(&ewmh::proto::SendCurrentDesktop::new(&ewmh_con, 1).unwrap())
+        .unwrap();
+    eventually("the desktop to change", || {
+        let current = ewmh_con
+            .wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetCurrentDesktop))
+            .unwrap();
+        Some(()).filter(|_| current.desktop == 1)
+    });
+
+    assert!(matches!(
+        ewmh_con.wait_for_reply(ewmh_con.send_request(&ewmh::proto::GetSupported)),
+        Ok(supported) if !supported.atoms.is_empty()
+    ));
+}
+
+#[test]
+fn icccm_over_x11rb() {
+    let xvfb = match Xvfb::start() {
+        Some(xvfb) => xvfb,
+        None => return,
+    };
+    let window = create_window(&xvfb.connect());
+    let x11rb_con = connect(&xvfb);
+    let backend = X11rbBackend::new(&x11rb_con);
+    let icccm_con = icccm::Connection::try_connect(&backend, 0).unwrap();
+
+    icccm_con
+        .send_and_check_request(&icccm::proto::SetWmName::with_text(
+            &icccm_con, window, "x11rb",
+        ))
+        .unwrap();
+    let name = icccm_con
+        .wait_for_reply(icccm_con.send_request(&icccm::proto::GetWmName::new(window)))
+        .unwrap();
+    assert_eq!(name.name, "x11rb");
+
+    let missing = xcb::XidNew::new(window.resource_id() + 1000);
+    assert!(matches!(
+        icccm_con.wait_for_reply(icccm_con.send_request(&icccm::proto::GetWmName::new(missing))),
+        Err(Error::Backend(_))
+    ));
+}